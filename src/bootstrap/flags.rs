@@ -63,6 +63,9 @@ pub enum Subcommand {
         rustc_args: Vec<String>,
         fail_fast: bool,
         doc_tests: bool,
+        rerun_failed: bool,
+        slowest_first: bool,
+        diff_last_run: bool,
     },
     Bench {
         paths: Vec<PathBuf>,
@@ -172,6 +175,21 @@ To learn more about a subcommand, run `./x.py <subcommand> -h`");
                     "ARGS",
                 );
                 opts.optflag("", "doc", "run doc tests");
+                opts.optflag(
+                    "",
+                    "rerun-failed",
+                    "only run the compiletest tests that failed in the last run",
+                );
+                opts.optflag(
+                    "",
+                    "slowest-first",
+                    "run the compiletest tests that were slowest in the last run first",
+                );
+                opts.optflag(
+                    "",
+                    "diff-last-run",
+                    "print the compiletest tests that started or stopped failing in this run",
+                );
             },
             "bench" => { opts.optmulti("", "test-args", "extra arguments", "ARGS"); },
             "clean" => { opts.optflag("", "all", "clean all build artifacts"); },
@@ -325,6 +343,9 @@ Arguments:
                     rustc_args: matches.opt_strs("rustc-args"),
                     fail_fast: !matches.opt_present("no-fail-fast"),
                     doc_tests: matches.opt_present("doc"),
+                    rerun_failed: matches.opt_present("rerun-failed"),
+                    slowest_first: matches.opt_present("slowest-first"),
+                    diff_last_run: matches.opt_present("diff-last-run"),
                 }
             }
             "bench" => {
@@ -417,6 +438,27 @@ impl Subcommand {
             _ => false,
         }
     }
+
+    pub fn rerun_failed(&self) -> bool {
+        match *self {
+            Subcommand::Test { rerun_failed, .. } => rerun_failed,
+            _ => false,
+        }
+    }
+
+    pub fn slowest_first(&self) -> bool {
+        match *self {
+            Subcommand::Test { slowest_first, .. } => slowest_first,
+            _ => false,
+        }
+    }
+
+    pub fn diff_last_run(&self) -> bool {
+        match *self {
+            Subcommand::Test { diff_last_run, .. } => diff_last_run,
+            _ => false,
+        }
+    }
 }
 
 fn split(s: Vec<String>) -> Vec<String> {
@@ -913,6 +913,16 @@ impl Step for Compiletest {
 
         cmd.args(&build.config.cmd.test_args());
 
+        if build.config.cmd.rerun_failed() {
+            cmd.arg("--rerun-failed");
+        }
+        if build.config.cmd.slowest_first() {
+            cmd.arg("--slowest-first");
+        }
+        if build.config.cmd.diff_last_run() {
+            cmd.arg("--diff-last-run");
+        }
+
         if build.is_verbose() {
             cmd.arg("--verbose");
         }
@@ -266,6 +266,30 @@ declare_lint! {
     "detects name collision with an existing but unstable method"
 }
 
+declare_lint! {
+    pub INTRA_DOC_LINK_RESOLUTION_FAILURE,
+    Warn,
+    "intra-doc links that name no item, or more than one"
+}
+
+declare_lint! {
+    pub PRIVATE_INTRA_DOC_LINKS,
+    Warn,
+    "intra-doc links from the documentation of a public item to a private one"
+}
+
+declare_lint! {
+    pub BARE_URLS,
+    Warn,
+    "URLs in documentation that are not links"
+}
+
+declare_lint! {
+    pub INVALID_RUST_CODEBLOCKS,
+    Warn,
+    "Rust code blocks in documentation that cannot be parsed"
+}
+
 /// Does nothing as a lint pass, but registers some `Lint`s
 /// which are used by other parts of the compiler.
 #[derive(Copy, Clone)]
@@ -315,6 +339,10 @@ impl LintPass for HardwiredLints {
             ELIDED_LIFETIME_IN_PATH,
             BARE_TRAIT_OBJECT,
             UNSTABLE_NAME_COLLISION,
+            INTRA_DOC_LINK_RESOLUTION_FAILURE,
+            PRIVATE_INTRA_DOC_LINKS,
+            BARE_URLS,
+            INVALID_RUST_CODEBLOCKS,
         )
     }
 }
@@ -28,6 +28,8 @@ use util::nodemap::FxHashMap;
 pub struct LintLevelSets {
     list: Vec<LintSet>,
     lint_cap: Level,
+    // The lints with a cap of their own, which applies instead of `lint_cap`.
+    lint_caps: FxHashMap<LintId, Level>,
 }
 
 enum LintSet {
@@ -48,6 +50,7 @@ impl LintLevelSets {
         let mut me = LintLevelSets {
             list: Vec::new(),
             lint_cap: Level::Forbid,
+            lint_caps: FxHashMap(),
         };
         me.process_command_line(sess);
         return me
@@ -62,20 +65,28 @@ impl LintLevelSets {
         let mut specs = FxHashMap();
         self.lint_cap = sess.opts.lint_cap.unwrap_or(Level::Forbid);
 
+        for &(ref lint_name, cap) in &sess.opts.lint_caps {
+            if let Ok(ids) = store.find_lints(&lint_name) {
+                for id in ids {
+                    self.lint_caps.insert(id, cap);
+                }
+            }
+        }
+
         for &(ref lint_name, level) in &sess.opts.lint_opts {
             store.check_lint_name_cmdline(sess, &lint_name, level);
 
-            // If the cap is less than this specified level, e.g. if we've got
-            // `--cap-lints allow` but we've also got `-D foo` then we ignore
-            // this specification as the lint cap will set it to allow anyway.
-            let level = cmp::min(level, self.lint_cap);
-
             let lint_flag_val = Symbol::intern(lint_name);
             let ids = match store.find_lints(&lint_name) {
                 Ok(ids) => ids,
                 Err(_) => continue, // errors handled in check_lint_name_cmdline above
             };
             for id in ids {
+                // If the cap is less than this specified level, e.g. if we've got
+                // `--cap-lints allow` but we've also got `-D foo` then we ignore
+                // this specification as the lint cap will set it to allow anyway.
+                let level = cmp::min(level, self.cap_of(id));
+
                 let src = LintSource::CommandLine(lint_flag_val);
                 specs.insert(id, (level, src));
             }
@@ -116,11 +127,15 @@ impl LintLevelSets {
         }
 
         // Ensure that we never exceed the `--cap-lints` argument.
-        level = cmp::min(level, self.lint_cap);
+        level = cmp::min(level, self.cap_of(LintId::of(lint)));
 
         return (level, src)
     }
 
+    fn cap_of(&self, id: LintId) -> Level {
+        self.lint_caps.get(&id).cloned().unwrap_or(self.lint_cap)
+    }
+
     fn get_lint_id_level(&self,
                          id: LintId,
                          mut idx: u32,
@@ -409,9 +424,11 @@ impl<'a> HashStable<StableHashingContext<'a>> for LintLevelMap {
         let LintLevelSets {
             ref list,
             lint_cap,
+            ref lint_caps,
         } = *sets;
 
         lint_cap.hash_stable(hcx, hasher);
+        lint_caps.hash_stable(hcx, hasher);
 
         hcx.while_hashing_spans(true, |hcx| {
             list.len().hash_stable(hcx, hasher);
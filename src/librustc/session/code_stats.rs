@@ -12,8 +12,10 @@ use ty::AdtKind;
 use ty::layout::{Align, Size};
 
 use rustc_data_structures::fx::{FxHashSet};
+use serialize::json::Json;
 
 use std::cmp::{self, Ordering};
+use std::collections::BTreeMap;
 
 #[derive(Clone, PartialEq, Eq, Hash, Debug)]
 pub struct VariantInfo {
@@ -21,6 +23,8 @@ pub struct VariantInfo {
     pub kind: SizeKind,
     pub size: u64,
     pub align: u64,
+    /// The discriminant of the variant, for enums.
+    pub discr: Option<String>,
     pub fields: Vec<FieldInfo>,
 }
 
@@ -56,6 +60,23 @@ pub enum DataTypeKind {
     Closure,
 }
 
+#[derive(Copy, Clone, PartialEq, Eq, Hash, Debug)]
+pub enum DiscrKind {
+    /// The discriminant is stored in a field of its own.
+    Tag,
+    /// The discriminant is stored in values that are invalid for a field of
+    /// the variant at index `dataful_variant`, the only one to have data
+    /// there.
+    Niche { dataful_variant: usize },
+}
+
+#[derive(Clone, PartialEq, Eq, Hash, Debug)]
+pub struct DiscrInfo {
+    pub kind: DiscrKind,
+    pub offset: u64,
+    pub size: u64,
+}
+
 #[derive(PartialEq, Eq, Hash, Debug)]
 pub struct TypeSizeInfo {
     pub kind: DataTypeKind,
@@ -63,6 +84,8 @@ pub struct TypeSizeInfo {
     pub align: u64,
     pub overall_size: u64,
     pub opt_discr_size: Option<u64>,
+    /// Where the discriminant is stored, for enums with several variants.
+    pub discr: Option<DiscrInfo>,
     pub variants: Vec<VariantInfo>,
 }
 
@@ -80,6 +103,7 @@ impl CodeStats {
                                          align: Align,
                                          overall_size: Size,
                                          opt_discr_size: Option<Size>,
+                                         discr: Option<DiscrInfo>,
                                          variants: Vec<VariantInfo>) {
         let info = TypeSizeInfo {
             kind,
@@ -87,12 +111,13 @@ impl CodeStats {
             align: align.abi(),
             overall_size: overall_size.bytes(),
             opt_discr_size: opt_discr_size.map(|s| s.bytes()),
+            discr,
             variants,
         };
         self.type_sizes.insert(info);
     }
 
-    pub fn print_type_sizes(&self) {
+    fn sorted_type_sizes(&self) -> Vec<&TypeSizeInfo> {
         let mut sorted: Vec<_> = self.type_sizes.iter().collect();
 
         // Primary sort: large-to-small.
@@ -104,6 +129,11 @@ impl CodeStats {
                 other => other,
             }
         });
+        sorted
+    }
+
+    pub fn print_type_sizes(&self) {
+        let sorted = self.sorted_type_sizes();
 
         for info in &sorted {
             println!("print-type-size type: `{}`: {} bytes, alignment: {} bytes",
@@ -129,7 +159,7 @@ impl CodeStats {
                 DataTypeKind::Enum | DataTypeKind::Union => false,
             };
             for (i, variant_info) in info.variants.iter().enumerate() {
-                let VariantInfo { ref name, kind: _, align: _, size, ref fields } = *variant_info;
+                let VariantInfo { ref name, size, ref fields, .. } = *variant_info;
                 let indent = if !struct_like {
                     let name = match name.as_ref() {
                         Some(name) => format!("{}", name),
@@ -186,4 +216,104 @@ impl CodeStats {
             }
         }
     }
+
+    /// Prints the same information as `print_type_sizes`, as a JSON array
+    /// with an object for each type.
+    pub fn print_type_sizes_json(&self) {
+        let types = self.sorted_type_sizes().into_iter().map(|info| {
+            let mut ty = BTreeMap::new();
+            ty.insert("type".to_string(), Json::String(info.type_description.clone()));
+            ty.insert("kind".to_string(), Json::String(match info.kind {
+                DataTypeKind::Struct => "struct",
+                DataTypeKind::Union => "union",
+                DataTypeKind::Enum => "enum",
+                DataTypeKind::Closure => "closure",
+            }.to_string()));
+            ty.insert("size".to_string(), Json::U64(info.overall_size));
+            ty.insert("align".to_string(), Json::U64(info.align));
+
+            ty.insert("discriminant".to_string(), match info.discr {
+                Some(ref discr) => {
+                    let mut json = BTreeMap::new();
+                    let kind = match discr.kind {
+                        DiscrKind::Tag => "tag",
+                        DiscrKind::Niche { dataful_variant } => {
+                            json.insert("dataful_variant".to_string(),
+                                        Json::U64(dataful_variant as u64));
+                            "niche"
+                        }
+                    };
+                    json.insert("kind".to_string(), Json::String(kind.to_string()));
+                    json.insert("offset".to_string(), Json::U64(discr.offset));
+                    json.insert("size".to_string(), Json::U64(discr.size));
+                    Json::Object(json)
+                }
+                None => Json::Null,
+            });
+
+            // As in `print_type_sizes`, the fields of the variants of an enum
+            // start after its tag.
+            let start = info.opt_discr_size.unwrap_or(0);
+            let mut max_variant_size = start;
+            let variants = info.variants.iter().map(|variant| {
+                max_variant_size = cmp::max(max_variant_size, variant.size);
+                variant_json(variant, start)
+            }).collect();
+            ty.insert("variants".to_string(), Json::Array(variants));
+            ty.insert("end_padding".to_string(),
+                      Json::U64(info.overall_size.saturating_sub(max_variant_size)));
+
+            Json::Object(ty)
+        }).collect();
+
+        println!("{}", Json::Array(types));
+    }
+}
+
+/// The JSON for `variant`, whose fields start at `start`, listing the
+/// padding between them.
+fn variant_json(variant: &VariantInfo, start: u64) -> Json {
+    let mut json = BTreeMap::new();
+    json.insert("name".to_string(), match variant.name {
+        Some(ref name) => Json::String(name.clone()),
+        None => Json::Null,
+    });
+    json.insert("size".to_string(), Json::U64(variant.size));
+    json.insert("align".to_string(), Json::U64(variant.align));
+    json.insert("exact_size".to_string(), Json::Boolean(variant.kind == SizeKind::Exact));
+    json.insert("discriminant".to_string(), match variant.discr {
+        // Discriminants can be `u128`s, which do not fit in a JSON number.
+        Some(ref discr) => match (discr.parse::<i64>(), discr.parse::<u64>()) {
+            (Ok(value), _) => Json::I64(value),
+            (_, Ok(value)) => Json::U64(value),
+            _ => Json::String(discr.clone()),
+        },
+        None => Json::Null,
+    });
+
+    let mut fields = variant.fields.clone();
+    fields.sort_by_key(|f| f.offset);
+
+    let mut min_offset = start;
+    let mut padding = Vec::new();
+    let fields = fields.iter().map(|field| {
+        if field.offset > min_offset {
+            let mut hole = BTreeMap::new();
+            hole.insert("offset".to_string(), Json::U64(min_offset));
+            hole.insert("size".to_string(), Json::U64(field.offset - min_offset));
+            padding.push(Json::Object(hole));
+        }
+        min_offset = cmp::max(min_offset, field.offset + field.size);
+
+        let mut json = BTreeMap::new();
+        json.insert("name".to_string(), Json::String(field.name.clone()));
+        json.insert("offset".to_string(), Json::U64(field.offset));
+        json.insert("size".to_string(), Json::U64(field.size));
+        json.insert("align".to_string(), Json::U64(field.align));
+        Json::Object(json)
+    }).collect();
+    json.insert("fields".to_string(), Json::Array(fields));
+    json.insert("padding".to_string(), Json::Array(padding));
+
+    Json::Object(json)
 }
@@ -42,7 +42,7 @@ use std::collections::btree_map::Iter as BTreeMapIter;
 use std::collections::btree_map::Keys as BTreeMapKeysIter;
 use std::collections::btree_map::Values as BTreeMapValuesIter;
 
-use std::{fmt, str};
+use std::{env, fmt, str};
 use std::hash::Hasher;
 use std::collections::hash_map::DefaultHasher;
 use std::collections::HashSet;
@@ -361,6 +361,8 @@ top_level_options!(
         debuginfo: DebugInfoLevel [TRACKED],
         lint_opts: Vec<(String, lint::Level)> [TRACKED],
         lint_cap: Option<lint::Level> [TRACKED],
+        // Caps for single lints, which apply to them instead of `lint_cap`.
+        lint_caps: Vec<(String, lint::Level)> [TRACKED],
         describe_lints: bool [UNTRACKED],
         output_types: OutputTypes [TRACKED],
         search_paths: SearchPaths [UNTRACKED],
@@ -563,6 +565,7 @@ pub fn basic_options() -> Options {
         debuginfo: NoDebugInfo,
         lint_opts: Vec::new(),
         lint_cap: None,
+        lint_caps: Vec::new(),
         describe_lints: false,
         output_types: OutputTypes(BTreeMap::new()),
         search_paths: SearchPaths::new(),
@@ -602,7 +605,16 @@ impl Options {
     }
 
     pub fn file_path_mapping(&self) -> FilePathMapping {
-        FilePathMapping::new(self.remap_path_prefix.clone())
+        let mapping = FilePathMapping::new(self.remap_path_prefix.clone());
+        match self.debugging_opts.incremental_path_root {
+            Some(ref root) => {
+                // An invalid working directory is reported when the session
+                // is built.
+                let working_dir = env::current_dir().unwrap_or_default();
+                mapping.with_stable_root(PathBuf::from(root), working_dir)
+            }
+            None => mapping,
+        }
     }
 
     /// True if there will be an output file generated
@@ -1095,6 +1107,15 @@ options! {DebuggingOptions, DebuggingSetter, basic_debugging_options,
         "when using two-phase-borrows, allow two phases even for non-autoref `&mut` borrows"),
     time_passes: bool = (false, parse_bool, [UNTRACKED],
         "measure time of each rustc pass"),
+    time_items: bool = (false, parse_bool, [UNTRACKED_WITH_WARNING(true,
+        "The output of `-Z time-items` will only reflect timings of items \
+         that are checked or translated again when used with incremental compilation")],
+        "measure the time spent type checking, borrow checking, optimizing and \
+         translating each item, and print the slowest ones"),
+    time_items_limit: usize = (20, parse_uint, [UNTRACKED],
+        "the number of items printed by `-Z time-items` (default: 20)"),
+    time_items_json: bool = (false, parse_bool, [UNTRACKED],
+        "print every item measured by `-Z time-items` as JSON instead of a table"),
     count_llvm_insns: bool = (false, parse_bool,
         [UNTRACKED_WITH_WARNING(true,
         "The output generated by `-Z count_llvm_insns` might not be reliable \
@@ -1172,6 +1193,19 @@ options! {DebuggingOptions, DebuggingSetter, basic_debugging_options,
         "verify incr. comp. hashes of green query instances"),
     incremental_ignore_spans: bool = (false, parse_bool, [UNTRACKED],
         "ignore spans during ICH computation -- used for testing"),
+    incremental_cache_size_limit: Option<usize> = (None, parse_opt_uint, [UNTRACKED],
+        "delete the least recently used sessions in the incremental compilation directory \
+         until it is no larger than this many megabytes"),
+    incremental_cache_max_age: Option<usize> = (None, parse_opt_uint, [UNTRACKED],
+        "delete the sessions in the incremental compilation directory that were last used \
+         more than this many days ago"),
+    incremental_cache_report: bool = (false, parse_bool, [UNTRACKED],
+        "print the sessions stored in the incremental compilation directory and which of \
+         them would be deleted, without compiling anything"),
+    incremental_path_root: Option<String> = (None, parse_opt_string, [UNTRACKED],
+        "name the source files under this directory by their path relative to it, in the \
+         incremental compilation cache and in the output, so that the cache can be used from \
+         another directory"),
     dump_dep_graph: bool = (false, parse_bool, [UNTRACKED],
           "dump the dependency graph to $RUST_DEP_GRAPH (default: /tmp/dep_graph.gv)"),
     query_dep_graph: bool = (false, parse_bool, [UNTRACKED],
@@ -1180,6 +1214,12 @@ options! {DebuggingOptions, DebuggingSetter, basic_debugging_options,
           "trace and profile the queries of the incremental compilation framework"),
     profile_queries_and_keys: bool = (false, parse_bool, [UNTRACKED],
           "trace and profile the queries and keys of the incremental compilation framework"),
+    daemon: Option<String> = (None, parse_opt_string, [UNTRACKED],
+          "run as a daemon compiling the requests of the clients that connect to the given \
+           Unix socket (see `RUSTC_DAEMON_SOCKET`)"),
+    self_profile: Option<String> = (None, parse_opt_string, [UNTRACKED],
+          "record the queries, incremental loads, passes and LLVM work of the compilation, \
+           and write them to the given file as a Chrome trace"),
     no_analysis: bool = (false, parse_bool, [UNTRACKED],
           "parse and expand the source, but run no analysis"),
     extra_plugins: Vec<String> = (Vec::new(), parse_list, [TRACKED],
@@ -1202,8 +1242,16 @@ options! {DebuggingOptions, DebuggingSetter, basic_debugging_options,
           "show spans for compiler debugging (expr|pat|ty)"),
     print_type_sizes: bool = (false, parse_bool, [UNTRACKED],
           "print layout information for each type encountered"),
+    print_type_sizes_json: bool = (false, parse_bool, [UNTRACKED],
+          "print the layouts of `-Z print-type-sizes` as JSON, with the offset of every field \
+           and the discriminant of every variant"),
     print_trans_items: Option<String> = (None, parse_opt_string, [UNTRACKED],
           "print the result of the translation item collection pass"),
+    print_mono_bloat: bool = (false, parse_bool, [UNTRACKED_WITH_WARNING(true,
+        "The LLVM instruction counts of `-Z print-mono-bloat` will only cover \
+         re-translated modules when used with incremental compilation")],
+          "print how many instances of each item are translated, with the size of \
+           their MIR and LLVM IR"),
     mir_opt_level: usize = (1, parse_uint, [TRACKED],
           "set the MIR optimization level (0-3, default: 1)"),
     mutable_noalias: bool = (false, parse_bool, [UNTRACKED],
@@ -2142,6 +2190,7 @@ pub fn build_session_options_and_crate_config(
             debuginfo,
             lint_opts,
             lint_cap,
+            lint_caps: Vec::new(),
             describe_lints,
             output_types: OutputTypes(output_types),
             search_paths,
@@ -2960,6 +3009,10 @@ mod tests {
         assert_eq!(reference.dep_tracking_hash(), opts.dep_tracking_hash());
         opts.debugging_opts.time_passes = true;
         assert_eq!(reference.dep_tracking_hash(), opts.dep_tracking_hash());
+        opts.debugging_opts.time_items_limit = 5;
+        assert_eq!(reference.dep_tracking_hash(), opts.dep_tracking_hash());
+        opts.debugging_opts.time_items_json = true;
+        assert_eq!(reference.dep_tracking_hash(), opts.dep_tracking_hash());
         opts.debugging_opts.count_llvm_insns = true;
         assert_eq!(reference.dep_tracking_hash(), opts.dep_tracking_hash());
         opts.debugging_opts.time_llvm_passes = true;
@@ -3002,6 +3055,14 @@ mod tests {
         assert_eq!(reference.dep_tracking_hash(), opts.dep_tracking_hash());
         opts.debugging_opts.query_dep_graph = true;
         assert_eq!(reference.dep_tracking_hash(), opts.dep_tracking_hash());
+        opts.debugging_opts.incremental_cache_size_limit = Some(1);
+        assert_eq!(reference.dep_tracking_hash(), opts.dep_tracking_hash());
+        opts.debugging_opts.incremental_cache_max_age = Some(1);
+        assert_eq!(reference.dep_tracking_hash(), opts.dep_tracking_hash());
+        opts.debugging_opts.incremental_cache_report = true;
+        assert_eq!(reference.dep_tracking_hash(), opts.dep_tracking_hash());
+        opts.debugging_opts.incremental_path_root = Some(String::from("abc"));
+        assert_eq!(reference.dep_tracking_hash(), opts.dep_tracking_hash());
         opts.debugging_opts.no_analysis = true;
         assert_eq!(reference.dep_tracking_hash(), opts.dep_tracking_hash());
         opts.debugging_opts.unstable_options = true;
@@ -3014,6 +3075,10 @@ mod tests {
         assert_eq!(reference.dep_tracking_hash(), opts.dep_tracking_hash());
         opts.debugging_opts.print_trans_items = Some(String::from("abc"));
         assert_eq!(reference.dep_tracking_hash(), opts.dep_tracking_hash());
+        opts.debugging_opts.print_type_sizes_json = true;
+        assert_eq!(reference.dep_tracking_hash(), opts.dep_tracking_hash());
+        opts.debugging_opts.print_mono_bloat = true;
+        assert_eq!(reference.dep_tracking_hash(), opts.dep_tracking_hash());
         opts.debugging_opts.dump_mir = Some(String::from("abc"));
         assert_eq!(reference.dep_tracking_hash(), opts.dep_tracking_hash());
         opts.debugging_opts.dump_mir_dir = String::from("abc");
@@ -0,0 +1,224 @@
+// Copyright 2018 The Rust Project Developers. See the COPYRIGHT
+// file at the top-level directory of this distribution and at
+// http://rust-lang.org/COPYRIGHT.
+//
+// Licensed under the Apache License, Version 2.0 <LICENSE-APACHE or
+// http://www.apache.org/licenses/LICENSE-2.0> or the MIT license
+// <LICENSE-MIT or http://opensource.org/licenses/MIT>, at your
+// option. This file may not be copied, modified, or distributed
+// except according to those terms.
+
+
+//! The measurements of `-Z time-items`, which tell how long the main
+//! activities of the compiler took for each item of the crate, and for each
+//! instance of the generic ones.
+
+use util::common::duration_to_secs_str;
+use util::nodemap::FxHashMap;
+
+use serialize::json::Json;
+
+use std::cmp::Ordering;
+use std::collections::BTreeMap;
+use std::time::{Duration, Instant};
+
+#[derive(Copy, Clone, PartialEq, Eq, Debug)]
+pub enum ItemActivity {
+    TypeCheck,
+    /// Both the AST and the MIR borrow checker.
+    Borrowck,
+    MirOptimization,
+    /// The translation of a monomorphized instance to LLVM IR. The LLVM
+    /// passes run on whole codegen units, so they cannot be attributed to
+    /// a single item.
+    Codegen,
+}
+
+const ACTIVITIES: [ItemActivity; 4] = [
+    ItemActivity::TypeCheck,
+    ItemActivity::Borrowck,
+    ItemActivity::MirOptimization,
+    ItemActivity::Codegen,
+];
+
+impl ItemActivity {
+    fn name(self) -> &'static str {
+        match self {
+            ItemActivity::TypeCheck => "typeck",
+            ItemActivity::Borrowck => "borrowck",
+            ItemActivity::MirOptimization => "mir-opt",
+            ItemActivity::Codegen => "codegen",
+        }
+    }
+}
+
+#[derive(Default)]
+struct ItemTime {
+    /// The time of each activity, indexed by `ItemActivity`.
+    times: [Duration; 4],
+    /// The codegen time of each instance of the item.
+    instances: FxHashMap<String, Duration>,
+}
+
+impl ItemTime {
+    fn total(&self) -> Duration {
+        self.times.iter().fold(Duration::new(0, 0), |total, &time| total + time)
+    }
+}
+
+pub struct ItemTimes {
+    items: FxHashMap<String, ItemTime>,
+    /// The measurements in progress, innermost last, with the time they
+    /// started and the time taken by the measurements nested in them.
+    stack: Vec<(Instant, Duration)>,
+}
+
+impl ItemTimes {
+    pub fn new() -> Self {
+        ItemTimes {
+            items: FxHashMap(),
+            stack: Vec::new(),
+        }
+    }
+
+    pub fn start(&mut self) {
+        self.start_at(Instant::now());
+    }
+
+    fn start_at(&mut self, now: Instant) {
+        self.stack.push((now, Duration::new(0, 0)));
+    }
+
+    /// Ends the innermost measurement, and charges `item` for it. The time of
+    /// the measurements nested in it is left out, so that checking an item
+    /// which needs another one checked first, say to evaluate a constant,
+    /// does not make both look expensive.
+    pub fn finish(&mut self,
+                  activity: ItemActivity,
+                  item: String,
+                  instance: Option<String>) {
+        self.finish_at(activity, item, instance, Instant::now());
+    }
+
+    fn finish_at(&mut self,
+                 activity: ItemActivity,
+                 item: String,
+                 instance: Option<String>,
+                 now: Instant) {
+        let (start, nested) = self.stack.pop().expect("no item is being measured");
+        let elapsed = now.duration_since(start);
+        if let Some(&mut (_, ref mut parent_nested)) = self.stack.last_mut() {
+            *parent_nested += elapsed;
+        }
+        let time = elapsed.checked_sub(nested).unwrap_or(Duration::new(0, 0));
+
+        let entry = self.items.entry(item).or_insert_with(ItemTime::default);
+        entry.times[activity as usize] += time;
+        if let Some(instance) = instance {
+            *entry.instances.entry(instance).or_insert(Duration::new(0, 0)) += time;
+        }
+    }
+
+    /// The items, slowest first.
+    fn sorted(&self) -> Vec<(&String, &ItemTime)> {
+        let mut sorted: Vec<_> = self.items.iter().collect();
+        sorted.sort_by(|&(name1, time1), &(name2, time2)| {
+            match time2.total().cmp(&time1.total()) {
+                Ordering::Equal => name1.cmp(name2),
+                other => other,
+            }
+        });
+        sorted
+    }
+
+    /// Prints a table of the `limit` slowest items, with times in seconds.
+    pub fn print_report(&self, limit: usize) {
+        print!("time-items: {:>9}", "total");
+        for activity in &ACTIVITIES {
+            print!(" {:>9}", activity.name());
+        }
+        println!(" {:>9}  item", "instances");
+
+        for &(name, time) in self.sorted().iter().take(limit) {
+            print!("time-items: {:>9}", duration_to_secs_str(time.total()));
+            for &activity_time in &time.times {
+                print!(" {:>9}", duration_to_secs_str(activity_time));
+            }
+            println!(" {:>9}  {}", time.instances.len(), name);
+        }
+    }
+
+    /// Prints every item as JSON, with the codegen time of its instances.
+    pub fn print_json(&self) {
+        let items = self.sorted().into_iter().map(|(name, time)| {
+            let mut item = BTreeMap::new();
+            item.insert("item".to_string(), Json::String(name.clone()));
+            item.insert("total".to_string(), secs(time.total()));
+            for activity in &ACTIVITIES {
+                item.insert(activity.name().replace("-", "_"),
+                            secs(time.times[*activity as usize]));
+            }
+
+            let mut instances: Vec<_> = time.instances.iter().collect();
+            instances.sort_by(|&(name1, time1), &(name2, time2)| {
+                match time2.cmp(time1) {
+                    Ordering::Equal => name1.cmp(name2),
+                    other => other,
+                }
+            });
+            let instances = instances.into_iter().map(|(name, &time)| {
+                let mut instance = BTreeMap::new();
+                instance.insert("instance".to_string(), Json::String(name.clone()));
+                instance.insert("codegen".to_string(), secs(time));
+                Json::Object(instance)
+            }).collect();
+            item.insert("instances".to_string(), Json::Array(instances));
+
+            Json::Object(item)
+        }).collect();
+
+        println!("{}", Json::Array(items));
+    }
+}
+
+fn secs(time: Duration) -> Json {
+    Json::F64(time.as_secs() as f64 + time.subsec_nanos() as f64 / 1_000_000_000.0)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{ItemActivity, ItemTimes};
+    use std::time::{Duration, Instant};
+
+    fn ms(ms: u64) -> Duration {
+        Duration::from_millis(ms)
+    }
+
+    #[test]
+    fn nested_times_are_left_out_of_the_parent() {
+        let mut times = ItemTimes::new();
+        let t0 = Instant::now();
+        times.start_at(t0);
+        times.start_at(t0 + ms(10));
+        times.start_at(t0 + ms(15));
+        times.finish_at(ItemActivity::TypeCheck, "CONST".to_string(), None, t0 + ms(25));
+        times.finish_at(ItemActivity::TypeCheck, "callee".to_string(), None, t0 + ms(40));
+        times.start_at(t0 + ms(45));
+        times.finish_at(ItemActivity::Codegen, "callee".to_string(),
+                        Some("callee::<u8>".to_string()), t0 + ms(50));
+        times.finish_at(ItemActivity::TypeCheck, "caller".to_string(), None, t0 + ms(60));
+        assert!(times.stack.is_empty());
+
+        let typeck = ItemActivity::TypeCheck as usize;
+        let codegen = ItemActivity::Codegen as usize;
+        assert_eq!(times.items["CONST"].times[typeck], ms(10));
+        assert_eq!(times.items["callee"].times[typeck], ms(20));
+        assert_eq!(times.items["callee"].times[codegen], ms(5));
+        assert_eq!(times.items["callee"].instances["callee::<u8>"], ms(5));
+        assert_eq!(times.items["caller"].times[typeck], ms(25));
+        assert_eq!(times.items["callee"].total(), ms(25));
+
+        let sorted = times.sorted().into_iter().map(|(name, _)| &name[..]).collect::<Vec<_>>();
+        assert_eq!(sorted, ["callee", "caller", "CONST"]);
+    }
+}
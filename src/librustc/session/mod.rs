@@ -8,8 +8,9 @@
 // option. This file may not be copied, modified, or distributed
 // except according to those terms.
 
-pub use self::code_stats::{CodeStats, DataTypeKind, FieldInfo};
+pub use self::code_stats::{CodeStats, DataTypeKind, DiscrInfo, DiscrKind, FieldInfo};
 pub use self::code_stats::{SizeKind, TypeSizeInfo, VariantInfo};
+pub use self::item_times::{ItemActivity, ItemTimes};
 
 use hir::def_id::CrateNum;
 use ich::Fingerprint;
@@ -25,6 +26,7 @@ use ty::tls;
 use util::nodemap::{FxHashMap, FxHashSet};
 use util::common::{duration_to_secs_str, ErrorReported};
 use util::common::ProfileQueriesMsg;
+use util::profiling::SelfProfiler;
 
 use rustc_data_structures::sync::{Lrc, Lock};
 
@@ -52,11 +54,12 @@ use std::env;
 use std::fmt;
 use std::io::Write;
 use std::path::{Path, PathBuf};
-use std::sync::{Once, ONCE_INIT};
+use std::sync::{Arc, Once, ONCE_INIT};
 use std::time::Duration;
 use std::sync::mpsc;
 
 mod code_stats;
+mod item_times;
 pub mod config;
 pub mod filesearch;
 pub mod search_paths;
@@ -131,12 +134,19 @@ pub struct Session {
     /// Used by -Z profile-queries in util::common
     pub profile_channel: Lock<Option<mpsc::Sender<ProfileQueriesMsg>>>,
 
+    /// The profiler enabled by -Z self-profile, shared with the LLVM worker
+    /// threads
+    pub self_profiling: Option<Arc<SelfProfiler>>,
+
     /// Some measurements that are being gathered during compilation.
     pub perf_stats: PerfStats,
 
     /// Data about code being compiled, gathered during compilation.
     pub code_stats: RefCell<CodeStats>,
 
+    /// The time spent on each item, measured if -Z time-items is given.
+    pub item_times: RefCell<ItemTimes>,
+
     next_node_id: Cell<ast::NodeId>,
 
     /// If -zfuel=crate=n is specified, Some(crate).
@@ -519,6 +529,10 @@ impl Session {
     pub fn trans_stats(&self) -> bool {
         self.opts.debugging_opts.trans_stats
     }
+    /// Whether the LLVM instructions of each function are counted.
+    pub fn count_fn_insns(&self) -> bool {
+        self.trans_stats() || self.opts.debugging_opts.print_mono_bloat
+    }
     pub fn meta_stats(&self) -> bool {
         self.opts.debugging_opts.meta_stats
     }
@@ -833,6 +847,41 @@ impl Session {
         }
     }
 
+    /// Runs `f`, charging the time it takes for `activity` to the item
+    /// named by `item` if -Z time-items is given.
+    pub fn time_item<T, F, N>(&self, activity: ItemActivity, item: N, f: F) -> T
+        where F: FnOnce() -> T,
+              N: FnOnce() -> String,
+    {
+        if !self.opts.debugging_opts.time_items {
+            return f();
+        }
+
+        self.item_times.borrow_mut().start();
+        let rv = f();
+        // Naming the item can run queries, which may measure items themselves.
+        let item = item();
+        self.item_times.borrow_mut().finish(activity, item, None);
+        rv
+    }
+
+    /// Like `time_item`, for the codegen of an instance. `names` gives the
+    /// name of the item, then the name of the instance.
+    pub fn time_instance<T, F, N>(&self, names: N, f: F) -> T
+        where F: FnOnce() -> T,
+              N: FnOnce() -> (String, String),
+    {
+        if !self.opts.debugging_opts.time_items {
+            return f();
+        }
+
+        self.item_times.borrow_mut().start();
+        let rv = f();
+        let (item, instance) = names();
+        self.item_times.borrow_mut().finish(ItemActivity::Codegen, item, Some(instance));
+        rv
+    }
+
     pub fn print_perf_stats(&self) {
         println!(
             "Total time spent computing SVHs:               {}",
@@ -1121,6 +1170,12 @@ pub fn build_session_(
     };
     let working_dir = file_path_mapping.map_prefix(working_dir);
 
+    let self_profiling = if sopts.debugging_opts.self_profile.is_some() {
+        Some(Arc::new(SelfProfiler::new()))
+    } else {
+        None
+    };
+
     let sess = Session {
         target: target_cfg,
         host,
@@ -1155,6 +1210,7 @@ pub fn build_session_(
         incr_comp_session: RefCell::new(IncrCompSession::NotInitialized),
         ignored_attr_names: ich::compute_ignored_attr_names(),
         profile_channel: Lock::new(None),
+        self_profiling,
         perf_stats: PerfStats {
             svh_time: Cell::new(Duration::from_secs(0)),
             incr_comp_hashes_time: Cell::new(Duration::from_secs(0)),
@@ -1168,6 +1224,7 @@ pub fn build_session_(
             normalize_projection_ty: Cell::new(0),
         },
         code_stats: RefCell::new(CodeStats::new()),
+        item_times: RefCell::new(ItemTimes::new()),
         optimization_fuel_crate,
         optimization_fuel_limit,
         print_fuel_crate,
@@ -1238,32 +1295,50 @@ pub enum IncrCompSession {
     InvalidBecauseOfErrors { session_directory: PathBuf },
 }
 
-pub fn early_error(output: config::ErrorOutputType, msg: &str) -> ! {
-    let emitter: Box<dyn Emitter> = match output {
-        config::ErrorOutputType::HumanReadable(color_config) => {
+thread_local! {
+    static EARLY_DIAGNOSTIC_OUTPUT: RefCell<Option<Box<dyn Fn() -> Box<dyn Write + Send>>>> =
+        RefCell::new(None)
+}
+
+/// Makes `early_error` and `early_warn` write to the writers made by `output`
+/// instead of stderr when they are called from the current thread, until it is
+/// called again with `None`. The compiler daemon uses this to send these
+/// diagnostics to its clients.
+pub fn set_early_diagnostic_output(output: Option<Box<dyn Fn() -> Box<dyn Write + Send>>>) {
+    EARLY_DIAGNOSTIC_OUTPUT.with(|slot| *slot.borrow_mut() = output);
+}
+
+fn early_emitter(output: config::ErrorOutputType) -> Box<dyn Emitter> {
+    let dst = EARLY_DIAGNOSTIC_OUTPUT.with(|slot| slot.borrow().as_ref().map(|output| output()));
+    match (output, dst) {
+        (config::ErrorOutputType::HumanReadable(color_config), None) => {
             Box::new(EmitterWriter::stderr(color_config, None, false, false))
         }
-        config::ErrorOutputType::Json(pretty) => Box::new(JsonEmitter::basic(pretty)),
-        config::ErrorOutputType::Short(color_config) => {
+        (config::ErrorOutputType::HumanReadable(_), Some(dst)) => {
+            Box::new(EmitterWriter::new(dst, None, false, false))
+        }
+        (config::ErrorOutputType::Json(pretty), None) => Box::new(JsonEmitter::basic(pretty)),
+        (config::ErrorOutputType::Json(pretty), Some(dst)) => {
+            let codemap = Lrc::new(codemap::CodeMap::new(codemap::FilePathMapping::empty()));
+            Box::new(JsonEmitter::new(dst, None, codemap, pretty, false))
+        }
+        (config::ErrorOutputType::Short(color_config), None) => {
             Box::new(EmitterWriter::stderr(color_config, None, true, false))
         }
-    };
-    let handler = errors::Handler::with_emitter(true, false, emitter);
+        (config::ErrorOutputType::Short(_), Some(dst)) => {
+            Box::new(EmitterWriter::new(dst, None, true, false))
+        }
+    }
+}
+
+pub fn early_error(output: config::ErrorOutputType, msg: &str) -> ! {
+    let handler = errors::Handler::with_emitter(true, false, early_emitter(output));
     handler.emit(&MultiSpan::new(), msg, errors::Level::Fatal);
     errors::FatalError.raise();
 }
 
 pub fn early_warn(output: config::ErrorOutputType, msg: &str) {
-    let emitter: Box<dyn Emitter> = match output {
-        config::ErrorOutputType::HumanReadable(color_config) => {
-            Box::new(EmitterWriter::stderr(color_config, None, false, false))
-        }
-        config::ErrorOutputType::Json(pretty) => Box::new(JsonEmitter::basic(pretty)),
-        config::ErrorOutputType::Short(color_config) => {
-            Box::new(EmitterWriter::stderr(color_config, None, true, false))
-        }
-    };
-    let handler = errors::Handler::with_emitter(true, false, emitter);
+    let handler = errors::Handler::with_emitter(true, false, early_emitter(output));
     handler.emit(&MultiSpan::new(), msg, errors::Level::Warning);
 }
 
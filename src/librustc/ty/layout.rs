@@ -1740,13 +1740,14 @@ impl<'a, 'tcx> LayoutCx<'tcx, TyCtxt<'a, 'tcx, 'tcx>> {
 
     fn record_layout_for_printing_outlined(self, layout: TyLayout<'tcx>) {
         // (delay format until we actually need it)
-        let record = |kind, opt_discr_size, variants| {
+        let record = |kind, opt_discr_size, discr, variants| {
             let type_desc = format!("{:?}", layout.ty);
             self.tcx.sess.code_stats.borrow_mut().record_type_size(kind,
                                                                    type_desc,
                                                                    layout.align,
                                                                    layout.size,
                                                                    opt_discr_size,
+                                                                   discr,
                                                                    variants);
         };
 
@@ -1758,7 +1759,7 @@ impl<'a, 'tcx> LayoutCx<'tcx, TyCtxt<'a, 'tcx, 'tcx>> {
 
             ty::TyClosure(..) => {
                 debug!("print-type-size t: `{:?}` record closure", layout.ty);
-                record(DataTypeKind::Closure, None, vec![]);
+                record(DataTypeKind::Closure, None, None, vec![]);
                 return;
             }
 
@@ -1772,6 +1773,7 @@ impl<'a, 'tcx> LayoutCx<'tcx, TyCtxt<'a, 'tcx, 'tcx>> {
 
         let build_variant_info = |n: Option<ast::Name>,
                                   flds: &[ast::Name],
+                                  discr: Option<String>,
                                   layout: TyLayout<'tcx>| {
             let mut min_size = Size::from_bytes(0);
             let field_info: Vec<_> = flds.iter().enumerate().map(|(i, &name)| {
@@ -1808,6 +1810,7 @@ impl<'a, 'tcx> LayoutCx<'tcx, TyCtxt<'a, 'tcx, 'tcx>> {
                 } else {
                     min_size.bytes()
                 },
+                discr,
                 fields: field_info,
             }
         };
@@ -1820,15 +1823,22 @@ impl<'a, 'tcx> LayoutCx<'tcx, TyCtxt<'a, 'tcx, 'tcx>> {
                     let variant_def = &adt_def.variants[index];
                     let fields: Vec<_> =
                         variant_def.fields.iter().map(|f| f.name).collect();
+                    let discr = if adt_def.is_enum() {
+                        Some(adt_def.discriminant_for_variant(self.tcx, index).to_string())
+                    } else {
+                        None
+                    };
                     record(adt_kind.into(),
+                           None,
                            None,
                            vec![build_variant_info(Some(variant_def.name),
                                                    &fields,
+                                                   discr,
                                                    layout)]);
                 } else {
                     // (This case arises for *empty* enums; so give it
                     // zero variants.)
-                    record(adt_kind.into(), None, vec![]);
+                    record(adt_kind.into(), None, None, vec![]);
                 }
             }
 
@@ -1840,15 +1850,31 @@ impl<'a, 'tcx> LayoutCx<'tcx, TyCtxt<'a, 'tcx, 'tcx>> {
                     adt_def.variants.iter().enumerate().map(|(i, variant_def)| {
                         let fields: Vec<_> =
                             variant_def.fields.iter().map(|f| f.name).collect();
+                        let discr = adt_def.discriminant_for_variant(self.tcx, i);
                         build_variant_info(Some(variant_def.name),
                                             &fields,
+                                            Some(discr.to_string()),
                                             layout.for_variant(self, i))
                     })
                     .collect();
+                // The tag or the niche is field 0 of the enum.
+                let (discr_kind, discr_scalar) = match layout.variants {
+                    Variants::Tagged { ref discr, .. } => (session::DiscrKind::Tag, discr),
+                    Variants::NicheFilling { dataful_variant, ref niche, .. } => {
+                        (session::DiscrKind::Niche { dataful_variant }, niche)
+                    }
+                    Variants::Single { .. } => bug!(),
+                };
+                let discr_size = discr_scalar.value.size(self);
+                let discr = session::DiscrInfo {
+                    kind: discr_kind,
+                    offset: layout.fields.offset(0).bytes(),
+                    size: discr_size.bytes(),
+                };
                 record(adt_kind.into(), match layout.variants {
-                    Variants::Tagged { ref discr, .. } => Some(discr.value.size(self)),
+                    Variants::Tagged { .. } => Some(discr_size),
                     _ => None
-                }, variant_infos);
+                }, Some(discr), variant_infos);
             }
         }
     }
@@ -183,11 +183,13 @@ impl<'sess> OnDiskCache<'sess> {
                 let mut file_to_file_index = FxHashMap();
                 let mut file_index_to_stable_id = FxHashMap();
 
+                let path_mapping = tcx.sess.codemap().path_mapping();
                 for (index, file) in tcx.sess.codemap().files().iter().enumerate() {
                     let index = FileMapIndex(index as u32);
                     let file_ptr: *const FileMap = &**file as *const _;
                     file_to_file_index.insert(file_ptr, index);
-                    file_index_to_stable_id.insert(index, StableFilemapId::new(&file));
+                    file_index_to_stable_id.insert(index,
+                                                   StableFilemapId::new(&file, path_mapping));
                 }
 
                 (file_to_file_index, file_index_to_stable_id)
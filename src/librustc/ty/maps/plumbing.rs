@@ -183,6 +183,15 @@ macro_rules! profq_msg {
     }
 }
 
+// If enabled, record an event in the profiler of -Z self-profile
+macro_rules! self_profile {
+    ($tcx:expr, $method:ident($($arg:expr),*)) => {
+        if let Some(ref profiler) = $tcx.sess.self_profiling {
+            profiler.$method($($arg),*);
+        }
+    }
+}
+
 // If enabled, format a key using its debug string, which can be
 // expensive to compute (in terms of time).
 macro_rules! profq_key {
@@ -314,6 +323,7 @@ macro_rules! define_maps {
                             QueryResult::Started(ref job) => Some(job.clone()),
                             QueryResult::Complete(ref value) => {
                                 profq_msg!(tcx, ProfileQueriesMsg::CacheHit);
+                                self_profile!(tcx, record_query_hit(stringify!($name)));
                                 let result = Ok(((&value.value).clone(), value.index));
                                 return TryGetLock::JobCompleted(result);
                             },
@@ -401,13 +411,17 @@ macro_rules! define_maps {
 
                 if dep_node.kind.is_anon() {
                     profq_msg!(tcx, ProfileQueriesMsg::ProviderBegin);
+                    self_profile!(tcx, start_query(stringify!($name)));
 
                     let res = Self::start_job(tcx, span, key, lock, |tcx| {
                         tcx.dep_graph.with_anon_task(dep_node.kind, || {
                             Self::compute_result(tcx.global_tcx(), key)
                         })
-                    })?;
+                    });
 
+                    // Ended before a cycle error is returned, to keep the trace balanced.
+                    self_profile!(tcx, end_query(stringify!($name)));
+                    let res = res?;
                     profq_msg!(tcx, ProfileQueriesMsg::ProviderEnd);
                     let (((result, dep_node_index), diagnostics), job) = res;
 
@@ -434,6 +448,7 @@ macro_rules! define_maps {
                     mem::drop(lock);
                     if let Some(dep_node_index) = tcx.try_mark_green_and_read(&dep_node) {
                         profq_msg!(tcx, ProfileQueriesMsg::CacheHit);
+                        self_profile!(tcx, record_query_hit(stringify!($name)));
                         return Self::load_from_disk_and_cache_in_memory(tcx,
                                                                         key,
                                                                         span,
@@ -565,8 +580,10 @@ macro_rules! define_maps {
                                 tcx.sess.opts.debugging_opts.incremental_queries {
                     let prev_dep_node_index =
                         tcx.dep_graph.prev_dep_node_index_of(dep_node);
+                    self_profile!(tcx, start_incremental_load(stringify!($name)));
                     let result = Self::try_load_from_disk(tcx.global_tcx(),
                                                           prev_dep_node_index);
+                    self_profile!(tcx, end_incremental_load(stringify!($name)));
 
                     // We always expect to find a cached result for things that
                     // can be forced from DepNode.
@@ -683,6 +700,7 @@ macro_rules! define_maps {
                         key, dep_node);
 
                 profq_msg!(tcx, ProfileQueriesMsg::ProviderBegin);
+                self_profile!(tcx, start_query(stringify!($name)));
                 let res = Self::start_job(tcx,
                                           span,
                                           key,
@@ -699,7 +717,9 @@ macro_rules! define_maps {
                                                 key,
                                                 Self::compute_result)
                     }
-                })?;
+                });
+                self_profile!(tcx, end_query(stringify!($name)));
+                let res = res?;
                 profq_msg!(tcx, ProfileQueriesMsg::ProviderEnd);
 
                 let (((result, dep_node_index), diagnostics), job) = res;
@@ -138,6 +138,13 @@ pub fn set_time_depth(depth: usize) {
 pub fn time<T, F>(sess: &Session, what: &str, f: F) -> T where
     F: FnOnce() -> T,
 {
+    if let Some(ref profiler) = sess.self_profiling {
+        profiler.start_activity("pass", what);
+        let rv = time_ext(sess.time_passes(), Some(sess), what, f);
+        profiler.end_activity("pass");
+        return rv;
+    }
+
     time_ext(sess.time_passes(), Some(sess), what, f)
 }
 
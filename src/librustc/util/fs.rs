@@ -11,7 +11,11 @@
 use std::path::{self, Path, PathBuf};
 use std::ffi::OsString;
 use std::fs;
+use std::hash::Hasher;
 use std::io;
+use std::time::{Duration, SystemTime};
+
+use rustc_data_structures::stable_hasher::StableHasher;
 
 // Unfortunately, on windows, it looks like msvcrt.dll is silently translating
 // verbatim paths under the hood to non-verbatim paths! This manifests itself as
@@ -109,3 +113,65 @@ pub fn rename_or_copy_remove<P: AsRef<Path>, Q: AsRef<Path>>(p: P,
         }
     }
 }
+
+/// What tells whether a file changed without reading it: its length, its
+/// modification time and, on Unix, the inode it is stored in.
+#[derive(Copy, Clone, Debug)]
+pub struct FileStamp {
+    len: u64,
+    modified: Option<SystemTime>,
+    inode: Option<(u64, u64)>,
+    /// When the stamp was taken.
+    taken: SystemTime,
+}
+
+impl FileStamp {
+    pub fn of(path: &Path) -> io::Result<FileStamp> {
+        let taken = SystemTime::now();
+        let metadata = fs::metadata(path)?;
+        Ok(FileStamp {
+            len: metadata.len(),
+            modified: metadata.modified().ok(),
+            inode: inode(&metadata),
+            taken,
+        })
+    }
+
+    /// Whether `other` was taken of a file with the same metadata.
+    pub fn same_metadata(&self, other: &FileStamp) -> bool {
+        self.len == other.len && self.modified == other.modified && self.inode == other.inode
+    }
+
+    /// Whether the file was last modified long enough before the stamp was
+    /// taken that a later change would have changed its modification time.
+    /// A file modified again within the granularity of modification times
+    /// keeps the same one, so only its contents tell whether it changed.
+    pub fn is_settled(&self) -> bool {
+        match self.modified {
+            Some(modified) => match self.taken.duration_since(modified) {
+                Ok(age) => age >= Duration::from_secs(2),
+                Err(_) => false,
+            },
+            None => false,
+        }
+    }
+}
+
+#[cfg(unix)]
+fn inode(metadata: &fs::Metadata) -> Option<(u64, u64)> {
+    use std::os::unix::fs::MetadataExt;
+    Some((metadata.dev(), metadata.ino()))
+}
+
+#[cfg(not(unix))]
+fn inode(_: &fs::Metadata) -> Option<(u64, u64)> {
+    None
+}
+
+/// A hash of the contents of a file, for when its `FileStamp` cannot tell
+/// whether it changed.
+pub fn contents_hash(contents: &[u8]) -> u128 {
+    let mut hasher = StableHasher::<u128>::new();
+    hasher.write(contents);
+    hasher.finish()
+}
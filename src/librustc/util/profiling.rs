@@ -0,0 +1,256 @@
+// Copyright 2018 The Rust Project Developers. See the COPYRIGHT
+// file at the top-level directory of this distribution and at
+// http://rust-lang.org/COPYRIGHT.
+//
+// Licensed under the Apache License, Version 2.0 <LICENSE-APACHE or
+// http://www.apache.org/licenses/LICENSE-2.0> or the MIT license
+// <LICENSE-MIT or http://opensource.org/licenses/MIT>, at your
+// option. This file may not be copied, modified, or distributed
+// except according to those terms.
+
+//! The self-profiler enabled by `-Z self-profile`.
+//!
+//! Unlike `-Z profile-queries`, which sends every event to another thread,
+//! the self-profiler only appends a small fixed-size record to a buffer of
+//! the thread the event happens on, so that it can be left on for real
+//! builds. The buffers are only read once compilation is done, to write
+//! them out in the trace event format of Chrome, which `chrome://tracing`
+//! and Perfetto can open.
+
+use std::cell::RefCell;
+use std::fs::File;
+use std::io::{self, BufWriter, Write};
+use std::path::Path;
+use std::process;
+use std::sync::{Arc, Mutex};
+use std::sync::atomic::{AtomicUsize, Ordering, ATOMIC_USIZE_INIT};
+use std::time::Instant;
+
+use serialize::json::Json;
+
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+enum EventKind {
+    QueryStart,
+    QueryEnd,
+    /// A query whose result was already in memory, or was green in the
+    /// incremental dep-graph.
+    QueryCacheHit,
+    IncrementalLoadStart,
+    IncrementalLoadEnd,
+    /// A pass of `time`, or a piece of LLVM work.
+    ActivityStart,
+    ActivityEnd,
+}
+
+/// One event as recorded, before it is written out.
+#[derive(Copy, Clone)]
+struct RawEvent {
+    kind: EventKind,
+    /// The query, or the kind of activity.
+    name: &'static str,
+    /// The index of a string in `SelfProfiler::strings` telling more about
+    /// the event, like the module it is about, or `NO_DETAIL`.
+    detail: u32,
+    /// Nanoseconds since the profiler was created.
+    timestamp: u64,
+}
+
+const NO_DETAIL: u32 = !0;
+
+type EventBuffer = Arc<Mutex<Vec<RawEvent>>>;
+
+static NEXT_PROFILER_ID: AtomicUsize = ATOMIC_USIZE_INIT;
+
+thread_local! {
+    /// The buffer of the current thread, along with the ID of the profiler it
+    /// belongs to. A thread can be used by several sessions in turn, as
+    /// rustdoc does for doctests.
+    static THREAD_BUFFER: RefCell<Option<(usize, EventBuffer)>> = RefCell::new(None)
+}
+
+pub struct SelfProfiler {
+    id: usize,
+    start: Instant,
+    /// The buffers of the threads that recorded events, in the order they
+    /// recorded their first one.
+    buffers: Mutex<Vec<EventBuffer>>,
+    strings: Mutex<Vec<String>>,
+}
+
+impl SelfProfiler {
+    pub fn new() -> SelfProfiler {
+        SelfProfiler {
+            id: NEXT_PROFILER_ID.fetch_add(1, Ordering::SeqCst),
+            start: Instant::now(),
+            buffers: Mutex::new(Vec::new()),
+            strings: Mutex::new(Vec::new()),
+        }
+    }
+
+    pub fn start_query(&self, query: &'static str) {
+        self.record(EventKind::QueryStart, query, NO_DETAIL);
+    }
+
+    pub fn end_query(&self, query: &'static str) {
+        self.record(EventKind::QueryEnd, query, NO_DETAIL);
+    }
+
+    pub fn record_query_hit(&self, query: &'static str) {
+        self.record(EventKind::QueryCacheHit, query, NO_DETAIL);
+    }
+
+    pub fn start_incremental_load(&self, query: &'static str) {
+        self.record(EventKind::IncrementalLoadStart, query, NO_DETAIL);
+    }
+
+    pub fn end_incremental_load(&self, query: &'static str) {
+        self.record(EventKind::IncrementalLoadEnd, query, NO_DETAIL);
+    }
+
+    /// Starts an activity of the kind `name`, like an LLVM pass, about
+    /// `detail`, like the module the pass runs on. These are far fewer than
+    /// queries, so the cost of storing `detail` does not matter.
+    pub fn start_activity(&self, name: &'static str, detail: &str) {
+        let detail = {
+            let mut strings = self.strings.lock().unwrap();
+            strings.push(detail.to_owned());
+            (strings.len() - 1) as u32
+        };
+        self.record(EventKind::ActivityStart, name, detail);
+    }
+
+    pub fn end_activity(&self, name: &'static str) {
+        self.record(EventKind::ActivityEnd, name, NO_DETAIL);
+    }
+
+    fn record(&self, kind: EventKind, name: &'static str, detail: u32) {
+        let elapsed = self.start.elapsed();
+        let timestamp = elapsed.as_secs() * 1_000_000_000 + elapsed.subsec_nanos() as u64;
+        let event = RawEvent { kind, name, detail, timestamp };
+
+        THREAD_BUFFER.with(|buffer| {
+            let mut buffer = buffer.borrow_mut();
+            let current = match *buffer {
+                Some((id, _)) => id == self.id,
+                None => false,
+            };
+            if !current {
+                let new_buffer = Arc::new(Mutex::new(Vec::with_capacity(1024)));
+                self.buffers.lock().unwrap().push(new_buffer.clone());
+                *buffer = Some((self.id, new_buffer));
+            }
+            // The lock is only ever contended while the trace is written.
+            buffer.as_ref().unwrap().1.lock().unwrap().push(event);
+        });
+    }
+
+    /// Writes the events recorded so far to `path`, as a JSON trace in the
+    /// Chrome trace event format, with a track for every thread.
+    pub fn write_chrome_trace(&self, path: &Path) -> io::Result<()> {
+        let mut out = BufWriter::new(File::create(path)?);
+        self.write_chrome_trace_to(&mut out)?;
+        out.flush()
+    }
+
+    fn write_chrome_trace_to<W: Write>(&self, out: &mut W) -> io::Result<()> {
+        let pid = process::id();
+        let strings = self.strings.lock().unwrap();
+
+        write!(out, "{{\"traceEvents\":[")?;
+        let mut first = true;
+        for (tid, buffer) in self.buffers.lock().unwrap().iter().enumerate() {
+            for event in buffer.lock().unwrap().iter() {
+                if !first {
+                    write!(out, ",")?;
+                }
+                first = false;
+
+                let (phase, category) = match event.kind {
+                    EventKind::QueryStart => ("B", "query"),
+                    EventKind::QueryEnd => ("E", "query"),
+                    EventKind::QueryCacheHit => ("i", "query-cache-hit"),
+                    EventKind::IncrementalLoadStart => ("B", "incremental-load"),
+                    EventKind::IncrementalLoadEnd => ("E", "incremental-load"),
+                    EventKind::ActivityStart => ("B", "activity"),
+                    EventKind::ActivityEnd => ("E", "activity"),
+                };
+                // Timestamps are in microseconds.
+                write!(out,
+                       "\n{{\"name\":{},\"cat\":\"{}\",\"ph\":\"{}\",\"ts\":{}.{:03},\
+                        \"pid\":{},\"tid\":{}",
+                       Json::String(event.name.to_owned()), category, phase,
+                       event.timestamp / 1000, event.timestamp % 1000, pid, tid)?;
+                if phase == "i" {
+                    // Instant events are drawn on their thread's track.
+                    write!(out, ",\"s\":\"t\"")?;
+                }
+                if event.detail != NO_DETAIL {
+                    write!(out, ",\"args\":{{\"detail\":{}}}",
+                           Json::String(strings[event.detail as usize].clone()))?;
+                }
+                write!(out, "}}")?;
+            }
+        }
+        write!(out, "\n],\"displayTimeUnit\":\"ms\"}}\n")
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::SelfProfiler;
+    use serialize::json::Json;
+    use std::sync::Arc;
+    use std::thread;
+
+    fn field<'a>(event: &'a Json, key: &str) -> &'a Json {
+        event.find(key).unwrap_or_else(|| panic!("no `{}` in {}", key, event))
+    }
+
+    #[test]
+    fn chrome_trace_format() {
+        let profiler = Arc::new(SelfProfiler::new());
+        profiler.start_query("type_of");
+        profiler.record_query_hit("typeck_tables_of");
+        profiler.start_activity("codegen", "a \"quoted\" module");
+        profiler.end_activity("codegen");
+        profiler.end_query("type_of");
+        {
+            let profiler = profiler.clone();
+            thread::spawn(move || profiler.record_query_hit("layout_raw")).join().unwrap();
+        }
+
+        let mut out = Vec::new();
+        profiler.write_chrome_trace_to(&mut out).unwrap();
+        let trace = Json::from_str(&String::from_utf8(out).unwrap()).unwrap();
+        assert_eq!(field(&trace, "displayTimeUnit").as_string(), Some("ms"));
+        let events = field(&trace, "traceEvents").as_array().unwrap();
+
+        let summary = events.iter().map(|event| {
+            (field(event, "name").as_string().unwrap(),
+             field(event, "cat").as_string().unwrap(),
+             field(event, "ph").as_string().unwrap(),
+             field(event, "tid").as_u64().unwrap())
+        }).collect::<Vec<_>>();
+        assert_eq!(summary, [
+            ("type_of", "query", "B", 0),
+            ("typeck_tables_of", "query-cache-hit", "i", 0),
+            ("codegen", "activity", "B", 0),
+            ("codegen", "activity", "E", 0),
+            ("type_of", "query", "E", 0),
+            ("layout_raw", "query-cache-hit", "i", 1),
+        ]);
+
+        // Instant events are drawn on their thread's track, and only
+        // activities have details.
+        assert_eq!(field(&events[1], "s").as_string(), Some("t"));
+        assert!(events[0].find("s").is_none());
+        assert_eq!(field(field(&events[2], "args"), "detail").as_string(),
+                   Some("a \"quoted\" module"));
+        assert!(events[3].find("args").is_none());
+
+        let timestamps = events[..5].iter()
+            .map(|event| field(event, "ts").as_f64().unwrap())
+            .collect::<Vec<_>>();
+        assert!(timestamps.windows(2).all(|w| w[0] <= w[1]));
+    }
+}
@@ -36,6 +36,7 @@ use rustc::middle::mem_categorization::Categorization;
 use rustc::middle::mem_categorization::ImmutabilityBlame;
 use rustc::middle::region;
 use rustc::middle::free_region::RegionRelations;
+use rustc::session::ItemActivity;
 use rustc::ty::{self, Ty, TyCtxt};
 use rustc::ty::maps::Providers;
 use rustc_mir::util::borrowck_errors::{BorrowckErrors, Origin};
@@ -88,6 +89,14 @@ pub struct AnalysisData<'a, 'tcx: 'a> {
 
 fn borrowck<'a, 'tcx>(tcx: TyCtxt<'a, 'tcx, 'tcx>, owner_def_id: DefId)
     -> Lrc<BorrowCheckResult>
+{
+    tcx.sess.time_item(ItemActivity::Borrowck,
+                       || tcx.item_path_str(owner_def_id),
+                       || borrowck_item(tcx, owner_def_id))
+}
+
+fn borrowck_item<'a, 'tcx>(tcx: TyCtxt<'a, 'tcx, 'tcx>, owner_def_id: DefId)
+    -> Lrc<BorrowCheckResult>
 {
     debug!("borrowck(body_owner_def_id={:?})", owner_def_id);
 
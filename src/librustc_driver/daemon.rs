@@ -0,0 +1,353 @@
+// Copyright 2018 The Rust Project Developers. See the COPYRIGHT
+// file at the top-level directory of this distribution and at
+// http://rust-lang.org/COPYRIGHT.
+//
+// Licensed under the Apache License, Version 2.0 <LICENSE-APACHE or
+// http://www.apache.org/licenses/LICENSE-2.0> or the MIT license
+// <LICENSE-MIT or http://opensource.org/licenses/MIT>, at your
+// option. This file may not be copied, modified, or distributed
+// except according to those terms.
+
+
+//! The compiler daemon started by `-Z daemon=<socket>`, and its client.
+//!
+//! The daemon listens on a Unix socket, and compiles the requests it receives
+//! one at a time, in the same thread, so that what outlives a session stays
+//! warm between requests: the codegen backend and LLVM are only loaded and
+//! initialized once, the metadata of the libraries used by the crates is only
+//! read and decompressed again when a library changes, and the dep-graph and
+//! query result cache that a session saves for incremental compilation are
+//! kept in memory for the next session of the same crate. Everything else
+//! belongs to the session of a request.
+//!
+//! Proc macro and plugin libraries are loaded from copies named after their
+//! contents, in a directory next to the socket, so that a rebuilt library is
+//! loaded again instead of the daemon running its old code.
+//!
+//! A rustc started with `RUSTC_DAEMON_SOCKET` set to the socket of a daemon
+//! is a client: instead of compiling, it sends its arguments, working
+//! directory and environment to the daemon, prints what the daemon sends back
+//! and exits with the same code, which lets Cargo and other tools use the
+//! daemon without changes. If no daemon listens on the socket, if the input
+//! is read from the standard input, or if the daemon is another version of
+//! the compiler or uses another sysroot, the client compiles by itself.
+//!
+//! Each request is a JSON object on a line of its own:
+//!
+//! ```text
+//! {"args":["rustc","src/lib.rs"],"cwd":"/home/user/foo","env":[["PATH","/usr/bin"]],
+//!  "version":"1.27.0-nightly (...)","sysroot":"/home/user/.rustup/toolchains/nightly"}
+//! ```
+//!
+//! The daemon answers with JSON objects on lines of their own. The output of
+//! the compiler comes in `{"stdout":"..."}` and `{"stderr":"..."}` messages,
+//! and a last `{"exit_code":0}` message ends the answer. A request the daemon
+//! does not compile because it comes from another compiler is answered with
+//! `{"refused":"..."}` alone. A request of `{"shutdown":true}` stops the
+//! daemon.
+
+use rustc::session::{early_error, set_early_diagnostic_output, CompileIncomplete, CompileResult};
+use rustc::session::config::ErrorOutputType;
+use rustc::session::filesearch;
+use rustc_incremental;
+use rustc_metadata::{dynamic_lib, locator};
+use serialize::json::Json;
+
+use std::collections::BTreeMap;
+use std::env;
+use std::ffi::OsString;
+use std::fs;
+use std::io::{self, BufRead, BufReader, Write};
+use std::os::unix::fs::FileTypeExt;
+use std::os::unix::net::{UnixListener, UnixStream};
+use std::panic::{self, AssertUnwindSafe};
+use std::path::{Path, PathBuf};
+
+use errors::FatalErrorMarker;
+use super::{run_compiler, RustcDefaultCalls};
+
+/// The environment variable that makes rustc a client of the daemon
+/// listening on the socket it names.
+pub const SOCKET_VAR: &str = "RUSTC_DAEMON_SOCKET";
+
+/// Serves the requests sent to `socket` until a client asks the daemon to
+/// shut down.
+pub fn serve(socket: &Path) -> CompileResult {
+    // A socket left behind by a daemon that did not shut down cleanly
+    // cannot be bound again. Anything else at that path is left alone, and
+    // binding the socket fails.
+    let stale_socket = match fs::symlink_metadata(socket) {
+        Ok(metadata) => metadata.file_type().is_socket() && UnixStream::connect(socket).is_err(),
+        Err(_) => false,
+    };
+    if stale_socket {
+        let _ = fs::remove_file(socket);
+    }
+    let listener = match UnixListener::bind(socket) {
+        Ok(listener) => listener,
+        Err(e) => {
+            early_error(ErrorOutputType::default(),
+                        &format!("could not listen on `{}`: {}", socket.display(), e))
+        }
+    };
+
+    let dylib_dir = dylib_directory(socket);
+    if let Err(e) = fs::create_dir_all(&dylib_dir) {
+        early_error(ErrorOutputType::default(),
+                    &format!("could not create `{}`: {}", dylib_dir.display(), e))
+    }
+
+    locator::cache_metadata_in_current_thread();
+    rustc_incremental::keep_state_in_current_thread();
+    dynamic_lib::load_copies_in_current_thread(Some(dylib_dir.clone()));
+
+    for stream in listener.incoming() {
+        let result = stream.and_then(|stream| handle_connection(stream));
+        match result {
+            Ok(true) => {}
+            Ok(false) => break,
+            Err(e) => eprintln!("rustc daemon: {}", e),
+        }
+    }
+
+    dynamic_lib::load_copies_in_current_thread(None);
+    let _ = fs::remove_dir_all(&dylib_dir);
+    let _ = fs::remove_file(socket);
+    Ok(())
+}
+
+/// The directory where the daemon listening on `socket` copies the dynamic
+/// libraries it loads.
+fn dylib_directory(socket: &Path) -> PathBuf {
+    let mut dir = socket.as_os_str().to_owned();
+    dir.push(".dylibs");
+    PathBuf::from(dir)
+}
+
+/// Answers the request of a client, and returns whether the daemon should
+/// keep serving.
+fn handle_connection(mut stream: UnixStream) -> io::Result<bool> {
+    let mut line = String::new();
+    BufReader::new(stream.try_clone()?).read_line(&mut line)?;
+    let request = Json::from_str(&line).map_err(|e| {
+        io::Error::new(io::ErrorKind::InvalidData, format!("invalid request: {}", e))
+    })?;
+
+    if request.find("shutdown").and_then(|s| s.as_boolean()) == Some(true) {
+        send(&mut stream, "exit_code", Json::I64(0))?;
+        return Ok(false);
+    }
+
+    if let Some(reason) = compiler_mismatch(&request) {
+        send(&mut stream, "refused", Json::String(reason))?;
+        return Ok(true);
+    }
+
+    let args = strings(request.find("args"));
+    let cwd = request.find("cwd").and_then(|cwd| cwd.as_string());
+    let vars: Vec<(String, String)> = match request.find("env").and_then(|env| env.as_array()) {
+        Some(vars) => vars.iter().filter_map(|var| {
+            let var = strings(Some(var));
+            if var.len() == 2 {
+                Some((var[0].clone(), var[1].clone()))
+            } else {
+                None
+            }
+        }).collect(),
+        None => Vec::new(),
+    };
+
+    let invalid = args.is_empty() || starts_daemon(&args) || reads_stdin(&args);
+    let exit_code = match (invalid, cwd) {
+        (false, Some(cwd)) => compile(&args, Path::new(cwd), &vars, &stream)?,
+        _ => {
+            send(&mut stream, "stderr", Json::String("error: invalid request\n".to_string()))?;
+            1
+        }
+    };
+    send(&mut stream, "exit_code", Json::I64(exit_code))?;
+    Ok(true)
+}
+
+/// Compiles with the arguments, working directory and environment of a
+/// client, and returns the exit code of the compilation.
+fn compile(args: &[String],
+           cwd: &Path,
+           vars: &[(String, String)],
+           stream: &UnixStream)
+           -> io::Result<i64> {
+    let saved_cwd = env::current_dir()?;
+    let saved_vars: Vec<(OsString, OsString)> = env::vars_os().collect();
+    env::set_current_dir(cwd)?;
+    set_vars(vars.iter().map(|&(ref name, ref value)| (name.into(), value.into())));
+
+    let stdout = Forward { stream: stream.try_clone()?, kind: "stdout" };
+    let stderr = Forward { stream: stream.try_clone()?, kind: "stderr" };
+    let diagnostics = Forward { stream: stream.try_clone()?, kind: "stderr" };
+    let old_stdout = io::set_print(Some(box stdout));
+    let old_stderr = io::set_panic(Some(box stderr));
+    let early_stream = stream.try_clone()?;
+    set_early_diagnostic_output(Some(box move || -> Box<Write + Send> {
+        match early_stream.try_clone() {
+            Ok(stream) => box Forward { stream, kind: "stderr" },
+            Err(_) => box io::sink(),
+        }
+    }));
+
+    let result = panic::catch_unwind(AssertUnwindSafe(|| {
+        run_compiler(args, &mut RustcDefaultCalls, None, Some(box diagnostics))
+    }));
+
+    set_early_diagnostic_output(None);
+    io::set_print(old_stdout);
+    io::set_panic(old_stderr);
+    set_vars(saved_vars.into_iter());
+    env::set_current_dir(saved_cwd)?;
+
+    Ok(match result {
+        Ok((Ok(()), _)) | Ok((Err(CompileIncomplete::Stopped), _)) => 0,
+        Ok((Err(CompileIncomplete::Errored(_)), _)) => 1,
+        Err(ref value) if value.is::<FatalErrorMarker>() => 1,
+        // The panic message was sent to the client. The details that
+        // `monitor` adds for bug reports are left out.
+        Err(_) => 101,
+    })
+}
+
+/// Replaces the environment of the process with `vars`.
+fn set_vars<I: Iterator<Item=(OsString, OsString)>>(vars: I) {
+    for (name, _) in env::vars_os() {
+        env::remove_var(name);
+    }
+    for (name, value) in vars {
+        env::set_var(name, value);
+    }
+}
+
+/// The version of the compiler and the sysroot it uses by default, which the
+/// client and the daemon must share for the daemon to compile like the client
+/// would. An explicit `--sysroot` is part of the arguments.
+fn compiler_identity() -> (Json, Json) {
+    let version = match option_env!("CFG_VERSION") {
+        Some(version) => Json::String(version.to_string()),
+        None => Json::Null,
+    };
+    let sysroot = match filesearch::get_or_default_sysroot().into_os_string().into_string() {
+        Ok(sysroot) => Json::String(sysroot),
+        Err(_) => Json::Null,
+    };
+    (version, sysroot)
+}
+
+/// Why the daemon should not compile `request`, if it comes from another
+/// compiler than the daemon.
+fn compiler_mismatch(request: &Json) -> Option<String> {
+    let (version, sysroot) = compiler_identity();
+    if request.find("version") != Some(&version) {
+        Some(format!("the daemon is rustc {}", version))
+    } else if request.find("sysroot") != Some(&sysroot) || sysroot == Json::Null {
+        Some(format!("the daemon uses the sysroot {}", sysroot))
+    } else {
+        None
+    }
+}
+
+/// Whether `args` start a daemon, which a daemon or a client should not do.
+fn starts_daemon(args: &[String]) -> bool {
+    args.iter().any(|arg| arg.starts_with("daemon=") || arg.starts_with("-Zdaemon="))
+}
+
+/// Whether `args` read the input from the standard input, which the daemon
+/// cannot read for its clients.
+fn reads_stdin(args: &[String]) -> bool {
+    args.iter().skip(1).any(|arg| arg == "-")
+}
+
+/// The strings of `json` if it is an array, ignoring other elements.
+fn strings(json: Option<&Json>) -> Vec<String> {
+    match json.and_then(|json| json.as_array()) {
+        Some(array) => array.iter().filter_map(|s| s.as_string()).map(|s| s.to_string()).collect(),
+        None => Vec::new(),
+    }
+}
+
+fn send(stream: &mut UnixStream, kind: &str, value: Json) -> io::Result<()> {
+    let mut message = BTreeMap::new();
+    message.insert(kind.to_string(), value);
+    writeln!(stream, "{}", Json::Object(message))
+}
+
+/// Sends what is written to it to the client, as messages of kind `kind`.
+struct Forward {
+    stream: UnixStream,
+    kind: &'static str,
+}
+
+impl Write for Forward {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        let text = String::from_utf8_lossy(buf).into_owned();
+        send(&mut self.stream, self.kind, Json::String(text))?;
+        Ok(buf.len())
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        self.stream.flush()
+    }
+}
+
+/// If `RUSTC_DAEMON_SOCKET` is set and a daemon listens on it, has the daemon
+/// compile with the arguments of this process, and returns its exit code.
+pub fn run_as_client() -> Option<i32> {
+    let socket = env::var_os(SOCKET_VAR)?;
+    let args: Vec<String> = match env::args_os().map(|arg| arg.into_string()).collect() {
+        Ok(args) => args,
+        Err(_) => return None,
+    };
+    if starts_daemon(&args) || reads_stdin(&args) {
+        return None;
+    }
+    let cwd = env::current_dir().ok()?.into_os_string().into_string().ok()?;
+    let mut stream = UnixStream::connect(&socket).ok()?;
+
+    let vars = env::vars_os().filter_map(|(name, value)| {
+        match (name.into_string(), value.into_string()) {
+            (Ok(name), Ok(value)) => {
+                Some(Json::Array(vec![Json::String(name), Json::String(value)]))
+            }
+            _ => None,
+        }
+    }).collect();
+    let mut request = BTreeMap::new();
+    request.insert("args".to_string(),
+                   Json::Array(args.into_iter().map(Json::String).collect()));
+    request.insert("cwd".to_string(), Json::String(cwd));
+    request.insert("env".to_string(), Json::Array(vars));
+    let (version, sysroot) = compiler_identity();
+    request.insert("version".to_string(), version);
+    request.insert("sysroot".to_string(), sysroot);
+    if writeln!(stream, "{}", Json::Object(request)).is_err() {
+        return None;
+    }
+
+    for line in BufReader::new(stream).lines() {
+        let message = match line.ok().and_then(|line| Json::from_str(&line).ok()) {
+            Some(message) => message,
+            None => break,
+        };
+        if let Some(reason) = message.find("refused").and_then(|r| r.as_string()) {
+            debug!("the rustc daemon refused to compile: {}", reason);
+            return None;
+        } else if let Some(text) = message.find("stdout").and_then(|text| text.as_string()) {
+            print!("{}", text);
+        } else if let Some(text) = message.find("stderr").and_then(|text| text.as_string()) {
+            eprint!("{}", text);
+        } else if let Some(code) = message.find("exit_code").and_then(|code| code.as_i64()) {
+            let _ = io::stdout().flush();
+            return Some(code as i32);
+        }
+    }
+
+    eprintln!("error: lost the connection to the rustc daemon at `{}`",
+              Path::new(&socket).display());
+    Some(101)
+}
@@ -64,6 +64,23 @@ use pretty::ReplaceBodyWithLoop;
 
 use profile;
 
+/// Writes the trace of `-Z self-profile` when dropped, so that it is written
+/// however compilation ends, including when it stops early on an error.
+struct SelfProfileWriter<'a>(&'a Session);
+
+impl<'a> Drop for SelfProfileWriter<'a> {
+    fn drop(&mut self) {
+        let sess = self.0;
+        if let Some(ref path) = sess.opts.debugging_opts.self_profile {
+            if let Some(ref profiler) = sess.self_profiling {
+                if let Err(e) = profiler.write_chrome_trace(Path::new(path)) {
+                    sess.err(&format!("could not write self-profile to `{}`: {}", path, e));
+                }
+            }
+        }
+    }
+}
+
 pub fn compile_input(trans: Box<TransCrate>,
                      sess: &Session,
                      cstore: &CStore,
@@ -89,6 +106,10 @@ pub fn compile_input(trans: Box<TransCrate>,
         }}
     }
 
+    // Declared first so that it is dropped last, once everything else that
+    // records events is gone.
+    let _self_profile = SelfProfileWriter(sess);
+
     if sess.profile_queries() {
         profile::begin(sess);
     }
@@ -270,7 +291,20 @@ pub fn compile_input(trans: Box<TransCrate>,
     };
 
     if sess.opts.debugging_opts.print_type_sizes {
-        sess.code_stats.borrow().print_type_sizes();
+        if sess.opts.debugging_opts.print_type_sizes_json {
+            sess.code_stats.borrow().print_type_sizes_json();
+        } else {
+            sess.code_stats.borrow().print_type_sizes();
+        }
+    }
+
+    if sess.opts.debugging_opts.time_items {
+        let item_times = sess.item_times.borrow();
+        if sess.opts.debugging_opts.time_items_json {
+            item_times.print_json();
+        } else {
+            item_times.print_report(sess.opts.debugging_opts.time_items_limit);
+        }
     }
 
     trans.join_trans_and_link(ongoing_trans, sess, &dep_graph, &outputs)?;
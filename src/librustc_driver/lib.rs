@@ -111,6 +111,8 @@ mod test;
 pub mod profile;
 pub mod driver;
 pub mod pretty;
+#[cfg(unix)]
+mod daemon;
 mod derive_registrar;
 
 pub mod target_features {
@@ -474,6 +476,10 @@ fn run_compiler_impl<'a>(args: &[String],
 
     let (sopts, cfg) = config::build_session_options_and_crate_config(&matches);
 
+    if let Some(ref socket) = sopts.debugging_opts.daemon {
+        return (serve_daemon(Path::new(socket)), None);
+    }
+
     let descriptions = diagnostics_registry();
 
     do_or_return!(callbacks.early_callback(&matches,
@@ -547,6 +553,26 @@ fn run_compiler_impl<'a>(args: &[String],
     (result, Some(sess))
 }
 
+#[cfg(unix)]
+fn serve_daemon(socket: &Path) -> CompileResult {
+    daemon::serve(socket)
+}
+
+#[cfg(not(unix))]
+fn serve_daemon(_: &Path) -> CompileResult {
+    early_error(ErrorOutputType::default(), "`-Z daemon` is only supported on Unix")
+}
+
+#[cfg(unix)]
+fn run_as_daemon_client() -> Option<i32> {
+    daemon::run_as_client()
+}
+
+#[cfg(not(unix))]
+fn run_as_daemon_client() -> Option<i32> {
+    None
+}
+
 // Extract output directory and file from matches.
 fn make_output(matches: &getopts::Matches) -> (Option<PathBuf>, Option<PathBuf>) {
     let odir = matches.opt_str("out-dir").map(|o| PathBuf::from(&o));
@@ -815,6 +841,20 @@ impl<'a> CompilerCalls<'a> for RustcDefaultCalls {
                     describe_lints(&sess, &ls, false);
                     return None;
                 }
+                if sopts.debugging_opts.incremental_cache_report {
+                    let incr_dir = match sopts.incremental {
+                        Some(ref incr_dir) => incr_dir,
+                        None => early_error(sopts.error_format,
+                                            "`-Z incremental-cache-report` requires \
+                                             `-C incremental`"),
+                    };
+                    if let Err(err) = rustc_incremental::print_cache_report(&sess, incr_dir) {
+                        early_error(sopts.error_format,
+                                    &format!("could not read the incremental compilation \
+                                              directory `{}`: {}", incr_dir.display(), err));
+                    }
+                    return None;
+                }
                 rustc_lint::register_builtins(&mut sess.lint_store.borrow_mut(), Some(&sess));
                 let mut cfg = config::build_configuration(&sess, cfg.clone());
                 let trans = get_trans(&sess);
@@ -1662,6 +1702,9 @@ pub fn init_rustc_env_logger() {
 
 pub fn main() {
     init_rustc_env_logger();
+    if let Some(exit_code) = run_as_daemon_client() {
+        process::exit(exit_code);
+    }
     let result = run(|| {
         let args = env::args_os().enumerate()
             .map(|(i, arg)| arg.into_string().unwrap_or_else(|arg| {
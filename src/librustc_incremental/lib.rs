@@ -41,6 +41,8 @@ pub use persist::save_trans_partition;
 pub use persist::save_work_products;
 pub use persist::in_incr_comp_dir;
 pub use persist::prepare_session_directory;
+pub use persist::keep_state_in_current_thread;
 pub use persist::finalize_session_directory;
 pub use persist::delete_workproduct_files;
 pub use persist::garbage_collect_session_directories;
+pub use persist::print_cache_report;
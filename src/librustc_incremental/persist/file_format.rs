@@ -18,29 +18,37 @@
 //! Since we can always just ignore the incremental compilation cache and
 //! compiler versions don't change frequently for the typical user, being
 //! conservative here practically has no downside.
+//!
+//! The header also records whether the paths of source files were made
+//! relative to the root given with `-Z incremental-path-root` when the file was
+//! generated. The file only stores hashes of the paths, so it can be used from
+//! wherever the root is, but only by a compiler session that makes the paths
+//! relative to a root too, and vice versa.
 
 use std::io::{self, Read};
 use std::path::Path;
 use std::fs;
 use std::env;
 
+use rustc::session::Session;
 use rustc::session::config::nightly_options;
 
 /// The first few bytes of files generated by incremental compilation
 const FILE_MAGIC: &'static [u8] = b"RSIC";
 
 /// Change this if the header format changes
-const HEADER_FORMAT_VERSION: u16 = 0;
+const HEADER_FORMAT_VERSION: u16 = 1;
 
 /// A version string that hopefully is always different for compiler versions
 /// with different encodings of incremental compilation artifacts. Contains
 /// the git commit hash.
 const RUSTC_VERSION: Option<&'static str> = option_env!("CFG_VERSION");
 
-pub fn write_file_header<W: io::Write>(stream: &mut W) -> io::Result<()> {
+pub fn write_file_header<W: io::Write>(stream: &mut W, relative_paths: bool) -> io::Result<()> {
     stream.write_all(FILE_MAGIC)?;
     stream.write_all(&[(HEADER_FORMAT_VERSION >> 0) as u8,
                        (HEADER_FORMAT_VERSION >> 8) as u8])?;
+    stream.write_all(&[relative_paths as u8])?;
 
     let rustc_version = rustc_version();
     assert_eq!(rustc_version.len(), (rustc_version.len() as u8) as usize);
@@ -56,10 +64,11 @@ pub fn write_file_header<W: io::Write>(stream: &mut W) -> io::Result<()> {
 ///   compatible compiler version. `data` is the entire contents of the file
 ///   and `pos` points to the first byte after the header.
 /// - Returns `Ok(None)` if the file did not exist or was generated by an
-///   incompatible version of the compiler.
+///   incompatible version of the compiler, or with paths relative to a root
+///   when `relative_paths` is false, or the other way around.
 /// - Returns `Err(..)` if some kind of IO error occurred while reading the
 ///   file.
-pub fn read_file(report_incremental_info: bool, path: &Path)
+pub fn read_file(report_incremental_info: bool, path: &Path, relative_paths: bool)
     -> io::Result<Option<(Vec<u8>, usize)>>
 {
     if !path.exists() {
@@ -95,6 +104,22 @@ pub fn read_file(report_incremental_info: bool, path: &Path)
         }
     }
 
+    // Check whether the paths are relative to a root
+    {
+        let mut file_relative_paths = [0u8; 1];
+        file.read_exact(&mut file_relative_paths)?;
+
+        if file_relative_paths[0] != relative_paths as u8 {
+            let message = if relative_paths {
+                "Generated without `-Z incremental-path-root`"
+            } else {
+                "Generated with `-Z incremental-path-root`"
+            };
+            report_format_mismatch(report_incremental_info, path, message);
+            return Ok(None)
+        }
+    }
+
     // Check RUSTC_VERSION
     {
         let mut rustc_version_str_len = [0u8; 1];
@@ -114,6 +139,12 @@ pub fn read_file(report_incremental_info: bool, path: &Path)
     Ok(Some((file.into_inner(), post_header_start_pos)))
 }
 
+/// Whether the paths of source files are made relative to a root in the
+/// files generated by this session.
+pub fn relative_paths(sess: &Session) -> bool {
+    sess.opts.debugging_opts.incremental_path_root.is_some()
+}
+
 fn report_format_mismatch(report_incremental_info: bool, file: &Path, message: &str) {
     debug!("read_file: {}", message);
 
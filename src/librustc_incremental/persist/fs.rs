@@ -61,6 +61,19 @@
 //! any more and will delete those. It will also delete any finalized session
 //! directories for a given crate except for the most recent one.
 //!
+//! This only ever looks at the crate being compiled, so the caches of crates
+//! that are not compiled any more are kept forever. The
+//! `-Z incremental-cache-size-limit` and `-Z incremental-cache-max-age`
+//! options make the compiler also look at the other crates in the incremental
+//! compilation directory once a session is finalized, and delete the session
+//! directories that were last used too long ago, and then the least recently
+//! used ones until the whole directory is small enough. The work products of a
+//! session are files in its directory, so they go with it. Since a finalized
+//! session directory is never modified, the timestamp in its name is when its
+//! crate was last compiled, and thus when it was last used.
+//! `-Z incremental-cache-report` prints what would be deleted without
+//! deleting anything.
+//!
 //! ## Synchronization
 //!
 //! There is some synchronization needed in order for the compiler to be able to
@@ -384,6 +397,7 @@ pub fn finalize_session_directory(sess: &Session, svh: Svh) {
     }
 
     let _ = garbage_collect_session_directories(sess);
+    let _ = enforce_cache_limits(sess);
 }
 
 pub fn delete_all_session_dir_contents(sess: &Session) -> io::Result<()> {
@@ -879,6 +893,307 @@ fn all_except_most_recent(deletion_candidates: Vec<(SystemTime, PathBuf, Option<
     }
 }
 
+/// A session directory of any crate in the incremental compilation directory.
+struct CacheSession {
+    path: PathBuf,
+    /// The name of the crate directory the session directory is in.
+    crate_dir_name: String,
+    timestamp: SystemTime,
+    finalized: bool,
+    /// The total size of the files in the directory, in bytes.
+    size: u64,
+    /// The number of files in the directory that are work products, rather
+    /// than the dep-graph, the work product index or the query cache.
+    work_products: usize,
+}
+
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+enum EvictionReason {
+    /// There is a more recent finalized session of the same crate.
+    Superseded,
+    /// The session was last used before `-Z incremental-cache-max-age`.
+    TooOld,
+    /// The cache is larger than `-Z incremental-cache-size-limit`.
+    OverSizeLimit,
+}
+
+impl EvictionReason {
+    fn description(&self) -> &'static str {
+        match *self {
+            EvictionReason::Superseded => "superseded",
+            EvictionReason::TooOld => "max age",
+            EvictionReason::OverSizeLimit => "size limit",
+        }
+    }
+}
+
+fn cache_limits(sess: &Session) -> (Option<u64>, Option<Duration>) {
+    let max_size = sess.opts.debugging_opts.incremental_cache_size_limit
+                       .map(|megabytes| megabytes as u64 * 1024 * 1024);
+    let max_age = sess.opts.debugging_opts.incremental_cache_max_age
+                      .map(|days| Duration::from_secs(days as u64 * 24 * 60 * 60));
+    (max_size, max_age)
+}
+
+/// Deletes session directories of any crate in the incremental compilation
+/// directory, so that it stays within `-Z incremental-cache-size-limit` and
+/// `-Z incremental-cache-max-age`. The session directory of the current
+/// compilation session is never deleted.
+fn enforce_cache_limits(sess: &Session) -> io::Result<()> {
+    let (max_size, max_age) = cache_limits(sess);
+    if max_size.is_none() && max_age.is_none() {
+        return Ok(())
+    }
+
+    // The session directory has been canonicalized, so this gives the paths
+    // of the other session directories in the same form.
+    let session_directory = sess.incr_comp_session_dir().clone();
+    let incr_comp_directory = session_directory.parent().unwrap().parent().unwrap();
+    debug!("enforce_cache_limits() - incremental compilation directory: {}",
+           incr_comp_directory.display());
+
+    let sessions = collect_cache_sessions(incr_comp_directory)?;
+    let eviction = plan_cache_eviction(&sessions,
+                                       max_size,
+                                       max_age,
+                                       SystemTime::now(),
+                                       Some(session_directory.as_path()));
+
+    for (session, reason) in sessions.iter().zip(eviction) {
+        let reason = match reason {
+            Some(reason) => reason,
+            None => continue,
+        };
+
+        // Just like in `garbage_collect_session_directories()`, the exclusive
+        // lock makes sure that nobody is copying from the directory.
+        let lock_file_path = lock_file_path(&session.path);
+        let lock = match flock::Lock::new(&lock_file_path,
+                                          false,  // don't wait
+                                          false,  // don't create the lock-file
+                                          true) { // get an exclusive lock
+            Ok(lock) => lock,
+            Err(_) => {
+                debug!("enforce_cache_limits() - not collecting `{}`, still in use",
+                       session.path.display());
+                continue
+            }
+        };
+
+        debug!("enforce_cache_limits() - deleting `{}` ({})",
+               session.path.display(),
+               reason.description());
+
+        if let Err(err) = safe_remove_dir_all(&session.path) {
+            sess.warn(&format!("Failed to garbage collect incremental \
+                                compilation session directory `{}`: {}",
+                               session.path.display(),
+                               err));
+        } else {
+            delete_session_dir_lock_file(sess, &lock_file_path);
+        }
+
+        mem::drop(lock);
+    }
+
+    Ok(())
+}
+
+/// Prints the session directories stored in the incremental compilation
+/// directory `incr_comp_directory`, and which of them the limits given on the
+/// command line would delete. This is `-Z incremental-cache-report`, which
+/// does not delete anything.
+pub fn print_cache_report(sess: &Session, incr_comp_directory: &Path) -> io::Result<()> {
+    let (max_size, max_age) = cache_limits(sess);
+    let now = SystemTime::now();
+
+    let mut sessions = collect_cache_sessions(incr_comp_directory)?;
+    sessions.sort_by(|a, b| {
+        (&a.crate_dir_name, a.timestamp).cmp(&(&b.crate_dir_name, b.timestamp))
+    });
+    let eviction = plan_cache_eviction(&sessions, max_size, max_age, now, None);
+
+    println!("incremental-cache: {:>12} {:>13} {:>9}  {:<20} session",
+             "bytes", "work products", "last used", "action");
+
+    let mut stored = 0;
+    let mut deleted = 0;
+    for (session, reason) in sessions.iter().zip(eviction) {
+        let action = match reason {
+            Some(reason) => {
+                deleted += session.size;
+                format!("delete ({})", reason.description())
+            }
+            None if session.finalized => "keep".to_string(),
+            None => "keep (working)".to_string(),
+        };
+        stored += session.size;
+
+        let age = now.duration_since(session.timestamp).unwrap_or(Duration::from_secs(0));
+        println!("incremental-cache: {:>12} {:>13} {:>9}  {:<20} {}/{}",
+                 session.size,
+                 session.work_products,
+                 format_age(age),
+                 action,
+                 session.crate_dir_name,
+                 session.path.file_name().unwrap().to_string_lossy());
+    }
+
+    println!("incremental-cache: stored {} bytes, would delete {} bytes", stored, deleted);
+    Ok(())
+}
+
+fn format_age(age: Duration) -> String {
+    let secs = age.as_secs();
+    if secs >= 24 * 60 * 60 {
+        format!("{}d", secs / (24 * 60 * 60))
+    } else if secs >= 60 * 60 {
+        format!("{}h", secs / (60 * 60))
+    } else if secs >= 60 {
+        format!("{}m", secs / 60)
+    } else {
+        format!("{}s", secs)
+    }
+}
+
+/// Finds the session directories of all crates in the incremental compilation
+/// directory. Entries that cannot be read are left out.
+fn collect_cache_sessions(incr_comp_directory: &Path) -> io::Result<Vec<CacheSession>> {
+    let mut sessions = vec![];
+
+    for crate_dir_entry in incr_comp_directory.read_dir()? {
+        let crate_dir_entry = match crate_dir_entry {
+            Ok(crate_dir_entry) => crate_dir_entry,
+            Err(_) => continue,
+        };
+        let crate_dir_name = crate_dir_entry.file_name().to_string_lossy().into_owned();
+        let session_dir_entries = match crate_dir_entry.path().read_dir() {
+            Ok(session_dir_entries) => session_dir_entries,
+            // Not a directory, or one we cannot read; leave it alone
+            Err(_) => continue,
+        };
+
+        for dir_entry in session_dir_entries {
+            let dir_entry = match dir_entry {
+                Ok(dir_entry) => dir_entry,
+                Err(_) => continue,
+            };
+
+            let directory_name = dir_entry.file_name();
+            let directory_name = directory_name.to_string_lossy();
+            let timestamp = match extract_timestamp_from_session_dir(&directory_name) {
+                Ok(timestamp) => timestamp,
+                Err(()) => continue,
+            };
+
+            let path = dir_entry.path();
+            let (size, work_products) = session_dir_contents(&path);
+            sessions.push(CacheSession {
+                path,
+                crate_dir_name: crate_dir_name.clone(),
+                timestamp,
+                finalized: is_finalized(&directory_name),
+                size,
+                work_products,
+            });
+        }
+    }
+
+    Ok(sessions)
+}
+
+/// Returns the total size of the files in a session directory, and how many
+/// of them are work products.
+fn session_dir_contents(session_dir: &Path) -> (u64, usize) {
+    let mut size = 0;
+    let mut work_products = 0;
+
+    if let Ok(dir_entries) = session_dir.read_dir() {
+        for dir_entry in dir_entries.filter_map(|e| e.ok()) {
+            if let Ok(metadata) = dir_entry.metadata() {
+                size += metadata.len();
+            }
+
+            let file_name = dir_entry.file_name();
+            let file_name = file_name.to_string_lossy();
+            if file_name != DEP_GRAPH_FILENAME &&
+               file_name != WORK_PRODUCTS_FILENAME &&
+               file_name != QUERY_CACHE_FILENAME {
+                work_products += 1;
+            }
+        }
+    }
+
+    (size, work_products)
+}
+
+/// Decides which of `sessions` to delete, and why: the finalized sessions
+/// that are not the most recent one of their crate, the ones last used more
+/// than `max_age` ago, and then the least recently used ones until the
+/// remaining sessions take up no more than `max_size` bytes. Sessions that
+/// are not finalized yet, and `protected`, are never deleted.
+fn plan_cache_eviction(sessions: &[CacheSession],
+                       max_size: Option<u64>,
+                       max_age: Option<Duration>,
+                       now: SystemTime,
+                       protected: Option<&Path>)
+                       -> Vec<Option<EvictionReason>> {
+    let mut eviction = vec![None; sessions.len()];
+
+    let mut most_recent: FxHashMap<String, SystemTime> = FxHashMap();
+    for session in sessions.iter().filter(|session| session.finalized) {
+        let timestamp = most_recent.entry(session.crate_dir_name.clone())
+                                   .or_insert(session.timestamp);
+        if session.timestamp > *timestamp {
+            *timestamp = session.timestamp;
+        }
+    }
+
+    let can_be_deleted = |session: &CacheSession| {
+        session.finalized && Some(session.path.as_path()) != protected
+    };
+
+    for (session, reason) in sessions.iter().zip(eviction.iter_mut()) {
+        if !can_be_deleted(session) {
+            continue
+        }
+
+        if session.timestamp < most_recent[&session.crate_dir_name] {
+            *reason = Some(EvictionReason::Superseded);
+        } else if let Some(max_age) = max_age {
+            match now.duration_since(session.timestamp) {
+                Ok(age) if age > max_age => *reason = Some(EvictionReason::TooOld),
+                _ => {}
+            }
+        }
+    }
+
+    if let Some(max_size) = max_size {
+        let mut size: u64 = sessions.iter()
+                                    .zip(&eviction)
+                                    .filter(|&(_, reason)| reason.is_none())
+                                    .map(|(session, _)| session.size)
+                                    .sum();
+
+        let mut least_recently_used: Vec<usize> =
+            (0 .. sessions.len()).filter(|&i| {
+                                     eviction[i].is_none() && can_be_deleted(&sessions[i])
+                                 })
+                                 .collect();
+        least_recently_used.sort_by_key(|&i| sessions[i].timestamp);
+
+        for i in least_recently_used {
+            if size <= max_size {
+                break
+            }
+            eviction[i] = Some(EvictionReason::OverSizeLimit);
+            size -= sessions[i].size;
+        }
+    }
+
+    eviction
+}
+
 /// Since paths of artifacts within session directories can get quite long, we
 /// need to support deleting files with very long paths. The regular
 /// WinApi functions only support paths up to 260 characters, however. In order
@@ -966,3 +1281,59 @@ fn test_find_source_directory_in_iter() {
              PathBuf::from("crate-dir/s-1234-0000-working")].into_iter(), &already_visited),
         None);
 }
+
+#[test]
+fn test_plan_cache_eviction() {
+    fn session(crate_dir_name: &str, timestamp: u64, finalized: bool) -> CacheSession {
+        CacheSession {
+            path: PathBuf::from(format!("{}/s-{}", crate_dir_name, timestamp)),
+            crate_dir_name: crate_dir_name.to_string(),
+            timestamp: UNIX_EPOCH + Duration::new(timestamp, 0),
+            finalized,
+            size: 10,
+            work_products: 1,
+        }
+    }
+
+    let sessions = vec![
+        session("a", 1, true),
+        session("a", 4, true),
+        session("b", 2, true),
+        session("c", 3, false),
+        session("d", 5, true),
+    ];
+    let now = UNIX_EPOCH + Duration::new(6, 0);
+
+    // Without limits, only superseded sessions are deleted
+    assert_eq!(plan_cache_eviction(&sessions, None, None, now, None),
+               vec![Some(EvictionReason::Superseded), None, None, None, None]);
+
+    // Sessions that are not finalized are never too old
+    assert_eq!(plan_cache_eviction(&sessions, None, Some(Duration::new(2, 0)), now, None),
+               vec![Some(EvictionReason::Superseded),
+                    None,
+                    Some(EvictionReason::TooOld),
+                    None,
+                    None]);
+
+    // The least recently used sessions go first, and the protected one is
+    // kept even though the cache is still too large
+    assert_eq!(plan_cache_eviction(&sessions,
+                                   Some(15),
+                                   None,
+                                   now,
+                                   Some(Path::new("a/s-4"))),
+               vec![Some(EvictionReason::Superseded),
+                    None,
+                    Some(EvictionReason::OverSizeLimit),
+                    None,
+                    Some(EvictionReason::OverSizeLimit)]);
+
+    // Nothing more is deleted once the cache is small enough
+    assert_eq!(plan_cache_eviction(&sessions, Some(30), None, now, None),
+               vec![Some(EvictionReason::Superseded),
+                    None,
+                    Some(EvictionReason::OverSizeLimit),
+                    None,
+                    None]);
+}
@@ -0,0 +1,149 @@
+// Copyright 2018 The Rust Project Developers. See the COPYRIGHT
+// file at the top-level directory of this distribution and at
+// http://rust-lang.org/COPYRIGHT.
+//
+// Licensed under the Apache License, Version 2.0 <LICENSE-APACHE or
+// http://www.apache.org/licenses/LICENSE-2.0> or the MIT license
+// <LICENSE-MIT or http://opensource.org/licenses/MIT>, at your
+// option. This file may not be copied, modified, or distributed
+// except according to those terms.
+
+//! The incremental compilation state that the sessions of a thread keep in
+//! memory for the next session of the same crate, so that it is not read and
+//! decoded from disk again. This is meant for the compiler daemon, which runs
+//! many sessions in one thread.
+//!
+//! What a session saves is kept along with the stamp of the file it saved it
+//! to. The next session of the crate finds the same file, hard-linked, in its
+//! own session directory, and only uses what was kept if the file is still
+//! the one that was saved. Since that file is what would be loaded otherwise,
+//! using what was kept gives the same state as loading the file.
+
+use rustc::dep_graph::SerializedDepGraph;
+use rustc::util::fs::{contents_hash, FileStamp};
+use rustc_data_structures::fx::FxHashMap;
+use std::cell::RefCell;
+use std::fs;
+use std::path::{Path, PathBuf};
+
+/// A dep-graph, along with the hash of the command line it was built with.
+pub type KeptDepGraph = (u64, SerializedDepGraph);
+
+/// The contents of a file, and the position of the first byte after its
+/// header.
+pub type KeptBytes = (Vec<u8>, usize);
+
+struct Kept<T> {
+    stamp: FileStamp,
+    /// The hash of the file, if its stamp cannot tell whether it changed.
+    hash: Option<u128>,
+    /// Whether the paths in the file are relative to a root.
+    relative_paths: bool,
+    data: T,
+}
+
+#[derive(Default)]
+struct KeptState {
+    dep_graphs: FxHashMap<PathBuf, Kept<KeptDepGraph>>,
+    query_caches: FxHashMap<PathBuf, Kept<KeptBytes>>,
+}
+
+thread_local! {
+    static KEPT_STATE: RefCell<Option<KeptState>> = RefCell::new(None)
+}
+
+/// Makes the sessions run by the current thread keep the dep-graph and the
+/// query result cache they save in memory for the next session of the same
+/// crate.
+pub fn keep_state_in_current_thread() {
+    KEPT_STATE.with(|state| {
+        let mut state = state.borrow_mut();
+        if state.is_none() {
+            *state = Some(KeptState::default());
+        }
+    });
+}
+
+pub fn keep_dep_graph(path: &Path, contents: &[u8], relative_paths: bool, data: KeptDepGraph) {
+    KEPT_STATE.with(|state| {
+        if let Some(ref mut state) = *state.borrow_mut() {
+            if let Some((key, stamp, hash)) = stamp(path, contents) {
+                state.dep_graphs.insert(key, Kept { stamp, hash, relative_paths, data });
+            }
+        }
+    })
+}
+
+pub fn take_dep_graph(path: &Path, relative_paths: bool) -> Option<KeptDepGraph> {
+    KEPT_STATE.with(|state| {
+        match *state.borrow_mut() {
+            Some(ref mut state) => take(&mut state.dep_graphs, path, relative_paths),
+            None => None,
+        }
+    })
+}
+
+pub fn keep_query_cache(path: &Path, relative_paths: bool, data: KeptBytes) {
+    KEPT_STATE.with(|state| {
+        if let Some(ref mut state) = *state.borrow_mut() {
+            if let Some((key, stamp, hash)) = stamp(path, &data.0) {
+                state.query_caches.insert(key, Kept { stamp, hash, relative_paths, data });
+            }
+        }
+    })
+}
+
+pub fn take_query_cache(path: &Path, relative_paths: bool) -> Option<KeptBytes> {
+    KEPT_STATE.with(|state| {
+        match *state.borrow_mut() {
+            Some(ref mut state) => take(&mut state.query_caches, path, relative_paths),
+            None => None,
+        }
+    })
+}
+
+/// What is kept for a file of a session directory is found by the directory
+/// of the crate and the name of the file, as every session of a crate has a
+/// directory of its own.
+fn key(path: &Path) -> Option<PathBuf> {
+    let crate_dir = path.parent()?.parent()?;
+    Some(crate_dir.join(path.file_name()?))
+}
+
+/// The key, the stamp and, if needed, the hash of the file at `path`, which
+/// was just written with `contents`.
+fn stamp(path: &Path, contents: &[u8]) -> Option<(PathBuf, FileStamp, Option<u128>)> {
+    let stamp = FileStamp::of(path).ok()?;
+    // A file that was just written can only be told apart from a later
+    // version by its stamp once enough time has passed.
+    let hash = if stamp.is_settled() {
+        None
+    } else {
+        Some(contents_hash(contents))
+    };
+    Some((key(path)?, stamp, hash))
+}
+
+/// Takes what is kept for the file at `path`, if it is still the file it was
+/// kept for. A session saves new state, so the old one is not kept any more.
+fn take<T>(kept: &mut FxHashMap<PathBuf, Kept<T>>,
+           path: &Path,
+           relative_paths: bool)
+           -> Option<T> {
+    let entry = kept.remove(&key(path)?)?;
+    if entry.relative_paths != relative_paths {
+        return None;
+    }
+    let stamp = FileStamp::of(path).ok()?;
+    if !entry.stamp.same_metadata(&stamp) {
+        debug!("take: `{}` changed since it was saved", path.display());
+        return None;
+    }
+    if let Some(hash) = entry.hash {
+        if contents_hash(&fs::read(path).ok()?) != hash {
+            debug!("take: `{}` changed since it was saved", path.display());
+            return None;
+        }
+    }
+    Some(entry.data)
+}
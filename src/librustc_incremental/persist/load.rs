@@ -23,6 +23,7 @@ use std;
 use super::data::*;
 use super::fs::*;
 use super::file_format;
+use super::in_memory;
 use super::work_product;
 
 pub fn dep_graph_tcx_init<'a, 'tcx>(tcx: TyCtxt<'a, 'tcx, 'tcx>) {
@@ -41,7 +42,9 @@ pub fn dep_graph_tcx_init<'a, 'tcx>(tcx: TyCtxt<'a, 'tcx, 'tcx>) {
     }
 
     let work_products_path = work_products_path(tcx.sess);
-    let load_result = load_data(tcx.sess.opts.debugging_opts.incremental_info, &work_products_path);
+    let load_result = load_data(tcx.sess.opts.debugging_opts.incremental_info,
+                                &work_products_path,
+                                file_format::relative_paths(tcx.sess));
 
     if let LoadResult::Ok { data: (work_products_data, start_pos) } = load_result {
         // Decode the list of work_products
@@ -106,14 +109,17 @@ impl LoadResult<PreviousDepGraph> {
 }
 
 
-fn load_data(report_incremental_info: bool, path: &Path) -> LoadResult<(Vec<u8>, usize)> {
-    match file_format::read_file(report_incremental_info, path) {
+fn load_data(report_incremental_info: bool,
+             path: &Path,
+             relative_paths: bool)
+             -> LoadResult<(Vec<u8>, usize)> {
+    match file_format::read_file(report_incremental_info, path, relative_paths) {
         Ok(Some(data_and_pos)) => LoadResult::Ok {
             data: data_and_pos
         },
         Ok(None) => {
             // The file either didn't exist or was produced by an incompatible
-            // compiler version. Neither is an error.
+            // compiler version or path root setting. Neither is an error.
             LoadResult::DataOutOfDate
         }
         Err(err) => {
@@ -167,11 +173,21 @@ pub fn load_dep_graph(sess: &Session) ->
     // Fortunately, we just checked that this isn't the case.
     let path = dep_graph_path_from(&sess.incr_comp_session_dir());
     let report_incremental_info = sess.opts.debugging_opts.incremental_info;
+    let relative_paths = file_format::relative_paths(sess);
     let expected_hash = sess.opts.dep_tracking_hash();
 
+    // A graph kept in memory since the previous session does not need to be
+    // decoded again. If it was built with other arguments, the file is
+    // loaded and found to be out of date as usual.
+    if let Some((args_hash, dep_graph)) = in_memory::take_dep_graph(&path, relative_paths) {
+        if args_hash == expected_hash {
+            return MaybeAsync::Sync(LoadResult::Ok { data: PreviousDepGraph::new(dep_graph) });
+        }
+    }
+
     MaybeAsync::Async(std::thread::spawn(move || {
         time_ext(time_passes, None, "background load prev dep-graph", move || {
-            match load_data(report_incremental_info, &path) {
+            match load_data(report_incremental_info, &path, relative_paths) {
                 LoadResult::DataOutOfDate => LoadResult::DataOutOfDate,
                 LoadResult::Error { message } => LoadResult::Error { message },
                 LoadResult::Ok { data: (bytes, start_pos) } => {
@@ -208,7 +224,13 @@ pub fn load_query_result_cache<'sess>(sess: &'sess Session) -> OnDiskCache<'sess
         return OnDiskCache::new_empty(sess.codemap());
     }
 
-    match load_data(sess.opts.debugging_opts.incremental_info, &query_cache_path(sess)) {
+    let path = query_cache_path(sess);
+    let relative_paths = file_format::relative_paths(sess);
+    if let Some((bytes, start_pos)) = in_memory::take_query_cache(&path, relative_paths) {
+        return OnDiskCache::new(sess, bytes, start_pos);
+    }
+
+    match load_data(sess.opts.debugging_opts.incremental_info, &path, relative_paths) {
         LoadResult::Ok{ data: (bytes, start_pos) } => OnDiskCache::new(sess, bytes, start_pos),
         _ => OnDiskCache::new_empty(sess.codemap())
     }
@@ -15,6 +15,7 @@
 mod data;
 mod dirty_clean;
 mod fs;
+mod in_memory;
 mod load;
 mod save;
 mod work_product;
@@ -23,7 +24,9 @@ mod file_format;
 pub use self::fs::finalize_session_directory;
 pub use self::fs::garbage_collect_session_directories;
 pub use self::fs::in_incr_comp_dir;
+pub use self::fs::print_cache_report;
 pub use self::fs::prepare_session_directory;
+pub use self::in_memory::keep_state_in_current_thread;
 pub use self::load::dep_graph_tcx_init;
 pub use self::load::load_dep_graph;
 pub use self::load::load_query_result_cache;
@@ -8,7 +8,7 @@
 // option. This file may not be copied, modified, or distributed
 // except according to those terms.
 
-use rustc::dep_graph::{DepGraph, DepKind};
+use rustc::dep_graph::{DepGraph, DepKind, SerializedDepGraph};
 use rustc::session::Session;
 use rustc::ty::TyCtxt;
 use rustc::util::common::time;
@@ -23,6 +23,7 @@ use super::data::*;
 use super::fs::*;
 use super::dirty_clean;
 use super::file_format;
+use super::in_memory;
 use super::work_product;
 
 pub fn save_dep_graph<'a, 'tcx>(tcx: TyCtxt<'a, 'tcx, 'tcx>) {
@@ -33,17 +34,27 @@ pub fn save_dep_graph<'a, 'tcx>(tcx: TyCtxt<'a, 'tcx, 'tcx>) {
             return;
         }
 
+        let relative_paths = file_format::relative_paths(sess);
+
         time(sess, "persist query result cache", || {
-            save_in(sess,
-                    query_cache_path(sess),
-                    |e| encode_query_cache(tcx, e));
+            let path = query_cache_path(sess);
+            if let Some(saved) = save_in(sess, path.clone(), |e| encode_query_cache(tcx, e)) {
+                in_memory::keep_query_cache(&path, relative_paths, saved);
+            }
         });
 
         if tcx.sess.opts.debugging_opts.incremental_queries {
             time(sess, "persist dep-graph", || {
-                save_in(sess,
-                        dep_graph_path(sess),
-                        |e| encode_dep_graph(tcx, e));
+                let path = dep_graph_path(sess);
+                let serialized_graph = tcx.dep_graph.serialize();
+                let saved = save_in(sess,
+                                    path.clone(),
+                                    |e| encode_dep_graph(tcx, &serialized_graph, e));
+                if let Some((contents, _)) = saved {
+                    let args_hash = sess.opts.dep_tracking_hash();
+                    in_memory::keep_dep_graph(&path, &contents, relative_paths,
+                                              (args_hash, serialized_graph));
+                }
             });
         }
 
@@ -87,7 +98,10 @@ pub fn save_work_products(sess: &Session, dep_graph: &DepGraph) {
     });
 }
 
-fn save_in<F>(sess: &Session, path_buf: PathBuf, encode: F)
+/// Writes the data that `encode` encodes to `path_buf`, and returns the
+/// contents of the file and the position of the first byte after its header
+/// if it succeeded.
+fn save_in<F>(sess: &Session, path_buf: PathBuf, encode: F) -> Option<(Vec<u8>, usize)>
     where F: FnOnce(&mut Encoder) -> io::Result<()>
 {
     debug!("save: storing data in {}", path_buf.display());
@@ -105,47 +119,49 @@ fn save_in<F>(sess: &Session, path_buf: PathBuf, encode: F)
                 sess.err(&format!("unable to delete old dep-graph at `{}`: {}",
                                   path_buf.display(),
                                   err));
-                return;
+                return None;
             }
         }
     }
 
     // generate the data in a memory buffer
     let mut wr = Cursor::new(Vec::new());
-    file_format::write_file_header(&mut wr).unwrap();
+    file_format::write_file_header(&mut wr, file_format::relative_paths(sess)).unwrap();
+    let start_pos = wr.position() as usize;
     match encode(&mut Encoder::new(&mut wr)) {
         Ok(()) => {}
         Err(err) => {
             sess.err(&format!("could not encode dep-graph to `{}`: {}",
                               path_buf.display(),
                               err));
-            return;
+            return None;
         }
     }
 
     // write the data out
     let data = wr.into_inner();
-    match fs::write(&path_buf, data) {
+    match fs::write(&path_buf, &data) {
         Ok(_) => {
             debug!("save: data written to disk successfully");
+            Some((data, start_pos))
         }
         Err(err) => {
             sess.err(&format!("failed to write dep-graph to `{}`: {}",
                               path_buf.display(),
                               err));
-            return;
+            None
         }
     }
 }
 
 fn encode_dep_graph(tcx: TyCtxt,
+                    serialized_graph: &SerializedDepGraph,
                     encoder: &mut Encoder)
                     -> io::Result<()> {
     // First encode the commandline arguments hash
     tcx.sess.opts.dep_tracking_hash().encode(encoder)?;
 
     // Encode the graph data.
-    let serialized_graph = tcx.dep_graph.serialize();
 
     if tcx.sess.opts.debugging_opts.incremental_info {
         #[derive(Clone)]
@@ -12,8 +12,28 @@
 //!
 //! A simple wrapper over the platform's dynamic library facilities
 
+use std::cell::RefCell;
 use std::ffi::CString;
-use std::path::Path;
+use std::fs;
+use std::hash::Hasher;
+use std::io;
+use std::path::{Path, PathBuf};
+
+use rustc_data_structures::stable_hasher::StableHasher;
+
+thread_local! {
+    static COPY_DIRECTORY: RefCell<Option<PathBuf>> = RefCell::new(None)
+}
+
+/// Makes `DynamicLibrary::open_copy` load the libraries opened by the current
+/// thread from copies in `dir` named after their contents, or from where they
+/// are again if `dir` is `None`. The dynamic loader gives back the library it
+/// loaded before for the same path, so a process that keeps running while the
+/// libraries it loads are rebuilt, like the compiler daemon, needs this to get
+/// their new code. Unchanged libraries are still only loaded once.
+pub fn load_copies_in_current_thread(dir: Option<PathBuf>) {
+    COPY_DIRECTORY.with(|copy_dir| *copy_dir.borrow_mut() = dir);
+}
 
 pub struct DynamicLibrary {
     handle: *mut u8
@@ -52,6 +72,20 @@ impl DynamicLibrary {
         }
     }
 
+    /// Open a dynamic library like `open`, but from a copy if the current
+    /// thread loads copies of the libraries (see
+    /// `load_copies_in_current_thread`).
+    pub fn open_copy(filename: &Path) -> Result<DynamicLibrary, String> {
+        let copy = COPY_DIRECTORY.with(|dir| {
+            dir.borrow().as_ref().map(|dir| copy_named_after_contents(filename, dir))
+        });
+        match copy {
+            None => DynamicLibrary::open(Some(filename)),
+            Some(Ok(copy)) => DynamicLibrary::open(Some(&copy)),
+            Some(Err(err)) => Err(format!("could not copy `{}`: {}", filename.display(), err)),
+        }
+    }
+
     /// Returns the environment variable for this process's dynamic library
     /// search path
     pub fn envvar() -> &'static str {
@@ -83,6 +117,25 @@ impl DynamicLibrary {
     }
 }
 
+fn copy_named_after_contents(filename: &Path, dir: &Path) -> io::Result<PathBuf> {
+    let contents = fs::read(filename)?;
+    let mut hasher = StableHasher::<u128>::new();
+    hasher.write(&contents);
+    let name = match filename.file_name() {
+        Some(name) => format!("{:032x}-{}", hasher.finish(), name.to_string_lossy()),
+        None => format!("{:032x}", hasher.finish()),
+    };
+    let copy = dir.join(&name);
+    if !copy.exists() {
+        // Written under another name first, so that a copy is never loaded
+        // before it is complete.
+        let partial = dir.join(format!("{}.partial", name));
+        fs::write(&partial, &contents)?;
+        fs::rename(&partial, &copy)?;
+    }
+    Ok(copy)
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -31,10 +31,8 @@ use rustc::ty::codec::{self as ty_codec, TyEncoder};
 use rustc::session::config::{self, CrateTypeProcMacro};
 use rustc::util::nodemap::FxHashMap;
 
-use rustc_data_structures::stable_hasher::StableHasher;
 use rustc_serialize::{Encodable, Encoder, SpecializedEncoder, opaque};
 
-use std::hash::Hash;
 use std::io::prelude::*;
 use std::io::Cursor;
 use std::path::Path;
@@ -345,6 +343,7 @@ impl<'a, 'tcx> EncodeContext<'a, 'tcx> {
         let all_filemaps = codemap.files();
 
         let (working_dir, working_dir_was_remapped) = self.tcx.sess.working_dir.clone();
+        let path_mapping = codemap.path_mapping();
 
         let adapted = all_filemaps.iter()
             .filter(|filemap| {
@@ -371,11 +370,8 @@ impl<'a, 'tcx> EncodeContext<'a, 'tcx> {
                         } else {
                             let mut adapted = (**filemap).clone();
                             adapted.name = Path::new(&working_dir).join(name).into();
-                            adapted.name_hash = {
-                                let mut hasher: StableHasher<u128> = StableHasher::new();
-                                adapted.name.hash(&mut hasher);
-                                hasher.finish()
-                            };
+                            adapted.name_hash =
+                                FileMap::hash_name(&path_mapping.stable_name(&adapted.name));
                             Lrc::new(adapted)
                         }
                     },
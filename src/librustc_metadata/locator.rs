@@ -231,6 +231,7 @@ use rustc::middle::cstore::MetadataLoader;
 use rustc::session::{config, Session};
 use rustc::session::filesearch::{FileSearch, FileMatches, FileDoesntMatch};
 use rustc::session::search_paths::PathKind;
+use rustc::util::fs::{contents_hash, FileStamp};
 use rustc::util::nodemap::FxHashMap;
 
 use errors::DiagnosticBuilder;
@@ -238,6 +239,7 @@ use syntax::symbol::Symbol;
 use syntax_pos::Span;
 use rustc_back::target::{Target, TargetTriple};
 
+use std::cell::RefCell;
 use std::cmp;
 use std::collections::HashSet;
 use std::fmt;
@@ -249,6 +251,8 @@ use std::time::Instant;
 use flate2::read::DeflateDecoder;
 
 use rustc_data_structures::owning_ref::OwningRef;
+use rustc_data_structures::sync::Lrc;
+
 pub struct CrateMismatch {
     path: PathBuf,
     got: String,
@@ -283,7 +287,7 @@ pub struct CratePaths {
     pub rmeta: Option<PathBuf>,
 }
 
-#[derive(Copy, Clone, PartialEq)]
+#[derive(Copy, Clone, PartialEq, Eq, Hash)]
 enum CrateFlavor {
     Rlib,
     Rmeta,
@@ -838,6 +842,36 @@ impl<'a> Context<'a> {
     }
 }
 
+/// The metadata of a library, along with what tells whether the library
+/// changed since it was read.
+struct CachedMetadata {
+    stamp: FileStamp,
+    /// The hash of the library, if its stamp cannot tell whether it changed.
+    hash: Option<u128>,
+    metadata: Lrc<MetadataRef>,
+}
+
+/// The metadata read so far by the sessions of a thread that keeps it for
+/// the next ones.
+type MetadataCache = FxHashMap<(PathBuf, CrateFlavor), CachedMetadata>;
+
+thread_local! {
+    static METADATA_CACHE: RefCell<Option<MetadataCache>> = RefCell::new(None)
+}
+
+/// Makes the sessions run by the current thread keep the metadata of the
+/// libraries they load in memory, so that later sessions do not read and
+/// decompress it again unless the library changed. This is meant for the
+/// compiler daemon, which runs many sessions in one thread.
+pub fn cache_metadata_in_current_thread() {
+    METADATA_CACHE.with(|cache| {
+        let mut cache = cache.borrow_mut();
+        if cache.is_none() {
+            *cache = Some(FxHashMap());
+        }
+    });
+}
+
 // Just a small wrapper to time how long reading metadata takes.
 fn get_metadata_section(target: &Target,
                         flavor: CrateFlavor,
@@ -845,13 +879,66 @@ fn get_metadata_section(target: &Target,
                         loader: &MetadataLoader)
                         -> Result<MetadataBlob, String> {
     let start = Instant::now();
-    let ret = get_metadata_section_imp(target, flavor, filename, loader);
+    let ret = METADATA_CACHE.with(|cache| {
+        match *cache.borrow_mut() {
+            Some(ref mut cache) => {
+                get_cached_metadata_section(cache, target, flavor, filename, loader)
+            }
+            None => get_metadata_section_imp(target, flavor, filename, loader),
+        }
+    });
     info!("reading {:?} => {:?}",
           filename.file_name().unwrap(),
           start.elapsed());
     return ret;
 }
 
+fn get_cached_metadata_section(cache: &mut MetadataCache,
+                               target: &Target,
+                               flavor: CrateFlavor,
+                               filename: &Path,
+                               loader: &MetadataLoader)
+                               -> Result<MetadataBlob, String> {
+    // A library is only read in full when its metadata cannot tell whether
+    // it changed, which is when it was rebuilt shortly before it was cached.
+    let stamp = match FileStamp::of(filename) {
+        Ok(stamp) => stamp,
+        Err(_) => return get_metadata_section_imp(target, flavor, filename, loader),
+    };
+    let key = (filename.to_path_buf(), flavor);
+    let fresh = match cache.get_mut(&key) {
+        Some(cached) => {
+            let unchanged = cached.stamp.same_metadata(&stamp) && match cached.hash {
+                None => true,
+                Some(hash) => fs::read(filename).ok().map(|c| contents_hash(&c)) == Some(hash),
+            };
+            if unchanged && stamp.is_settled() {
+                // Only the metadata of the library is looked at from now on.
+                cached.stamp = stamp;
+                cached.hash = None;
+            }
+            unchanged
+        }
+        None => false,
+    };
+    if !fresh {
+        let hash = if stamp.is_settled() {
+            None
+        } else {
+            match fs::read(filename) {
+                Ok(contents) => Some(contents_hash(&contents)),
+                Err(_) => return get_metadata_section_imp(target, flavor, filename, loader),
+            }
+        };
+        let blob = get_metadata_section_imp(target, flavor, filename, loader)?;
+        cache.insert(key.clone(), CachedMetadata { stamp, hash, metadata: Lrc::new(blob.0) });
+    }
+
+    let bytes = cache[&key].metadata.clone();
+    let buf = OwningRef::new(bytes).map(|bytes| &bytes[..]);
+    Ok(MetadataBlob(rustc_erase_owner!(buf.map_owner_box())))
+}
+
 fn get_metadata_section_imp(target: &Target,
                             flavor: CrateFlavor,
                             filename: &Path,
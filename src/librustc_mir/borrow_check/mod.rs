@@ -15,6 +15,7 @@ use rustc::hir;
 use rustc::hir::def_id::DefId;
 use rustc::hir::map::definitions::DefPathData;
 use rustc::infer::InferCtxt;
+use rustc::session::ItemActivity;
 use rustc::ty::{self, ParamEnv, TyCtxt};
 use rustc::ty::maps::Providers;
 use rustc::mir::{AssertMessage, BasicBlock, BorrowKind, Location, Place};
@@ -74,9 +75,13 @@ fn mir_borrowck<'a, 'tcx>(
         return None;
     }
 
-    let opt_closure_req = tcx.infer_ctxt().enter(|infcx| {
-        let input_mir: &Mir = &input_mir.borrow();
-        do_mir_borrowck(&infcx, input_mir, def_id)
+    let opt_closure_req = tcx.sess.time_item(ItemActivity::Borrowck,
+                                             || tcx.item_path_str(def_id),
+                                             || {
+        tcx.infer_ctxt().enter(|infcx| {
+            let input_mir: &Mir = &input_mir.borrow();
+            do_mir_borrowck(&infcx, input_mir, def_id)
+        })
     });
     debug!("mir_borrowck done");
 
@@ -0,0 +1,96 @@
+// Copyright 2018 The Rust Project Developers. See the COPYRIGHT
+// file at the top-level directory of this distribution and at
+// http://rust-lang.org/COPYRIGHT.
+//
+// Licensed under the Apache License, Version 2.0 <LICENSE-APACHE or
+// http://www.apache.org/licenses/LICENSE-2.0> or the MIT license
+// <LICENSE-MIT or http://opensource.org/licenses/MIT>, at your
+// option. This file may not be copied, modified, or distributed
+// except according to those terms.
+
+
+//! Groups the functions the collector found by the item they are instances
+//! of, for `-Z print-mono-bloat`, to tell which generic items are translated
+//! the most times and end up with the most code.
+
+use monomorphize::item::{DefPathBasedNames, MonoItem};
+use rustc::mir::Mir;
+use rustc::ty::TyCtxt;
+use rustc::util::nodemap::FxHashMap;
+
+use std::cmp::Ordering;
+
+#[derive(Default)]
+struct BloatGroup {
+    instances: usize,
+    /// The number of MIR statements and terminators of all the instances.
+    mir_size: usize,
+    /// The number of LLVM instructions of all the instances, which are counted
+    /// once for each codegen unit they are translated into.
+    llvm_insns: usize,
+}
+
+pub struct MonoBloat {
+    groups: FxHashMap<String, BloatGroup>,
+}
+
+impl MonoBloat {
+    pub fn new<'a, 'b, 'tcx: 'b, I>(tcx: TyCtxt<'a, 'tcx, 'tcx>, items: I) -> MonoBloat
+        where I: Iterator<Item=&'b MonoItem<'tcx>>
+    {
+        let mut groups: FxHashMap<String, BloatGroup> = FxHashMap();
+        let names = DefPathBasedNames::new(tcx, true, true);
+        for item in items {
+            let instance = match *item {
+                MonoItem::Fn(instance) => instance,
+                MonoItem::Static(..) | MonoItem::GlobalAsm(..) => continue,
+            };
+
+            let mut name = String::new();
+            names.push_def_path(instance.def_id(), &mut name);
+            let mir_size = mir_size(tcx.instance_mir(instance.def));
+
+            let group = groups.entry(name).or_insert_with(BloatGroup::default);
+            group.instances += 1;
+            group.mir_size += mir_size;
+        }
+        MonoBloat { groups }
+    }
+
+    /// Adds the LLVM instructions of an instance of `item`, named the way
+    /// `DefPathBasedNames::push_def_path` does without disambiguators and
+    /// local crate name.
+    pub fn add_llvm_insns(&mut self, item: &str, insns: usize) {
+        if let Some(group) = self.groups.get_mut(item) {
+            group.llvm_insns += insns;
+        }
+    }
+
+    /// Prints the items, starting with the ones with the most LLVM
+    /// instructions.
+    pub fn print(&self) {
+        let mut sorted: Vec<_> = self.groups.iter().collect();
+        sorted.sort_by(|&(name1, group1), &(name2, group2)| {
+            let size1 = (group1.llvm_insns, group1.mir_size, group1.instances);
+            let size2 = (group2.llvm_insns, group2.mir_size, group2.instances);
+            match size2.cmp(&size1) {
+                Ordering::Equal => name1.cmp(name2),
+                other => other,
+            }
+        });
+
+        println!("print-mono-bloat: {:>9} {:>9} {:>10}  item",
+                 "instances", "MIR size", "LLVM insns");
+        for &(name, group) in &sorted {
+            println!("print-mono-bloat: {:>9} {:>9} {:>10}  {}",
+                     group.instances, group.mir_size, group.llvm_insns, name);
+        }
+    }
+}
+
+/// The number of statements and terminators of `mir` and of the MIR promoted
+/// out of it.
+fn mir_size(mir: &Mir) -> usize {
+    let own: usize = mir.basic_blocks().iter().map(|block| block.statements.len() + 1).sum();
+    own + mir.promoted.iter().map(mir_size).sum::<usize>()
+}
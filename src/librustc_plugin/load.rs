@@ -120,7 +120,7 @@ impl<'a> PluginLoader<'a> {
         // Make sure the path contains a / or the linker will search for it.
         let path = env::current_dir().unwrap().join(&path);
 
-        let lib = match DynamicLibrary::open(Some(&path)) {
+        let lib = match DynamicLibrary::open_copy(&path) {
             Ok(lib) => lib,
             // this is fatal: there are almost certainly macros we need
             // inside this crate, so continue would spew "macro undefined"
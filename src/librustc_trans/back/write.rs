@@ -33,6 +33,7 @@ use rustc::hir::def_id::{CrateNum, LOCAL_CRATE};
 use rustc::ty::TyCtxt;
 use rustc::util::common::{time_ext, time_depth, set_time_depth, print_time_passes_entry};
 use rustc::util::common::path2cstr;
+use rustc::util::profiling::SelfProfiler;
 use rustc::util::fs::{link_or_copy};
 use errors::{self, Handler, Level, DiagnosticBuilder, FatalError, DiagnosticId};
 use errors::emitter::{Emitter};
@@ -386,6 +387,8 @@ pub struct CodegenContext {
     // A reference to the TimeGraph so we can register timings. None means that
     // measuring is disabled.
     time_graph: Option<TimeGraph>,
+    // The profiler of -Z self-profile, None if it is not enabled.
+    pub self_profiling: Option<Arc<SelfProfiler>>,
     // The assembler command if no_integrated_as option is enabled, None otherwise
     assembler_cmd: Option<Arc<AssemblerCommand>>,
 }
@@ -1498,6 +1501,7 @@ fn start_executing_work(tcx: TyCtxt,
         save_temps: sess.opts.cg.save_temps,
         opts: Arc::new(sess.opts.clone()),
         time_passes: sess.time_passes(),
+        self_profiling: sess.self_profiling.clone(),
         exported_symbols,
         plugin_passes: sess.plugin_llvm_passes.borrow().clone(),
         remark: sess.opts.cg.remark.clone(),
@@ -2019,7 +2023,14 @@ fn spawn_work(cgcx: CodegenContext, work: WorkItem) {
                          &work.name())
             });
             let mut timeline = timeline.unwrap_or(Timeline::noop());
-            execute_work_item(&cgcx, work, &mut timeline).ok()
+            if let Some(ref profiler) = cgcx.self_profiling {
+                profiler.start_activity("LLVM work", &work.name());
+            }
+            let result = execute_work_item(&cgcx, work, &mut timeline).ok();
+            if let Some(ref profiler) = cgcx.self_profiling {
+                profiler.end_activity("LLVM work");
+            }
+            result
         };
     });
 }
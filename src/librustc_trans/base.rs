@@ -56,6 +56,7 @@ use attributes;
 use builder::Builder;
 use callee;
 use common::{C_bool, C_bytes_in_context, C_i32, C_usize};
+use rustc_mir::monomorphize::bloat::MonoBloat;
 use rustc_mir::monomorphize::collector::{self, MonoItemCollectionMode};
 use common::{self, C_struct_in_context, C_array, val_ty};
 use consts;
@@ -113,7 +114,7 @@ impl<'a, 'tcx> StatRecorder<'a, 'tcx> {
 
 impl<'a, 'tcx> Drop for StatRecorder<'a, 'tcx> {
     fn drop(&mut self) {
-        if self.cx.sess().trans_stats() {
+        if self.cx.sess().count_fn_insns() {
             let mut stats = self.cx.stats.borrow_mut();
             let iend = stats.n_llvm_insns;
             stats.fn_stats.push((self.name.take().unwrap(), iend - self.istart));
@@ -449,7 +450,7 @@ pub fn call_memset<'a, 'tcx>(bx: &Builder<'a, 'tcx>,
 }
 
 pub fn trans_instance<'a, 'tcx>(cx: &CodegenCx<'a, 'tcx>, instance: Instance<'tcx>) {
-    let _s = if cx.sess().trans_stats() {
+    let _s = if cx.sess().count_fn_insns() {
         let mut instance_name = String::new();
         DefPathBasedNames::new(cx.tcx, true, true)
             .push_def_path(instance.def_id(), &mut instance_name);
@@ -932,6 +933,19 @@ pub fn trans_crate<'a, 'tcx>(tcx: TyCtxt<'a, 'tcx, 'tcx>,
         }
     }
 
+    if tcx.sess.opts.debugging_opts.print_mono_bloat {
+        let (_, codegen_units) = tcx.collect_and_partition_translation_items(LOCAL_CRATE);
+        // Items inlined into several codegen units are still one instance.
+        let items: FxHashSet<_> = codegen_units.iter()
+            .flat_map(|cgu| cgu.items().keys())
+            .collect();
+        let mut bloat = MonoBloat::new(tcx, items.into_iter());
+        for &(ref name, insns) in &all_stats.fn_stats {
+            bloat.add_llvm_insns(name, insns);
+        }
+        bloat.print();
+    }
+
     ongoing_translation.check_for_errors(tcx.sess);
 
     assert_and_save_dep_graph(tcx);
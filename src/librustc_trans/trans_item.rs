@@ -69,7 +69,13 @@ pub trait MonoItemExt<'a, 'tcx>: fmt::Debug + BaseMonoItemExt<'a, 'tcx> {
                 }
             }
             MonoItem::Fn(instance) => {
-                base::trans_instance(&cx, instance);
+                let tcx = cx.tcx;
+                tcx.sess.time_instance(|| {
+                    let mut name = String::new();
+                    DefPathBasedNames::new(tcx, true, true)
+                        .push_instance_as_string(instance, &mut name);
+                    (tcx.item_path_str(instance.def_id()), name)
+                }, || base::trans_instance(&cx, instance));
             }
         }
 
@@ -104,7 +104,7 @@ use rustc::ty::util::{Representability, IntTypeExt, Discr};
 use errors::{DiagnosticBuilder, DiagnosticId};
 
 use require_c_abi_if_variadic;
-use session::{CompileIncomplete, config, Session};
+use session::{CompileIncomplete, config, ItemActivity, Session};
 use TypeAndSubsts;
 use lint;
 use util::common::{ErrorReported, indenter};
@@ -842,6 +842,14 @@ fn typeck_tables_of<'a, 'tcx>(tcx: TyCtxt<'a, 'tcx, 'tcx>,
         return tcx.typeck_tables_of(outer_def_id);
     }
 
+    tcx.sess.time_item(ItemActivity::TypeCheck,
+                       || tcx.item_path_str(def_id),
+                       || typeck_item_tables(tcx, def_id))
+}
+
+fn typeck_item_tables<'a, 'tcx>(tcx: TyCtxt<'a, 'tcx, 'tcx>,
+                                def_id: DefId)
+                                -> &'tcx ty::TypeckTables<'tcx> {
     let id = tcx.hir.as_local_node_id(def_id).unwrap();
     let span = tcx.hir.span(id);
 
@@ -0,0 +1,592 @@
+// Copyright 2018 The Rust Project Developers. See the COPYRIGHT
+// file at the top-level directory of this distribution and at
+// http://rust-lang.org/COPYRIGHT.
+//
+// Licensed under the Apache License, Version 2.0 <LICENSE-APACHE or
+// http://www.apache.org/licenses/LICENSE-2.0> or the MIT license
+// <LICENSE-MIT or http://opensource.org/licenses/MIT>, at your
+// option. This file may not be copied, modified, or distributed
+// except according to those terms.
+
+//! Comparison of the public API of two versions of a crate, as done by
+//! `--api-diff`.
+//!
+//! Both versions are read from the JSON written by `--output-format json`.
+//! Every public item is keyed by its path, and the items found in only one of
+//! the versions, or whose declaration differs between them, are listed.
+//!
+//! Changes that can break code using the crate are flagged, following RFC
+//! 1105: removing an item or changing its declaration is breaking, adding one
+//! is not, except when code could rely on the item not being there, like a
+//! new variant of an enum or a new required method of a trait. The rules err
+//! on the side of flagging: a change of the bounds of an impl is flagged even
+//! when the bounds only got looser.
+
+use std::collections::{BTreeMap, HashSet};
+use std::fs;
+use std::path::Path;
+
+use serialize::json::{Json, Object, ToJson};
+
+use json::FORMAT_VERSION;
+
+/// What can change about an item without its declaration changing.
+#[derive(Copy, Clone, Default, PartialEq)]
+struct Flags {
+    /// A function that is `const`.
+    is_const: bool,
+    /// An impl of `!Trait`.
+    negative: bool,
+    /// A struct, enum or variant with fields or variants that are not shown.
+    private_fields: bool,
+    /// A trait item that implementors have to provide.
+    required: bool,
+    deprecated: bool,
+}
+
+struct ApiItem {
+    kind: String,
+    /// The key of the item this one is a member of, if any.
+    parent: Option<String>,
+    /// The declaration of the item, as it is printed.
+    signature: String,
+    /// The declaration without the parts described by `flags`.
+    base: String,
+    flags: Flags,
+}
+
+struct Api {
+    version: Option<String>,
+    items: BTreeMap<String, ApiItem>,
+}
+
+#[derive(Copy, Clone, PartialEq, Eq, PartialOrd, Ord)]
+enum ChangeKind {
+    Removed,
+    Added,
+    Changed,
+}
+
+impl ChangeKind {
+    fn as_str(&self) -> &'static str {
+        match *self {
+            ChangeKind::Removed => "removed",
+            ChangeKind::Added => "added",
+            ChangeKind::Changed => "changed",
+        }
+    }
+}
+
+struct Change<'a> {
+    kind: ChangeKind,
+    path: &'a str,
+    old: Option<&'a ApiItem>,
+    new: Option<&'a ApiItem>,
+    breaking: bool,
+}
+
+impl<'a> ToJson for Change<'a> {
+    fn to_json(&self) -> Json {
+        let item = self.new.or(self.old).unwrap();
+        let mut data = BTreeMap::new();
+        data.insert("change".to_owned(), self.kind.as_str().to_json());
+        data.insert("path".to_owned(), self.path.to_json());
+        data.insert("kind".to_owned(), item.kind.to_json());
+        data.insert("old".to_owned(), self.old.map(|i| i.signature.clone()).to_json());
+        data.insert("new".to_owned(), self.new.map(|i| i.signature.clone()).to_json());
+        data.insert("breaking".to_owned(), self.breaking.to_json());
+        Json::Object(data)
+    }
+}
+
+/// Compares the JSON documentation of two versions of a crate, printing the
+/// changes to its public API as text or, if `json` is set, as JSON.
+pub fn run(old: &Path, new: &Path, json: bool) -> Result<(), String> {
+    let old = load(old)?;
+    let new = load(new)?;
+    let changes = diff(&old, &new);
+
+    if json {
+        let mut data = BTreeMap::new();
+        data.insert("old_version".to_owned(), old.version.to_json());
+        data.insert("new_version".to_owned(), new.version.to_json());
+        data.insert("breaking".to_owned(), changes.iter().any(|c| c.breaking).to_json());
+        data.insert("changes".to_owned(), changes.to_json());
+        println!("{}", Json::Object(data));
+        return Ok(());
+    }
+
+    for change in &changes {
+        let marker = match change.kind {
+            ChangeKind::Removed => '-',
+            ChangeKind::Added => '+',
+            ChangeKind::Changed => '~',
+        };
+        let item = change.new.or(change.old).unwrap();
+        let breaking = if change.breaking { " (breaking)" } else { "" };
+        println!("{} {} {}{}", marker, item.kind, change.path, breaking);
+        match (change.old, change.new) {
+            (Some(old), Some(new)) => {
+                println!("    was: {}", old.signature);
+                println!("    now: {}", new.signature);
+            }
+            (old, new) => println!("    {}", old.or(new).unwrap().signature),
+        }
+    }
+    let breaking = changes.iter().filter(|c| c.breaking).count();
+    println!("{} changes, {} of them breaking", changes.len(), breaking);
+    Ok(())
+}
+
+fn diff<'a>(old: &'a Api, new: &'a Api) -> Vec<Change<'a>> {
+    let mut changes = Vec::new();
+    for (path, old_item) in &old.items {
+        match new.items.get(path) {
+            None => {
+                // The removal of an item covers that of its members.
+                let parent_removed = old_item.parent.as_ref().map_or(false, |parent| {
+                    !new.items.contains_key(parent)
+                });
+                if !parent_removed {
+                    changes.push(Change {
+                        kind: ChangeKind::Removed,
+                        path,
+                        old: Some(old_item),
+                        new: None,
+                        breaking: true,
+                    });
+                }
+            }
+            Some(new_item) => {
+                if old_item.kind != new_item.kind || old_item.signature != new_item.signature ||
+                   old_item.flags != new_item.flags {
+                    changes.push(Change {
+                        kind: ChangeKind::Changed,
+                        path,
+                        old: Some(old_item),
+                        new: Some(new_item),
+                        breaking: change_is_breaking(old_item, new_item),
+                    });
+                }
+            }
+        }
+    }
+    for (path, new_item) in &new.items {
+        if old.items.contains_key(path) {
+            continue;
+        }
+        // Likewise, the addition of an item covers that of its members.
+        let old_parent = match new_item.parent {
+            Some(ref parent) => match old.items.get(parent) {
+                Some(old_parent) => Some(old_parent),
+                None => continue,
+            },
+            None => None,
+        };
+        changes.push(Change {
+            kind: ChangeKind::Added,
+            path,
+            old: None,
+            new: Some(new_item),
+            breaking: old_parent.map_or(false, |p| addition_is_breaking(new_item, p)),
+        });
+    }
+    changes.sort_by(|a, b| (a.path, a.kind).cmp(&(b.path, b.kind)));
+    changes
+}
+
+fn change_is_breaking(old: &ApiItem, new: &ApiItem) -> bool {
+    if old.kind != new.kind {
+        // A required trait method that gains a default is the only change of
+        // kind that breaks no code.
+        return !(old.kind == "tymethod" && new.kind == "method" && old.base == new.base);
+    }
+    old.base != new.base ||
+        (old.flags.is_const && !new.flags.is_const) ||
+        (!old.flags.negative && new.flags.negative) ||
+        (!old.flags.private_fields && new.flags.private_fields) ||
+        (!old.flags.required && new.flags.required)
+}
+
+/// Whether adding `item` to `old_parent`, which existed before, can break
+/// code.
+fn addition_is_breaking(item: &ApiItem, old_parent: &ApiItem) -> bool {
+    match &item.kind[..] {
+        // Existing matches on the enum no longer cover every variant.
+        "variant" => true,
+        // Existing struct expressions and patterns no longer list every field.
+        "structfield" => {
+            (old_parent.kind == "struct" || old_parent.kind == "variant") &&
+                !old_parent.flags.private_fields
+        }
+        // Existing implementations of the trait do not provide it.
+        _ => item.flags.required,
+    }
+}
+
+fn load(path: &Path) -> Result<Api, String> {
+    let contents = fs::read_to_string(path)
+        .map_err(|e| format!("could not read `{}`: {}", path.display(), e))?;
+    let doc = Json::from_str(&contents)
+        .map_err(|e| format!("could not parse `{}`: {}", path.display(), e))?;
+    if doc.find("format_version").and_then(|v| v.as_u64()) != Some(FORMAT_VERSION) {
+        return Err(format!("`{}` was not written by this version of `--output-format json`",
+                           path.display()));
+    }
+    let index = doc.find("index").and_then(|i| i.as_object());
+    let root = doc.find("root").and_then(|r| r.as_string());
+    let name = doc.find("name").and_then(|n| n.as_string());
+    let (index, root, name) = match (index, root, name) {
+        (Some(index), Some(root), Some(name)) => (index, root, name),
+        _ => return Err(format!("`{}` has no crate in it", path.display())),
+    };
+
+    let mut collector = Collector {
+        index,
+        items: BTreeMap::new(),
+        seen_impls: HashSet::new(),
+        module_impls: Vec::new(),
+    };
+    if let Some(root) = index.get(root) {
+        collector.members(root, name);
+    }
+    // Impls are listed under the types they are for, so that their items get
+    // the path of the type. The others are only found in modules.
+    for id in collector.module_impls.clone() {
+        if collector.seen_impls.insert(id) {
+            if let Some(item) = index.get(id) {
+                collector.impl_(item, None);
+            }
+        }
+    }
+
+    Ok(Api {
+        version: doc.find("version").and_then(|v| v.as_string()).map(|v| v.to_owned()),
+        items: collector.items,
+    })
+}
+
+struct Collector<'a> {
+    index: &'a Object,
+    items: BTreeMap<String, ApiItem>,
+    /// The impls whose items have been collected.
+    seen_impls: HashSet<&'a str>,
+    /// The impls found in modules.
+    module_impls: Vec<&'a str>,
+}
+
+impl<'a> Collector<'a> {
+    /// Collects the public item `id` and its members, `parent` being the path
+    /// of the item it is in.
+    fn item(&mut self, id: &'a str, parent: &str) {
+        let index = self.index;
+        let item = match index.get(id) {
+            Some(item) => item,
+            // Items of other crates are only in the index if they were inlined.
+            None => return,
+        };
+        if field(item, "visibility") == "default" {
+            return;
+        }
+        let kind = field(item, "kind");
+        if kind == "impl" {
+            self.module_impls.push(id);
+            return;
+        }
+        let inner = item.find("inner").unwrap_or(&Json::Null);
+        let name = match kind {
+            "import" if flag(inner, "glob") => format!("{}::*", field(inner, "source")),
+            _ => field(item, "name").to_owned(),
+        };
+        let path = format!("{}::{}", parent, name);
+
+        let mut flags = Flags {
+            deprecated: !item.find("deprecation").map_or(true, |d| d.is_null()) ||
+                        !item.find("stability")
+                             .and_then(|s| s.find("deprecated_since"))
+                             .map_or(true, |d| d.is_null()),
+            ..Flags::default()
+        };
+        let (params, where_clause) = generics(inner.find("generics"));
+        let base = match kind {
+            "mod" => format!("mod {}", name),
+            "externcrate" => format!("extern crate {}", name),
+            "import" => format!("use {}", field(inner, "source")),
+            "struct" | "union" => {
+                flags.private_fields = flag(inner, "fields_stripped");
+                let body = match field(inner, "struct_type") {
+                    "tuple" => "(..)",
+                    "unit" => ";",
+                    _ => " { .. }",
+                };
+                format!("{} {}{}{}{}", kind, name, params, where_clause, body)
+            }
+            "enum" => {
+                flags.private_fields = flag(inner, "variants_stripped");
+                format!("enum {}{}{}", name, params, where_clause)
+            }
+            "variant" => match field(inner, "variant_kind") {
+                "tuple" => {
+                    let fields = array(inner, "fields")
+                        .iter()
+                        .filter_map(|f| f.as_string())
+                        .collect::<Vec<_>>();
+                    format!("{}({})", name, fields.join(", "))
+                }
+                "struct" => {
+                    flags.private_fields = flag(inner, "fields_stripped");
+                    format!("{} {{ .. }}", name)
+                }
+                _ => name.clone(),
+            },
+            "structfield" => format!("{}: {}", name, field(inner, "type")),
+            "fn" | "method" | "tymethod" => {
+                flags.is_const = flag(inner, "const");
+                flags.required = kind == "tymethod";
+                let abi = match field(inner, "abi") {
+                    "Rust" => String::new(),
+                    abi => format!("extern {:?} ", abi),
+                };
+                format!("{}{}fn {}{}{}{}",
+                        if flag(inner, "unsafe") { "unsafe " } else { "" },
+                        abi, name, params, field(inner, "decl"), where_clause)
+            }
+            "type" => format!("type {}{} = {}{}", name, params, field(inner, "type"), where_clause),
+            "static" => {
+                format!("static {}{}: {}",
+                        if flag(inner, "mutable") { "mut " } else { "" },
+                        name, field(inner, "type"))
+            }
+            "constant" => format!("const {}: {}", name, field(inner, "type")),
+            "trait" => {
+                let bounds = strings(inner, "bounds");
+                format!("{}{}trait {}{}{}{}{}",
+                        if flag(inner, "unsafe") { "unsafe " } else { "" },
+                        if flag(inner, "auto") { "auto " } else { "" },
+                        name, params,
+                        if bounds.is_empty() { "" } else { ": " }, bounds.join(" + "),
+                        where_clause)
+            }
+            "associatedtype" => {
+                let bounds = strings(inner, "bounds");
+                let default = inner.find("default").and_then(|d| d.as_string());
+                flags.required = default.is_none() && self.in_trait(parent);
+                format!("type {}{}{}{}",
+                        name,
+                        if bounds.is_empty() { "" } else { ": " }, bounds.join(" + "),
+                        default.map(|d| format!(" = {}", d)).unwrap_or_default())
+            }
+            "associatedconstant" => {
+                let default = inner.find("default").and_then(|d| d.as_string());
+                flags.required = default.is_none() && self.in_trait(parent);
+                format!("const {}: {}{}", name, field(inner, "type"),
+                        default.map(|d| format!(" = {}", d)).unwrap_or_default())
+            }
+            _ => format!("{} {}", kind, name),
+        };
+        let signature = format!("{}{}{}",
+                                if flags.deprecated { "#[deprecated] " } else { "" },
+                                if flags.is_const { "const " } else { "" },
+                                base);
+        let parent = if self.items.contains_key(parent) { Some(parent.to_owned()) } else { None };
+        self.items.insert(path.clone(), ApiItem {
+            kind: kind.to_owned(),
+            parent,
+            signature,
+            base,
+            flags,
+        });
+
+        self.members(item, &path);
+    }
+
+    /// Collects the members of `item`, whose path is `path`.
+    fn members(&mut self, item: &'a Json, path: &str) {
+        let inner = match item.find("inner") {
+            Some(inner) => inner,
+            None => return,
+        };
+        let children = match field(item, "kind") {
+            "mod" => array(inner, "items"),
+            "struct" | "union" => array(inner, "fields"),
+            "enum" => array(inner, "variants"),
+            "variant" if field(inner, "variant_kind") == "struct" => array(inner, "fields"),
+            "trait" => array(inner, "items"),
+            _ => &[][..],
+        };
+        for child in children {
+            if let Some(id) = child.as_string() {
+                self.item(id, path);
+            }
+        }
+
+        let index = self.index;
+        for id in array(inner, "impls").iter().filter_map(|i| i.as_string()) {
+            if self.seen_impls.insert(id) {
+                if let Some(imp) = index.get(id) {
+                    self.impl_(imp, Some(path));
+                }
+            }
+        }
+    }
+
+    /// Collects the impl `item`. The items of inherent impls are members of
+    /// the type, named after `owner`, the path of the type if it is known.
+    /// Those of trait impls are covered by the trait.
+    fn impl_(&mut self, item: &'a Json, owner: Option<&str>) {
+        let inner = match item.find("inner") {
+            Some(inner) => inner,
+            None => return,
+        };
+        let for_ = field(inner, "for");
+        // Name the type by its path, keeping the generic arguments.
+        let self_ty = match owner {
+            Some(owner) => {
+                let args = for_.find('<').map_or("", |i| &for_[i..]);
+                format!("{}{}", owner, args)
+            }
+            None => for_.to_owned(),
+        };
+
+        let trait_ = match inner.find("trait").and_then(|t| t.as_string()) {
+            Some(trait_) => trait_,
+            None => {
+                for id in array(inner, "items").iter().filter_map(|i| i.as_string()) {
+                    self.item(id, &self_ty);
+                }
+                return;
+            }
+        };
+        let negative = flag(inner, "negative");
+        let path = format!("impl {} for {}", trait_, self_ty);
+        let (params, where_clause) = generics(inner.find("generics"));
+        let unsafety = if flag(inner, "unsafe") { "unsafe " } else { "" };
+        let base = format!("{}impl{} {} for {}{}", unsafety, params, trait_, for_, where_clause);
+        let signature = format!("{}impl{} {}{} for {}{}",
+                                unsafety, params, if negative { "!" } else { "" }, trait_, for_,
+                                where_clause);
+        let parent = match owner {
+            Some(owner) if self.items.contains_key(owner) => Some(owner.to_owned()),
+            _ => None,
+        };
+        self.items.insert(path, ApiItem {
+            kind: if flag(inner, "synthetic") { "auto impl" } else { "impl" }.to_owned(),
+            parent,
+            signature,
+            base,
+            flags: Flags { negative, ..Flags::default() },
+        });
+    }
+
+    fn in_trait(&self, path: &str) -> bool {
+        self.items.get(path).map_or(false, |item| item.kind == "trait")
+    }
+}
+
+fn field<'a>(json: &'a Json, key: &str) -> &'a str {
+    json.find(key).and_then(|v| v.as_string()).unwrap_or("")
+}
+
+fn flag(json: &Json, key: &str) -> bool {
+    json.find(key).and_then(|v| v.as_boolean()).unwrap_or(false)
+}
+
+fn array<'a>(json: &'a Json, key: &str) -> &'a [Json] {
+    json.find(key).and_then(|v| v.as_array()).map_or(&[][..], |a| &a[..])
+}
+
+fn strings<'a>(json: &'a Json, key: &str) -> Vec<&'a str> {
+    array(json, key).iter().filter_map(|s| s.as_string()).collect()
+}
+
+/// Writes the generics of an item as its parameter list and where clause.
+fn generics(generics: Option<&Json>) -> (String, String) {
+    let generics = match generics {
+        Some(generics) => generics,
+        None => return (String::new(), String::new()),
+    };
+    let params = array(generics, "params").iter().map(|param| {
+        let mut s = field(param, "name").to_owned();
+        let bounds = strings(param, "bounds");
+        if !bounds.is_empty() {
+            s.push_str(": ");
+            s.push_str(&bounds.join(" + "));
+        }
+        if let Some(default) = param.find("default").and_then(|d| d.as_string()) {
+            s.push_str(" = ");
+            s.push_str(default);
+        }
+        s
+    }).collect::<Vec<_>>();
+    let predicates = strings(generics, "where_predicates");
+
+    let params = if params.is_empty() {
+        String::new()
+    } else {
+        format!("<{}>", params.join(", "))
+    };
+    let where_clause = if predicates.is_empty() {
+        String::new()
+    } else {
+        format!(" where {}", predicates.join(", "))
+    };
+    (params, where_clause)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{ApiItem, Flags, addition_is_breaking, change_is_breaking};
+
+    fn item(kind: &str, base: &str, flags: Flags) -> ApiItem {
+        ApiItem {
+            kind: kind.to_owned(),
+            parent: None,
+            signature: base.to_owned(),
+            base: base.to_owned(),
+            flags,
+        }
+    }
+
+    #[test]
+    fn breaking_changes() {
+        let f = item("fn", "fn f(x: u32)", Flags::default());
+        let g = item("fn", "fn f(x: u64)", Flags::default());
+        let f_const = item("fn", "fn f(x: u32)", Flags { is_const: true, ..Flags::default() });
+        assert!(change_is_breaking(&f, &g));
+        assert!(!change_is_breaking(&f, &f_const));
+        assert!(change_is_breaking(&f_const, &f));
+
+        let required = item("tymethod", "fn m(&self)",
+                            Flags { required: true, ..Flags::default() });
+        let provided = item("method", "fn m(&self)", Flags::default());
+        assert!(!change_is_breaking(&required, &provided));
+        assert!(change_is_breaking(&provided, &required));
+
+        let send = item("auto impl", "impl Send for S", Flags::default());
+        let not_send = item("auto impl", "impl Send for S",
+                            Flags { negative: true, ..Flags::default() });
+        assert!(change_is_breaking(&send, &not_send));
+        assert!(!change_is_breaking(&not_send, &send));
+    }
+
+    #[test]
+    fn breaking_additions() {
+        let open = item("struct", "struct S { .. }", Flags::default());
+        let closed = item("struct", "struct S { .. }",
+                          Flags { private_fields: true, ..Flags::default() });
+        let field = item("structfield", "x: u32", Flags::default());
+        assert!(addition_is_breaking(&field, &open));
+        assert!(!addition_is_breaking(&field, &closed));
+
+        let trait_ = item("trait", "trait T", Flags::default());
+        let required = item("tymethod", "fn m(&self)",
+                            Flags { required: true, ..Flags::default() });
+        let provided = item("method", "fn m(&self)", Flags::default());
+        assert!(addition_is_breaking(&required, &trait_));
+        assert!(!addition_is_breaking(&provided, &trait_));
+
+        let enum_ = item("enum", "enum E", Flags::default());
+        assert!(addition_is_breaking(&item("variant", "V", Flags::default()), &enum_));
+        assert!(!addition_is_breaking(&item("fn", "fn f()", Flags::default()), &enum_));
+    }
+}
@@ -705,6 +705,17 @@ pub struct Attributes {
     pub span: Option<syntax_pos::Span>,
     /// map from Rust paths to resolved defs and potential URL fragments
     pub links: Vec<(String, Option<DefId>, Option<String>)>,
+    /// The intra-doc links that could not be resolved, reported by the
+    /// `check_doc_lints` pass
+    pub link_failures: Vec<LinkFailure>,
+}
+
+#[derive(Clone, RustcEncodable, RustcDecodable, PartialEq, Eq, Debug, Hash)]
+pub enum LinkFailure {
+    /// Nothing the link could name was found.
+    Unresolved(String),
+    /// The link names more than one item, and needs a disambiguator.
+    Ambiguous { link: String, message: String, help: String },
 }
 
 impl Attributes {
@@ -855,6 +866,7 @@ impl Attributes {
             cfg: if cfg == Cfg::True { None } else { Some(Rc::new(cfg)) },
             span: sp,
             links: vec![],
+            link_failures: vec![],
         }
     }
 
@@ -958,23 +970,21 @@ fn type_ns_kind(def: Def, path_str: &str) -> (&'static str, &'static str, String
     (kind, article, format!("{}@{}", kind, path_str))
 }
 
-fn ambiguity_error(cx: &DocContext, attrs: &Attributes,
+fn ambiguity_error(attrs: &mut Attributes,
                    path_str: &str,
                    article1: &str, kind1: &str, disambig1: &str,
                    article2: &str, kind2: &str, disambig2: &str) {
-    let sp = attrs.doc_strings.first()
-                  .map_or(DUMMY_SP, |a| a.span());
-    cx.sess()
-      .struct_span_warn(sp,
-                        &format!("`{}` is both {} {} and {} {}",
-                                 path_str, article1, kind1,
-                                 article2, kind2))
-      .help(&format!("try `{}` if you want to select the {}, \
-                      or `{}` if you want to \
-                      select the {}",
+    attrs.link_failures.push(LinkFailure::Ambiguous {
+        link: path_str.to_owned(),
+        message: format!("`{}` is both {} {} and {} {}",
+                         path_str, article1, kind1,
+                         article2, kind2),
+        help: format!("try `{}` if you want to select the {}, \
+                       or `{}` if you want to \
+                       select the {}",
                       disambig1, kind1, disambig2,
-                      kind2))
-      .emit();
+                      kind2),
+    });
 }
 
 /// Given an enum variant's def, return the def of its enum and the associated fragment
@@ -1218,7 +1228,9 @@ impl Clean<Attributes> for [ast::Attribute] {
                         &link[..]
                     }.trim();
 
-                    if path_str.contains(|ch: char| !(ch.is_alphanumeric() ||
+                    if path_str.is_empty() ||
+                       path_str.starts_with(|ch: char| ch.is_digit(10)) ||
+                       path_str.contains(|ch: char| !(ch.is_alphanumeric() ||
                                                       ch == ':' || ch == '_')) {
                         continue;
                     }
@@ -1228,9 +1240,8 @@ impl Clean<Attributes> for [ast::Attribute] {
                             if let Ok(def) = resolve(cx, path_str, true) {
                                 def
                             } else {
-                                // this could just be a normal link or a broken link
-                                // we could potentially check if something is
-                                // "intra-doc-link-like" and warn in that case
+                                attrs.link_failures.push(LinkFailure::Unresolved(
+                                    path_str.to_owned()));
                                 continue;
                             }
                         }
@@ -1238,7 +1249,8 @@ impl Clean<Attributes> for [ast::Attribute] {
                             if let Ok(def) = resolve(cx, path_str, false) {
                                 def
                             } else {
-                                // this could just be a normal link
+                                attrs.link_failures.push(LinkFailure::Unresolved(
+                                    path_str.to_owned()));
                                 continue;
                             }
                         }
@@ -1248,7 +1260,7 @@ impl Clean<Attributes> for [ast::Attribute] {
                                 if let Ok(type_def) = resolve(cx, path_str, false) {
                                     let (type_kind, article, type_disambig)
                                         = type_ns_kind(type_def.0, path_str);
-                                    ambiguity_error(cx, &attrs, path_str,
+                                    ambiguity_error(&mut attrs, path_str,
                                                     article, type_kind, &type_disambig,
                                                     "a", "macro", &format!("macro@{}", path_str));
                                     continue;
@@ -1257,7 +1269,7 @@ impl Clean<Attributes> for [ast::Attribute] {
                                         = value_ns_kind(value_def.0, path_str)
                                             .expect("struct and mod cases should have been \
                                                      caught in previous branch");
-                                    ambiguity_error(cx, &attrs, path_str,
+                                    ambiguity_error(&mut attrs, path_str,
                                                     "a", value_kind, &value_disambig,
                                                     "a", "macro", &format!("macro@{}", path_str));
                                 }
@@ -1272,7 +1284,7 @@ impl Clean<Attributes> for [ast::Attribute] {
                                     if let Some((value_kind, value_disambig)) = kind {
                                         let (type_kind, article, type_disambig)
                                             = type_ns_kind(type_def.0, path_str);
-                                        ambiguity_error(cx, &attrs, path_str,
+                                        ambiguity_error(&mut attrs, path_str,
                                                         article, type_kind, &type_disambig,
                                                         "a", value_kind, &value_disambig);
                                         continue;
@@ -1282,7 +1294,10 @@ impl Clean<Attributes> for [ast::Attribute] {
                             } else if let Ok(value_def) = resolve(cx, path_str, true) {
                                 value_def
                             } else {
-                                // this could just be a normal link
+                                // this could just be a normal link, which is why the
+                                // lint reporting it can be allowed
+                                attrs.link_failures.push(LinkFailure::Unresolved(
+                                    path_str.to_owned()));
                                 continue;
                             }
                         }
@@ -1290,6 +1305,8 @@ impl Clean<Attributes> for [ast::Attribute] {
                             if let Some(def) = macro_resolve(cx, path_str) {
                                 (def, None)
                             } else {
+                                attrs.link_failures.push(LinkFailure::Unresolved(
+                                    path_str.to_owned()));
                                 continue
                             }
                         }
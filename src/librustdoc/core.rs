@@ -41,6 +41,8 @@ use visit_ast::RustdocVisitor;
 use clean;
 use clean::Clean;
 use html::render::RenderInfo;
+use passes;
+use scrape_examples;
 
 pub use rustc::session::config::Input;
 pub use rustc::session::search_paths::SearchPaths;
@@ -123,9 +125,11 @@ pub fn run_core(search_paths: SearchPaths,
                 triple: Option<TargetTriple>,
                 maybe_sysroot: Option<PathBuf>,
                 allow_warnings: bool,
+                lint_cap: Option<lint::Level>,
                 crate_name: Option<String>,
                 force_unstable_if_unmarked: bool,
-                edition: Edition) -> (clean::Crate, RenderInfo)
+                edition: Edition,
+                scrape_options: scrape_examples::Options) -> (clean::Crate, RenderInfo)
 {
     // Parse, resolve, and typecheck the given crate.
 
@@ -134,15 +138,21 @@ pub fn run_core(search_paths: SearchPaths,
         _ => None
     };
 
-    let warning_lint = lint::builtin::WARNINGS.name_lower();
+    // The lints of the compiler were reported when the crate was compiled, so
+    // only those about the documentation are shown, unless asked otherwise.
+    // These are still capped by `--cap-lints`, so that documenting a crate
+    // that denies warnings does not fail because of them.
+    let lint_caps = passes::DOC_LINTS.iter()
+        .map(|doc_lint| (doc_lint.name_lower(), lint_cap.unwrap_or(lint::Forbid)))
+        .collect();
 
     let host_triple = TargetTriple::from_triple(config::host_triple());
     let sessopts = config::Options {
         maybe_sysroot,
         search_paths,
         crate_types: vec![config::CrateTypeRlib],
-        lint_opts: if !allow_warnings { vec![(warning_lint, lint::Allow)] } else { vec![] },
-        lint_cap: Some(lint::Allow),
+        lint_cap: if allow_warnings { lint_cap } else { Some(lint::Allow) },
+        lint_caps,
         externs,
         target_triple: triple.unwrap_or(host_triple),
         // Ensure that rustdoc works even if rustc is feature-staged
@@ -240,6 +250,13 @@ pub fn run_core(search_paths: SearchPaths,
             sess.fatal("Compilation failed, aborting rustdoc");
         }
 
+        if let Some(ref path) = scrape_options.output_path {
+            if let Err(e) = scrape_examples::scrape(tcx, &scrape_options, path) {
+                sess.fatal(&format!("failed to write the scraped examples to `{}`: {}",
+                                    path.display(), e));
+            }
+        }
+
         let ty::CrateAnalysis { access_levels, .. } = analysis;
 
         // Convert from a NodeId set to a DefId set since we don't always have easy access
@@ -282,7 +299,16 @@ pub fn run_core(search_paths: SearchPaths,
             v.visit(tcx.hir.krate());
             v.clean(&ctxt)
         };
+        let krate = passes::check_doc_lints(&ctxt, krate);
+        sess.abort_if_errors();
+
+        let mut renderinfo = ctxt.renderinfo.into_inner();
+        renderinfo.scraped_examples =
+            match scrape_examples::load(tcx, &scrape_options.with_examples) {
+                Ok(examples) => examples,
+                Err(e) => sess.fatal(&e),
+            };
 
-        (krate, ctxt.renderinfo.into_inner())
+        (krate, renderinfo)
     }), &sess)
 }
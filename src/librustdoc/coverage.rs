@@ -0,0 +1,169 @@
+// Copyright 2018 The Rust Project Developers. See the COPYRIGHT
+// file at the top-level directory of this distribution and at
+// http://rust-lang.org/COPYRIGHT.
+//
+// Licensed under the Apache License, Version 2.0 <LICENSE-APACHE or
+// http://www.apache.org/licenses/LICENSE-2.0> or the MIT license
+// <LICENSE-MIT or http://opensource.org/licenses/MIT>, at your
+// option. This file may not be copied, modified, or distributed
+// except according to those terms.
+
+//! Documentation coverage, as reported by `--show-coverage`.
+//!
+//! After the passes have run, the crate is walked and, for every module, the
+//! items that are left are counted along with how many of them have
+//! documentation and how many have an example in it. Imports are left out, as
+//! are the items of trait impls, which are documented on the trait.
+
+use std::cmp;
+use std::collections::BTreeMap;
+
+use serialize::json::{Json, ToJson};
+
+use clean;
+use html::markdown::has_rust_code_block;
+
+#[derive(Default, Copy, Clone)]
+struct ItemCount {
+    total: u64,
+    with_docs: u64,
+    with_examples: u64,
+}
+
+impl ItemCount {
+    fn add(&mut self, other: ItemCount) {
+        self.total += other.total;
+        self.with_docs += other.with_docs;
+        self.with_examples += other.with_examples;
+    }
+
+    fn percentage(&self, count: u64) -> f64 {
+        if self.total == 0 {
+            0.0
+        } else {
+            count as f64 * 100.0 / self.total as f64
+        }
+    }
+}
+
+impl ToJson for ItemCount {
+    fn to_json(&self) -> Json {
+        let mut data = BTreeMap::new();
+        data.insert("total".to_owned(), self.total.to_json());
+        data.insert("with_docs".to_owned(), self.with_docs.to_json());
+        data.insert("with_examples".to_owned(), self.with_examples.to_json());
+        Json::Object(data)
+    }
+}
+
+struct CoverageCalculator {
+    /// The counts of every module, by path.
+    modules: BTreeMap<String, ItemCount>,
+    /// The path of the module currently being visited.
+    stack: Vec<String>,
+}
+
+impl CoverageCalculator {
+    fn visit(&mut self, item: &clean::Item) {
+        match item.inner {
+            // Items stripped by a pass are not part of the documentation.
+            clean::StrippedItem(..) => return,
+            // Imports are documented where the item they bring in is defined.
+            clean::ImportItem(..) | clean::ExternCrateItem(..) => return,
+            // Impls carry no documentation of their own, and the items of
+            // trait impls are documented on the trait.
+            clean::ImplItem(ref i) => {
+                if i.trait_.is_none() {
+                    for item in &i.items {
+                        self.visit(item);
+                    }
+                }
+                return;
+            }
+            _ => {}
+        }
+
+        // Inlined items from other crates are counted in their own crate.
+        if item.def_id.is_local() {
+            let doc = item.doc_value().unwrap_or("");
+            let has_docs = !doc.trim().is_empty();
+            let count = self.modules.entry(self.stack.join("::")).or_insert_with(Default::default);
+            count.add(ItemCount {
+                total: 1,
+                with_docs: has_docs as u64,
+                with_examples: (has_docs && has_rust_code_block(doc)) as u64,
+            });
+        }
+
+        let children: &[clean::Item] = match item.inner {
+            clean::ModuleItem(ref m) => &m.items,
+            clean::StructItem(ref s) => &s.fields,
+            clean::UnionItem(ref u) => &u.fields,
+            clean::EnumItem(ref e) => &e.variants,
+            clean::VariantItem(clean::Variant {
+                kind: clean::VariantKind::Struct(ref s)
+            }) => &s.fields,
+            clean::TraitItem(ref t) => &t.items,
+            _ => &[],
+        };
+        let pushed = match item.inner {
+            clean::ModuleItem(ref m) if !m.is_crate => {
+                self.stack.push(item.name.clone().unwrap_or_default());
+                true
+            }
+            _ => false,
+        };
+        for child in children {
+            self.visit(child);
+        }
+        if pushed {
+            self.stack.pop();
+        }
+    }
+
+    fn print_table(&self) {
+        let width = self.modules.keys().map(|name| name.len()).fold("Module".len(), cmp::max);
+        let separator = format!("+-{}-+------------+------------+------------+------------+",
+                                "-".repeat(width));
+        let print_row = |name: &str, count: &ItemCount| {
+            println!("| {:<width$} | {:>10} | {:>9.1}% | {:>10} | {:>9.1}% |",
+                     name,
+                     count.with_docs,
+                     count.percentage(count.with_docs),
+                     count.with_examples,
+                     count.percentage(count.with_examples),
+                     width = width);
+        };
+
+        println!("{}", separator);
+        println!("| {:<width$} | {:>10} | {:>10} | {:>10} | {:>10} |",
+                 "Module", "Documented", "Percentage", "Examples", "Percentage", width = width);
+        println!("{}", separator);
+        let mut total = ItemCount::default();
+        for (name, count) in &self.modules {
+            print_row(name, count);
+            total.add(*count);
+        }
+        println!("{}", separator);
+        print_row("Total", &total);
+        println!("{}", separator);
+    }
+}
+
+/// Prints the documentation coverage of `krate`, as a table or, if `json` is
+/// set, as a JSON object mapping module paths to their counts.
+pub fn run(krate: &clean::Crate, json: bool) {
+    let mut calculator = CoverageCalculator {
+        modules: BTreeMap::new(),
+        stack: vec![krate.name.clone()],
+    };
+    if let Some(ref module) = krate.module {
+        calculator.visit(module);
+    }
+
+    if json {
+        println!("{}", calculator.modules.to_json());
+    } else {
+        calculator.print_table();
+    }
+}
@@ -29,7 +29,7 @@
 
 use rustc::session;
 use std::cell::RefCell;
-use std::collections::{HashMap, VecDeque};
+use std::collections::{BTreeSet, HashMap, VecDeque};
 use std::default::Default;
 use std::fmt::{self, Write};
 use std::str;
@@ -783,10 +783,111 @@ pub fn markdown_links(md: &str) -> Vec<String> {
     links
 }
 
+/// Whether `md` contains a block of Rust code, that is, an example.
+pub fn has_rust_code_block(md: &str) -> bool {
+    Parser::new(md).any(|event| match event {
+        Event::Start(Tag::CodeBlock(lang)) => LangString::parse(&lang).rust,
+        _ => false,
+    })
+}
+
+/// Returns the distinct words of the text of `md`, lowercased and sorted, as
+/// they are indexed for full-text search.
+///
+/// The search in `main.js` splits the query with the same rules: words are
+/// made of ASCII letters, digits, underscores and any non-ASCII character,
+/// and only those longer than one UTF-16 code unit are kept.
+pub fn plain_text_words(md: &str) -> Vec<String> {
+    let mut words = BTreeSet::new();
+    for event in Parser::new(md) {
+        if let Event::Text(text) = event {
+            let text = text.to_lowercase();
+            let is_separator = |c: char| c.is_ascii() && !c.is_ascii_alphanumeric() && c != '_';
+            for word in text.split(is_separator) {
+                if word.encode_utf16().count() > 1 {
+                    words.insert(word.to_owned());
+                }
+            }
+        }
+    }
+    words.into_iter().collect()
+}
+
+/// The URLs written as plain text in `md`, which are not turned into links.
+pub fn bare_urls(md: &str) -> Vec<String> {
+    fn push_urls(text: &str, urls: &mut Vec<String>) {
+        for word in text.split_whitespace() {
+            if word.starts_with("http://") || word.starts_with("https://") {
+                let url = word.trim_right_matches(|c: char| ".,;:!?)'\"".contains(c));
+                urls.push(url.to_owned());
+            }
+        }
+    }
+
+    let mut urls = vec![];
+    // The text of a paragraph can come in several events, which are joined so
+    // that URLs are not cut short.
+    let mut text = String::new();
+    // Links and code are left alone.
+    let mut depth = 0;
+    for event in Parser::new(md) {
+        match event {
+            Event::Text(ref s) if depth == 0 => {
+                text.push_str(s);
+                continue;
+            }
+            Event::Start(Tag::Link(..)) | Event::Start(Tag::Code) |
+            Event::Start(Tag::CodeBlock(_)) => depth += 1,
+            Event::End(Tag::Link(..)) | Event::End(Tag::Code) |
+            Event::End(Tag::CodeBlock(_)) => depth -= 1,
+            _ => {}
+        }
+        push_urls(&text, &mut urls);
+        text.clear();
+    }
+    push_urls(&text, &mut urls);
+    urls
+}
+
+/// The code of the blocks of `md` that are meant to be valid Rust, hidden
+/// lines included: the Rust blocks not marked `ignore` or `compile_fail`.
+pub fn rust_code_blocks(md: &str) -> Vec<String> {
+    let mut blocks = vec![];
+    let mut current = None;
+    for event in Parser::new(md) {
+        match event {
+            Event::Start(Tag::CodeBlock(lang)) => {
+                let info = if lang.is_empty() {
+                    LangString::all_false()
+                } else {
+                    LangString::parse(&lang)
+                };
+                if info.rust && !info.ignore && !info.compile_fail {
+                    current = Some(String::new());
+                }
+            }
+            Event::Text(ref s) => {
+                if let Some(ref mut code) = current {
+                    code.push_str(s);
+                }
+            }
+            Event::End(Tag::CodeBlock(_)) => {
+                if let Some(code) = current.take() {
+                    let lines = code.lines().map(|l| map_line(l).for_code());
+                    blocks.push(lines.collect::<Vec<&str>>().join("\n"));
+                }
+            }
+            _ => {}
+        }
+    }
+    blocks
+}
+
 #[cfg(test)]
 mod tests {
     use super::{LangString, Markdown, MarkdownHtml};
-    use super::plain_summary_line;
+    use super::{bare_urls, has_rust_code_block, plain_summary_line, plain_text_words};
+    use super::rust_code_blocks;
     use html::render::reset_ids;
 
     #[test]
@@ -911,4 +1012,41 @@ mod tests {
         t("Struct<'a, T>", "<p>Struct&lt;'a, T&gt;</p>\n");
         t("Struct<br>", "<p>Struct&lt;br&gt;</p>\n");
     }
+
+    #[test]
+    fn test_has_rust_code_block() {
+        assert!(has_rust_code_block("```\nlet x = 1;\n```"));
+        assert!(has_rust_code_block("text\n\n```should_panic\npanic!();\n```"));
+        assert!(!has_rust_code_block("```text\nnot rust\n```"));
+        assert!(!has_rust_code_block("no `code` blocks here"));
+    }
+
+    #[test]
+    fn test_plain_text_words() {
+        fn t(input: &str, expect: &[&str]) {
+            assert_eq!(plain_text_words(input), expect, "original: {}", input);
+        }
+
+        t("Opens a [File](https://example.com/file) in `read_only` mode, a file.",
+          &["file", "in", "mode", "opens", "read_only"]);
+        t("# Panics\n\n```\nlet x = Vec::new();\n```", &["let", "new", "panics", "vec"]);
+        t("", &[]);
+        t("Größe, ÉTÉ et café-crème: 日本 ü x 𝔸",
+          &["café", "crème", "et", "größe", "été", "日本", "𝔸"]);
+    }
+
+    #[test]
+    fn test_bare_urls() {
+        assert_eq!(bare_urls("see https://example.com/a_b."), vec!["https://example.com/a_b"]);
+        assert!(bare_urls("see <https://example.com>").is_empty());
+        assert!(bare_urls("see [here](https://example.com)").is_empty());
+        assert!(bare_urls("see `https://example.com`").is_empty());
+    }
+
+    #[test]
+    fn test_rust_code_blocks() {
+        let md = "```\n# let x = 1;\nx;\n```\n\n```text\nhi\n```\n\n\
+                  ```ignore\nnot rust\n```";
+        assert_eq!(rust_code_blocks(md), vec!["let x = 1;\nx;"]);
+    }
 }
@@ -38,7 +38,7 @@ pub use self::ExternalLocation::*;
 use std::borrow::Cow;
 use std::cell::RefCell;
 use std::cmp::Ordering;
-use std::collections::{BTreeMap, HashSet, VecDeque};
+use std::collections::{BTreeMap, BTreeSet, HashSet, VecDeque};
 use std::default::Default;
 use std::error;
 use std::fmt::{self, Display, Formatter, Write as FmtWrite};
@@ -74,6 +74,7 @@ use html::format::fmt_impl_for_trait_page;
 use html::item_type::ItemType;
 use html::markdown::{self, Markdown, MarkdownHtml, MarkdownSummaryLine};
 use html::{highlight, layout};
+use scrape_examples::CallExample;
 
 /// A pair of name and its optional document.
 pub type NameDoc = (String, Option<String>);
@@ -311,6 +312,10 @@ pub struct Cache {
     /// The version of the crate being documented, if given fron the `--crate-version` flag.
     pub crate_version: Option<String>,
 
+    /// Calls to local functions found in other crates by `--scrape-examples-output-path`,
+    /// shown on the pages of the functions called.
+    pub scraped_examples: FxHashMap<DefId, Vec<CallExample>>,
+
     // Private fields only used when initially crawling a crate to build a cache
 
     stack: Vec<String>,
@@ -318,6 +323,7 @@ pub struct Cache {
     parent_is_trait_impl: bool,
     search_index: Vec<IndexItem>,
     stripped_mod: bool,
+    full_text_search: bool,
     deref_trait_did: Option<DefId>,
     deref_mut_trait_did: Option<DefId>,
     owned_box_did: Option<DefId>,
@@ -342,6 +348,7 @@ pub struct RenderInfo {
     pub deref_trait_did: Option<DefId>,
     pub deref_mut_trait_did: Option<DefId>,
     pub owned_box_did: Option<DefId>,
+    pub scraped_examples: FxHashMap<DefId, Vec<CallExample>>,
 }
 
 /// Helper struct to render all source code to HTML pages
@@ -377,6 +384,8 @@ struct IndexItem {
     parent: Option<DefId>,
     parent_idx: Option<usize>,
     search_type: Option<IndexItemFunctionType>,
+    /// The words of the item's documentation, if a full-text index is built.
+    words: Vec<String>,
 }
 
 impl ToJson for IndexItem {
@@ -501,7 +510,8 @@ pub fn run(mut krate: clean::Crate,
            css_file_extension: Option<PathBuf>,
            renderinfo: RenderInfo,
            sort_modules_alphabetically: bool,
-           themes: Vec<PathBuf>) -> Result<(), Error> {
+           themes: Vec<PathBuf>,
+           full_text_search: bool) -> Result<(), Error> {
     let src_root = match krate.src {
         FileName::Real(ref p) => match p.parent() {
             Some(p) => p.to_path_buf(),
@@ -564,7 +574,10 @@ pub fn run(mut krate: clean::Crate,
         }
     }
     try_err!(fs::create_dir_all(&dst), &dst);
-    krate = render_sources(&dst, &mut scx, krate)?;
+    let example_sources = renderinfo.scraped_examples.values()
+        .flat_map(|examples| examples.iter().map(|e| e.path.clone()))
+        .collect::<BTreeSet<_>>();
+    krate = render_sources(&dst, &mut scx, krate, &example_sources)?;
     let cx = Context {
         current: Vec::new(),
         dst,
@@ -581,6 +594,7 @@ pub fn run(mut krate: clean::Crate,
         deref_trait_did,
         deref_mut_trait_did,
         owned_box_did,
+        scraped_examples,
     } = renderinfo;
 
     let external_paths = external_paths.into_iter()
@@ -600,8 +614,10 @@ pub fn run(mut krate: clean::Crate,
         extern_locations: FxHashMap(),
         primitive_locations: FxHashMap(),
         stripped_mod: false,
+        full_text_search,
         access_levels: krate.access_levels.clone(),
         crate_version: krate.version.take(),
+        scraped_examples,
         orphan_impl_items: Vec::new(),
         traits: mem::replace(&mut krate.external_traits, FxHashMap()),
         deref_trait_did,
@@ -644,7 +660,7 @@ pub fn run(mut krate: clean::Crate,
     krate = cache.fold_crate(krate);
 
     // Build our search index
-    let index = build_index(&krate, &mut cache);
+    let (index, full_text_index) = build_index(&krate, &mut cache);
 
     // Freeze the cache now that the index has been built. Put an Arc into TLS
     // for future parallelization opportunities
@@ -652,21 +668,33 @@ pub fn run(mut krate: clean::Crate,
     CACHE_KEY.with(|v| *v.borrow_mut() = cache.clone());
     CURRENT_LOCATION_KEY.with(|s| s.borrow_mut().clear());
 
-    write_shared(&cx, &krate, &*cache, index)?;
+    write_shared(&cx, &krate, &*cache, index, full_text_index)?;
 
     // And finally render the whole crate's documentation
     cx.krate(krate)
 }
 
-/// Build the search index from the collected metadata
-fn build_index(krate: &clean::Crate, cache: &mut Cache) -> String {
+/// The words of the documentation of `item` that the full-text index maps to
+/// it, if one is built.
+fn index_words(item: &clean::Item, full_text_search: bool) -> Vec<String> {
+    match item.doc_value() {
+        Some(doc) if full_text_search => markdown::plain_text_words(doc),
+        _ => Vec::new(),
+    }
+}
+
+/// Build the search index from the collected metadata, along with the
+/// full-text index of the documentation if `--full-text-search` was given.
+fn build_index(krate: &clean::Crate, cache: &mut Cache) -> (String, Option<String>) {
     let mut nodeid_to_pathid = FxHashMap();
     let mut crate_items = Vec::with_capacity(cache.search_index.len());
     let mut crate_paths = Vec::<Json>::new();
+    let mut crate_words = BTreeMap::<String, Vec<usize>>::new();
 
     let Cache { ref mut search_index,
                 ref orphan_impl_items,
-                ref mut paths, .. } = *cache;
+                ref mut paths,
+                full_text_search, .. } = *cache;
 
     // Attach all orphan items to the type's definition if the type
     // has since been learned.
@@ -680,6 +708,7 @@ fn build_index(krate: &clean::Crate, cache: &mut Cache) -> String {
                 parent: Some(did),
                 parent_idx: None,
                 search_type: get_index_search_type(&item),
+                words: index_words(item, full_text_search),
             });
         }
     }
@@ -710,6 +739,9 @@ fn build_index(krate: &clean::Crate, cache: &mut Cache) -> String {
         } else {
             lastpath = item.path.clone();
         }
+        for word in mem::replace(&mut item.words, Vec::new()) {
+            crate_words.entry(word).or_insert_with(Vec::new).push(crate_items.len());
+        }
         crate_items.push(item.to_json());
     }
 
@@ -721,17 +753,34 @@ fn build_index(krate: &clean::Crate, cache: &mut Cache) -> String {
     crate_data.insert("doc".to_owned(), Json::String(crate_doc));
     crate_data.insert("items".to_owned(), Json::Array(crate_items));
     crate_data.insert("paths".to_owned(), Json::Array(crate_paths));
+    if full_text_search {
+        crate_data.insert("full_text".to_owned(), Json::Boolean(true));
+    }
+
+    // The full-text index maps every word to the positions in `items` of the
+    // items whose documentation contains it. It is only loaded once a search
+    // is made, so it is kept out of the search index.
+    let full_text_index = if full_text_search {
+        let words = crate_words.into_iter().map(|(word, items)| (word, items.to_json())).collect();
+        Some(format!("register_full_text_index({}, {});",
+                     as_json(&krate.name),
+                     Json::Object(words)))
+    } else {
+        None
+    };
 
     // Collect the index into a string
-    format!("searchIndex[{}] = {};",
-            as_json(&krate.name),
-            Json::Object(crate_data))
+    (format!("searchIndex[{}] = {};",
+             as_json(&krate.name),
+             Json::Object(crate_data)),
+     full_text_index)
 }
 
 fn write_shared(cx: &Context,
                 krate: &clean::Crate,
                 cache: &Cache,
-                search_index: String) -> Result<(), Error> {
+                search_index: String,
+                full_text_index: Option<String>) -> Result<(), Error> {
     // Write out the shared files. Note that these are shared among all rustdoc
     // docs placed in the output directory, so this needs to be a synchronized
     // operation with respect to all other rustdocs running around.
@@ -879,6 +928,14 @@ themePicker.onclick = function() {{
     }
     try_err!(writeln!(&mut w, "initSearch(searchIndex);"), &dst);
 
+    // The full-text index is sharded by crate, so that each crate's can be
+    // replaced without touching the others.
+    if let Some(full_text_index) = full_text_index {
+        let dst = cx.dst.join("search-docs");
+        try_err!(fs::create_dir_all(&dst), &dst);
+        write(dst.join(&format!("{}.js", krate.name)), full_text_index.as_bytes())?;
+    }
+
     // Update the list of all implementors for traits
     let dst = cx.dst.join("implementors");
     for (&did, imps) in &cache.implementors {
@@ -956,7 +1013,8 @@ themePicker.onclick = function() {{
 }
 
 fn render_sources(dst: &Path, scx: &mut SharedContext,
-                  krate: clean::Crate) -> Result<clean::Crate, Error> {
+                  krate: clean::Crate,
+                  example_sources: &BTreeSet<PathBuf>) -> Result<clean::Crate, Error> {
     info!("emitting source files");
     let dst = dst.join("src").join(&krate.name);
     try_err!(fs::create_dir_all(&dst), &dst);
@@ -964,7 +1022,19 @@ fn render_sources(dst: &Path, scx: &mut SharedContext,
         dst,
         scx,
     };
-    Ok(folder.fold_crate(krate))
+    let krate = folder.fold_crate(krate);
+
+    // The files scraped examples come from are rendered along with the
+    // crate's own, so that the examples can link to them.
+    if folder.scx.include_sources {
+        for path in example_sources {
+            if let Err(e) = folder.emit_source(&FileName::Real(path.clone())) {
+                println!("warning: failed to render the source of the example `{}`: {}",
+                         path.display(), e);
+            }
+        }
+    }
+    Ok(krate)
 }
 
 /// Writes the entire contents of a string to a destination, not attempting to
@@ -1216,6 +1286,7 @@ impl DocFolder for Cache {
                             parent,
                             parent_idx: None,
                             search_type: get_index_search_type(&item),
+                            words: index_words(&item, self.full_text_search),
                         });
                     }
                 }
@@ -1983,6 +2054,7 @@ fn document(w: &mut fmt::Formatter, cx: &Context, item: &clean::Item) -> fmt::Re
     document_stability(w, cx, item)?;
     let prefix = render_assoc_const_value(item);
     document_full(w, item, cx, &prefix)?;
+    document_scraped_examples(w, cx, item)?;
     Ok(())
 }
 
@@ -2011,6 +2083,52 @@ fn document_short(w: &mut fmt::Formatter, item: &clean::Item, link: AssocItemLin
     Ok(())
 }
 
+/// The number of scraped examples of a function shown in full; the others are
+/// only linked to.
+const MAX_FULL_EXAMPLES: usize = 3;
+
+fn document_scraped_examples(w: &mut fmt::Formatter, cx: &Context,
+                             item: &clean::Item) -> fmt::Result {
+    let cache = cache();
+    let examples = match cache.scraped_examples.get(&item.def_id) {
+        Some(examples) => examples,
+        None => return Ok(()),
+    };
+
+    write!(w, "<div class='docblock scraped-examples'>\
+               <p><strong>Examples found in repository</strong></p>")?;
+    for (i, example) in examples.iter().enumerate() {
+        let (lo, hi) = example.call_lines;
+        let title = format!("{}:{}", example.path.display(), lo);
+        let title = match cx.shared.local_sources.get(&example.path) {
+            Some(href) => {
+                let lines = if lo == hi { format!("{}", lo) } else { format!("{}-{}", lo, hi) };
+                format!("<a href='{root}src/{krate}/{href}#{lines}'>{title}</a>",
+                        root = Escape(&cx.root_path()),
+                        krate = cx.shared.layout.krate,
+                        href = href,
+                        lines = lines,
+                        title = Escape(&title))
+            }
+            None => format!("<code>{}</code>", Escape(&title)),
+        };
+        if i < MAX_FULL_EXAMPLES {
+            write!(w, "<p>{}</p>{}", title,
+                   highlight::render_with_highlighting(&example.snippet, None, None, None,
+                                                       None))?;
+        } else {
+            if i == MAX_FULL_EXAMPLES {
+                write!(w, "<p>More examples:</p><ul>")?;
+            }
+            write!(w, "<li>{}</li>", title)?;
+        }
+    }
+    if examples.len() > MAX_FULL_EXAMPLES {
+        write!(w, "</ul>")?;
+    }
+    write!(w, "</div>")
+}
+
 fn render_assoc_const_value(item: &clean::Item) -> String {
     match item.inner {
         clean::AssociatedConstItem(ref ty, Some(ref default)) => {
@@ -3632,6 +3750,7 @@ fn render_impl(w: &mut fmt::Formatter, cx: &Context, i: &Impl, link: AssocItemLi
                     document_stability(w, cx, item)?;
                     if show_def_docs {
                         document_full(w, item, cx, &prefix)?;
+                        document_scraped_examples(w, cx, item)?;
                     }
                 }
             } else {
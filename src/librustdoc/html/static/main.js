@@ -45,6 +45,7 @@
     // 0 for "In Names"
     // 1 for "In Parameters"
     // 2 for "In Return Types"
+    // 3 for "In Documentation"
     var currentTab = 0;
 
     var themesWidth = null;
@@ -409,6 +410,12 @@
 
     function initSearch(rawSearchIndex) {
         var currentResults, index, searchIndex;
+        // The position in `searchIndex` of the first item of each crate.
+        var crateOffsets = {};
+        // The full-text indexes of the crates built with one: `null` until it
+        // is requested, `false` while it loads, and then a map from every word
+        // to the positions in the crate's items of the items documented with it.
+        var fullTextIndexes = {};
         var MAX_LEV_DISTANCE = 3;
         var MAX_RESULTS = 200;
         var params = getQueryStringParams();
@@ -442,6 +449,7 @@
                 val = valLower,
                 typeFilter = itemTypeFromName(query.type),
                 results = {}, results_in_args = {}, results_returned = {},
+                results_in_docs = {},
                 split = valLower.split("::");
 
             for (var z = 0; z < split.length; ++z) {
@@ -1012,9 +1020,42 @@
                 }
             }
 
+            // The items whose documentation contains every word of the query.
+            // The words are split as `plain_text_words` splits them when
+            // building the index: `length` counts UTF-16 code units there too.
+            var docWords = valLower.split(/[^a-z0-9_\u0080-\uffff]+/).filter(function(word) {
+                return word.length > 1;
+            });
+            for (var crate in fullTextIndexes) {
+                var words = fullTextIndexes[crate];
+                if (!fullTextIndexes.hasOwnProperty(crate) || !words || docWords.length === 0) {
+                    continue;
+                }
+                var found = null;
+                for (var i = 0; i < docWords.length; ++i) {
+                    var items = words.hasOwnProperty(docWords[i]) ? words[docWords[i]] : [];
+                    found = found === null ? items : found.filter(function(item) {
+                        return items.indexOf(item) !== -1;
+                    });
+                }
+                for (var i = 0; i < found.length; ++i) {
+                    var id = crateOffsets[crate] + found[i];
+                    var ty = searchIndex[id];
+                    if (typePassesFilter(typeFilter, ty.ty)) {
+                        results_in_docs[generateId(ty)] = {
+                            id: id,
+                            index: -1,
+                            lev: 0,
+                            dontValidate: true,
+                        };
+                    }
+                }
+            }
+
             return {
                 'in_args': sortResults(results_in_args, true),
                 'returned': sortResults(results_returned, true),
+                'in_docs': sortResults(results_in_docs),
                 'others': sortResults(results),
             };
         }
@@ -1270,12 +1311,18 @@
                 '<div id="titles">' +
                 makeTabHeader(0, "In Names", results['others'].length) +
                 makeTabHeader(1, "In Parameters", results['in_args'].length) +
-                makeTabHeader(2, "In Return Types", results['returned'].length) +
-                '</div><div id="results">';
+                makeTabHeader(2, "In Return Types", results['returned'].length);
+            if (hasFullTextIndex()) {
+                output += makeTabHeader(3, "In Documentation", results['in_docs'].length);
+            }
+            output += '</div><div id="results">';
 
             output += addTab(results['others'], query);
             output += addTab(results['in_args'], query, false);
             output += addTab(results['returned'], query, false);
+            if (hasFullTextIndex()) {
+                output += addTab(results['in_docs'], query, false);
+            }
             output += '</div>';
 
             addClass(document.getElementById('main'), 'hidden');
@@ -1296,13 +1343,16 @@
             elems[0].onclick = function() { printTab(0); };
             elems[1].onclick = function() { printTab(1); };
             elems[2].onclick = function() { printTab(2); };
-            printTab(currentTab);
+            if (elems.length > 3) {
+                elems[3].onclick = function() { printTab(3); };
+            }
+            printTab(currentTab < elems.length ? currentTab : 0);
         }
 
         function search(e) {
             var query,
                 obj, i, len,
-                results = {"in_args": [], "returned": [], "others": []},
+                results = {"in_args": [], "returned": [], "in_docs": [], "others": []},
                 resultIndex;
             var params = getQueryStringParams();
 
@@ -1328,10 +1378,42 @@
                 }
             }
 
+            loadFullTextIndexes();
             results = execQuery(query, index);
             showResults(results);
         }
 
+        function hasFullTextIndex() {
+            for (var crate in fullTextIndexes) {
+                if (fullTextIndexes.hasOwnProperty(crate)) {
+                    return true;
+                }
+            }
+            return false;
+        }
+
+        // The full-text indexes are only loaded once they are needed, as they
+        // are much larger than the search index. Each one registers itself
+        // through `register_full_text_index` once loaded.
+        function loadFullTextIndexes() {
+            for (var crate in fullTextIndexes) {
+                if (!fullTextIndexes.hasOwnProperty(crate) || fullTextIndexes[crate] !== null) {
+                    continue;
+                }
+                fullTextIndexes[crate] = false;
+                var script = document.createElement('script');
+                script.src = rootPath + 'search-docs/' + crate + '.js';
+                document.head.appendChild(script);
+            }
+        }
+
+        window.register_full_text_index = function(crate, words) {
+            fullTextIndexes[crate] = words;
+            // Search again, so that the results found in this crate show up.
+            currentResults = null;
+            search();
+        };
+
         function buildIndex(rawSearchIndex) {
             searchIndex = [];
             var searchWords = [];
@@ -1347,6 +1429,10 @@
                     desc: rawSearchIndex[crate].doc,
                     type: null,
                 });
+                crateOffsets[crate] = searchIndex.length;
+                if (rawSearchIndex[crate].full_text) {
+                    fullTextIndexes[crate] = null;
+                }
 
                 // an array of [(Number) item type,
                 //              (String) name,
@@ -1929,7 +2015,7 @@
 
     // In the search display, allows to switch between tabs.
     function printTab(nb) {
-        if (nb === 0 || nb === 1 || nb === 2) {
+        if (nb === 0 || nb === 1 || nb === 2 || nb === 3) {
             currentTab = nb;
         }
         var nb_copy = nb;
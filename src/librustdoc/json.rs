@@ -0,0 +1,470 @@
+// Copyright 2018 The Rust Project Developers. See the COPYRIGHT
+// file at the top-level directory of this distribution and at
+// http://rust-lang.org/COPYRIGHT.
+//
+// Licensed under the Apache License, Version 2.0 <LICENSE-APACHE or
+// http://www.apache.org/licenses/LICENSE-2.0> or the MIT license
+// <LICENSE-MIT or http://opensource.org/licenses/MIT>, at your
+// option. This file may not be copied, modified, or distributed
+// except according to those terms.
+
+//! JSON output of the cleaned crate.
+//!
+//! With `--output-format json`, rustdoc writes a single `<crate>.json` file
+//! to the output directory instead of HTML pages. Its layout is:
+//!
+//! ```text
+//! {
+//!     "format_version": 1,
+//!     "name": "crate name",
+//!     "version": "crate version, or null",
+//!     "root": "id of the crate's root module",
+//!     "index": { "id": item, ... },
+//!     "paths": { "id": { "crate_id": 0, "path": ["a", "b"], "kind": "struct" }, ... },
+//!     "external_crates": { "crate id": { "name": "core" }, ... },
+//!     "implementors": { "trait id": ["impl id", ...], ... }
+//! }
+//! ```
+//!
+//! Every documented item is in `index`, and items refer to each other by id.
+//! Each item has an `id`, a `crate_id`, a `name`, a `kind` (the same names as
+//! used in the file names of the HTML output), a `visibility`, its `docs`, a
+//! `span`, its `stability` and `deprecation` and an `inner` object whose
+//! contents depend on the kind. Types and signatures are given as strings,
+//! written as they would be in the source. `paths` holds the full path of
+//! every type, trait or function that is either documented or referred to
+//! from another crate.
+//!
+//! `format_version` is bumped whenever the layout changes incompatibly.
+
+use std::collections::BTreeMap;
+use std::fs::{self, File};
+use std::io::{self, BufWriter, Write};
+use std::path::PathBuf;
+
+use rustc::hir;
+use rustc::hir::def_id::DefId;
+use rustc::middle::stability;
+use serialize::json::{Json, ToJson};
+use syntax::abi::Abi;
+
+use clean::{self, GetDefId};
+use html::format::TyParamBounds;
+use html::item_type::ItemType;
+use html::render::RenderInfo;
+
+/// The version of the JSON layout described in the module documentation.
+pub const FORMAT_VERSION: u64 = 1;
+
+/// Writes `krate` as JSON into the directory `dst`.
+pub fn run(krate: clean::Crate, renderinfo: RenderInfo, dst: PathBuf) -> io::Result<()> {
+    let mut renderer = JsonRenderer {
+        index: BTreeMap::new(),
+        paths: BTreeMap::new(),
+        impls: BTreeMap::new(),
+        implementors: BTreeMap::new(),
+        stack: vec![krate.name.clone()],
+    };
+
+    for (did, (path, kind)) in renderinfo.external_paths {
+        renderer.paths.insert(id(did), path_entry(did, &path, ItemType::from(kind)));
+    }
+
+    let root = match krate.module {
+        Some(ref module) => renderer.item(module, true),
+        None => None,
+    };
+    renderer.attach_impls();
+
+    let external_crates = krate.externs
+        .iter()
+        .map(|&(cnum, ref e)| {
+            (cnum.as_u32().to_string(), object(vec![("name", e.name.to_json())]))
+        })
+        .collect();
+    let implementors = renderer.implementors
+        .into_iter()
+        .map(|(trait_id, impls)| (trait_id, Json::Array(impls)))
+        .collect();
+
+    let json = object(vec![
+        ("format_version", FORMAT_VERSION.to_json()),
+        ("name", krate.name.to_json()),
+        ("version", krate.version.to_json()),
+        ("root", root.unwrap_or(Json::Null)),
+        ("index", Json::Object(renderer.index)),
+        ("paths", Json::Object(renderer.paths)),
+        ("external_crates", Json::Object(external_crates)),
+        ("implementors", Json::Object(implementors)),
+    ]);
+
+    fs::create_dir_all(&dst)?;
+    let mut out = BufWriter::new(File::create(dst.join(format!("{}.json", krate.name)))?);
+    write!(out, "{}", json)?;
+    out.flush()
+}
+
+struct JsonRenderer {
+    index: BTreeMap<String, Json>,
+    paths: BTreeMap<String, Json>,
+    /// The impls of every type, by the id of the type.
+    impls: BTreeMap<String, Vec<Json>>,
+    /// The impls of every trait, by the id of the trait.
+    implementors: BTreeMap<String, Vec<Json>>,
+    /// The path of the module currently being rendered.
+    stack: Vec<String>,
+}
+
+impl JsonRenderer {
+    /// Adds `item` and everything in it to the index and returns its id, or
+    /// `None` if the item was stripped. `in_module` is set for the direct
+    /// children of modules, the only items that can be named by a path.
+    fn item(&mut self, item: &clean::Item, in_module: bool) -> Option<Json> {
+        if item.is_stripped() {
+            return None;
+        }
+        let id = id(item.def_id);
+        if self.index.contains_key(&id) {
+            // Inlined items may be reachable from several modules.
+            return Some(Json::String(id));
+        }
+        let kind = item.type_();
+
+        if in_module && kind != ItemType::Impl && kind != ItemType::Import {
+            if let Some(ref name) = item.name {
+                let mut path = self.stack.clone();
+                if !item.is_crate() {
+                    path.push(name.clone());
+                }
+                self.paths.insert(id.clone(), path_entry(item.def_id, &path, kind));
+            }
+        }
+
+        let pushed = match (kind, &item.name) {
+            (ItemType::Module, &Some(ref name)) if !item.is_crate() => {
+                self.stack.push(name.clone());
+                true
+            }
+            _ => false,
+        };
+        let inner = self.inner(item, &id);
+        if pushed {
+            self.stack.pop();
+        }
+
+        let visibility = match item.visibility {
+            Some(clean::Public) => "public".to_json(),
+            Some(clean::Inherited) => "default".to_json(),
+            None => Json::Null,
+        };
+        let json = object(vec![
+            ("id", id.to_json()),
+            ("crate_id", item.def_id.krate.as_u32().to_json()),
+            ("name", item.name.to_json()),
+            ("kind", kind.css_class().to_json()),
+            ("visibility", visibility),
+            ("docs", item.collapsed_doc_value().to_json()),
+            ("span", span(&item.source)),
+            ("stability", item.stability.as_ref().map_or(Json::Null, stability)),
+            ("deprecation", item.deprecation.as_ref().map_or(Json::Null, deprecation)),
+            ("inner", inner),
+        ]);
+        self.index.insert(id.clone(), json);
+        Some(Json::String(id))
+    }
+
+    fn items(&mut self, items: &[clean::Item], in_module: bool) -> Json {
+        Json::Array(items.iter().filter_map(|item| self.item(item, in_module)).collect())
+    }
+
+    fn inner(&mut self, item: &clean::Item, id: &str) -> Json {
+        match item.inner {
+            clean::ModuleItem(ref m) => object(vec![
+                ("is_crate", m.is_crate.to_json()),
+                ("items", self.items(&m.items, true)),
+            ]),
+            clean::ExternCrateItem(ref name, ref rename) => object(vec![
+                ("name", name.to_json()),
+                ("rename", rename.to_json()),
+            ]),
+            clean::ImportItem(ref import) => {
+                let (name, source, glob) = match *import {
+                    clean::Import::Simple(ref name, ref source) => {
+                        (Some(name.clone()), source, false)
+                    }
+                    clean::Import::Glob(ref source) => (None, source, true),
+                };
+                let path = source.path.segments
+                    .iter()
+                    .map(|s| &s.name[..])
+                    .collect::<Vec<_>>()
+                    .join("::");
+                object(vec![
+                    ("name", name.to_json()),
+                    ("source", path.to_json()),
+                    ("id", source.did.map_or(Json::Null, |did| self::id(did).to_json())),
+                    ("glob", glob.to_json()),
+                ])
+            }
+            clean::StructItem(ref s) => object(vec![
+                ("struct_type", struct_type(s.struct_type)),
+                ("generics", generics(&s.generics)),
+                ("fields", self.items(&s.fields, false)),
+                ("fields_stripped", s.fields_stripped.to_json()),
+                ("impls", Json::Array(Vec::new())),
+            ]),
+            clean::UnionItem(ref u) => object(vec![
+                ("struct_type", struct_type(u.struct_type)),
+                ("generics", generics(&u.generics)),
+                ("fields", self.items(&u.fields, false)),
+                ("fields_stripped", u.fields_stripped.to_json()),
+                ("impls", Json::Array(Vec::new())),
+            ]),
+            clean::EnumItem(ref e) => object(vec![
+                ("generics", generics(&e.generics)),
+                ("variants", self.items(&e.variants, false)),
+                ("variants_stripped", e.variants_stripped.to_json()),
+                ("impls", Json::Array(Vec::new())),
+            ]),
+            clean::VariantItem(ref v) => match v.kind {
+                clean::VariantKind::CLike => object(vec![("variant_kind", "plain".to_json())]),
+                clean::VariantKind::Tuple(ref tys) => object(vec![
+                    ("variant_kind", "tuple".to_json()),
+                    ("fields", Json::Array(tys.iter().map(type_).collect())),
+                ]),
+                clean::VariantKind::Struct(ref s) => object(vec![
+                    ("variant_kind", "struct".to_json()),
+                    ("fields", self.items(&s.fields, false)),
+                    ("fields_stripped", s.fields_stripped.to_json()),
+                ]),
+            },
+            clean::StructFieldItem(ref ty) => object(vec![("type", type_(ty))]),
+            clean::FunctionItem(ref f) | clean::ForeignFunctionItem(ref f) => {
+                function(&f.decl, &f.generics, f.unsafety, f.constness, f.abi)
+            }
+            clean::MethodItem(ref m) => {
+                function(&m.decl, &m.generics, m.unsafety, m.constness, m.abi)
+            }
+            clean::TyMethodItem(ref m) => {
+                function(&m.decl, &m.generics, m.unsafety, hir::Constness::NotConst, m.abi)
+            }
+            clean::TypedefItem(ref t, _) => object(vec![
+                ("type", type_(&t.type_)),
+                ("generics", generics(&t.generics)),
+            ]),
+            clean::StaticItem(ref s) | clean::ForeignStaticItem(ref s) => object(vec![
+                ("type", type_(&s.type_)),
+                ("mutable", (s.mutability == clean::Mutable).to_json()),
+                ("expr", s.expr.to_json()),
+            ]),
+            clean::ConstantItem(ref c) => object(vec![
+                ("type", type_(&c.type_)),
+                ("expr", c.expr.to_json()),
+            ]),
+            clean::TraitItem(ref t) => object(vec![
+                ("auto", t.is_auto.to_json()),
+                ("unsafe", (t.unsafety == hir::Unsafety::Unsafe).to_json()),
+                ("generics", generics(&t.generics)),
+                ("bounds", bounds(&t.bounds)),
+                ("items", self.items(&t.items, false)),
+            ]),
+            clean::ImplItem(ref i) => {
+                if let Some(did) = i.trait_.def_id() {
+                    self.implementors.entry(self::id(did)).or_insert_with(Vec::new)
+                        .push(id.to_json());
+                }
+                if let Some(did) = i.for_.def_id() {
+                    self.impls.entry(self::id(did)).or_insert_with(Vec::new)
+                        .push(id.to_json());
+                }
+
+                let mut provided_trait_methods = i.provided_trait_methods
+                    .iter()
+                    .cloned()
+                    .collect::<Vec<_>>();
+                provided_trait_methods.sort();
+                object(vec![
+                    ("unsafe", (i.unsafety == hir::Unsafety::Unsafe).to_json()),
+                    ("generics", generics(&i.generics)),
+                    ("trait", i.trait_.as_ref().map_or(Json::Null, type_)),
+                    ("for", type_(&i.for_)),
+                    ("negative", (i.polarity == Some(clean::ImplPolarity::Negative)).to_json()),
+                    ("synthetic", i.synthetic.to_json()),
+                    ("items", self.items(&i.items, false)),
+                    ("provided_trait_methods", provided_trait_methods.to_json()),
+                ])
+            }
+            clean::AssociatedConstItem(ref ty, ref default) => object(vec![
+                ("type", type_(ty)),
+                ("default", default.to_json()),
+            ]),
+            clean::AssociatedTypeItem(ref bounds, ref default) => object(vec![
+                ("bounds", self::bounds(bounds)),
+                ("default", default.as_ref().map_or(Json::Null, type_)),
+            ]),
+            clean::MacroItem(ref m) => object(vec![
+                ("source", m.source.to_json()),
+                ("imported_from", m.imported_from.to_json()),
+            ]),
+            clean::PrimitiveItem(prim) => object(vec![("name", prim.as_str().to_json())]),
+            clean::ForeignTypeItem |
+            clean::StrippedItem(..) => object(Vec::new()),
+        }
+    }
+
+    /// Fills in the `impls` of the types in the index, now that every impl
+    /// in the crate has been seen.
+    fn attach_impls(&mut self) {
+        for (ty_id, impls) in &self.impls {
+            if let Some(&mut Json::Object(ref mut item)) = self.index.get_mut(ty_id) {
+                if let Some(&mut Json::Object(ref mut inner)) = item.get_mut("inner") {
+                    if inner.contains_key("impls") {
+                        inner.insert("impls".to_string(), Json::Array(impls.clone()));
+                    }
+                }
+            }
+        }
+    }
+}
+
+/// Ids are made of the crate number and the index of the item in its crate.
+fn id(did: DefId) -> String {
+    format!("{}:{}", did.krate.as_u32(), did.index.as_raw_u32())
+}
+
+fn object(fields: Vec<(&str, Json)>) -> Json {
+    Json::Object(fields.into_iter().map(|(k, v)| (k.to_string(), v)).collect())
+}
+
+/// Strings the compiler leaves empty when they do not apply become `null`.
+fn non_empty(s: &str) -> Json {
+    if s.is_empty() {
+        Json::Null
+    } else {
+        s.to_json()
+    }
+}
+
+fn path_entry(did: DefId, path: &[String], kind: ItemType) -> Json {
+    object(vec![
+        ("crate_id", did.krate.as_u32().to_json()),
+        ("path", path.to_json()),
+        ("kind", kind.css_class().to_json()),
+    ])
+}
+
+fn span(span: &clean::Span) -> Json {
+    if let clean::Span { filename: ::syntax_pos::FileName::Anon, .. } = *span {
+        return Json::Null;
+    }
+    object(vec![
+        ("filename", span.filename.to_string().to_json()),
+        ("begin", vec![span.loline, span.locol].to_json()),
+        ("end", vec![span.hiline, span.hicol].to_json()),
+    ])
+}
+
+fn stability(stab: &clean::Stability) -> Json {
+    let level = match stab.level {
+        stability::Stable => "stable",
+        stability::Unstable => "unstable",
+    };
+    object(vec![
+        ("level", level.to_json()),
+        ("feature", non_empty(&stab.feature)),
+        ("since", non_empty(&stab.since)),
+        ("unstable_reason", non_empty(&stab.unstable_reason)),
+        ("issue", stab.issue.to_json()),
+        ("deprecated_since", non_empty(&stab.deprecated_since)),
+        ("deprecated_reason", non_empty(&stab.deprecated_reason)),
+    ])
+}
+
+fn deprecation(depr: &clean::Deprecation) -> Json {
+    object(vec![
+        ("since", non_empty(&depr.since)),
+        ("note", non_empty(&depr.note)),
+    ])
+}
+
+fn struct_type(struct_type: ::doctree::StructType) -> Json {
+    match struct_type {
+        ::doctree::Plain => "plain",
+        ::doctree::Tuple => "tuple",
+        ::doctree::Unit => "unit",
+    }.to_json()
+}
+
+/// Types are written the same way as on the HTML pages, without the links.
+fn type_(ty: &clean::Type) -> Json {
+    format!("{:#}", ty).to_json()
+}
+
+fn bounds(bounds: &[clean::TyParamBound]) -> Json {
+    Json::Array(bounds.iter().map(|b| format!("{:#}", b).to_json()).collect())
+}
+
+fn generics(generics: &clean::Generics) -> Json {
+    let params = generics.params
+        .iter()
+        .filter(|p| !p.is_synthetic_type_param())
+        .map(|p| match *p {
+            clean::GenericParam::Lifetime(ref lt) => object(vec![
+                ("kind", "lifetime".to_json()),
+                ("name", lt.get_ref().to_json()),
+                ("bounds", Json::Array(Vec::new())),
+                ("default", Json::Null),
+            ]),
+            clean::GenericParam::Type(ref tp) => object(vec![
+                ("kind", "type".to_json()),
+                ("name", tp.name.to_json()),
+                ("bounds", bounds(&tp.bounds)),
+                ("default", tp.default.as_ref().map_or(Json::Null, type_)),
+            ]),
+        })
+        .collect();
+    let where_predicates = generics.where_predicates
+        .iter()
+        .map(|pred| match *pred {
+            clean::WherePredicate::BoundPredicate { ref ty, ref bounds } => {
+                format!("{:#}: {:#}", ty, TyParamBounds(bounds))
+            }
+            clean::WherePredicate::RegionPredicate { ref lifetime, ref bounds } => {
+                let bounds = bounds.iter().map(|b| b.get_ref()).collect::<Vec<_>>();
+                format!("{}: {}", lifetime.get_ref(), bounds.join(" + "))
+            }
+            clean::WherePredicate::EqPredicate { ref lhs, ref rhs } => {
+                format!("{:#} == {:#}", lhs, rhs)
+            }
+        }.to_json())
+        .collect();
+    object(vec![
+        ("params", Json::Array(params)),
+        ("where_predicates", Json::Array(where_predicates)),
+    ])
+}
+
+fn function(decl: &clean::FnDecl,
+            gens: &clean::Generics,
+            unsafety: hir::Unsafety,
+            constness: hir::Constness,
+            abi: Abi) -> Json {
+    let inputs = decl.inputs.values
+        .iter()
+        .map(|arg| object(vec![("name", arg.name.to_json()), ("type", type_(&arg.type_))]))
+        .collect();
+    let output = match decl.output {
+        clean::Return(ref ty) => type_(ty),
+        clean::DefaultReturn => Json::Null,
+    };
+    object(vec![
+        ("decl", format!("{:#}", decl).to_json()),
+        ("inputs", Json::Array(inputs)),
+        ("output", output),
+        ("variadic", decl.variadic.to_json()),
+        ("generics", generics(gens)),
+        ("unsafe", (unsafety == hir::Unsafety::Unsafe).to_json()),
+        ("const", (constness == hir::Constness::Const).to_json()),
+        ("abi", abi.name().to_json()),
+    ])
+}
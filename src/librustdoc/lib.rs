@@ -61,6 +61,7 @@ use std::sync::mpsc::channel;
 
 use syntax::edition::Edition;
 use externalfiles::ExternalHtml;
+use rustc::lint;
 use rustc::session::search_paths::SearchPaths;
 use rustc::session::config::{ErrorOutputType, RustcOptGroup, nightly_options, Externs};
 use rustc_back::target::TargetTriple;
@@ -68,10 +69,13 @@ use rustc_back::target::TargetTriple;
 #[macro_use]
 pub mod externalfiles;
 
+pub mod api_diff;
 pub mod clean;
 pub mod core;
+pub mod coverage;
 pub mod doctree;
 pub mod fold;
+pub mod json;
 pub mod html {
     pub mod highlight;
     pub mod escape;
@@ -85,6 +89,7 @@ pub mod html {
 pub mod markdown;
 pub mod passes;
 pub mod plugins;
+pub mod scrape_examples;
 pub mod visit_ast;
 pub mod visit_lib;
 pub mod test;
@@ -139,7 +144,7 @@ pub fn opts() -> Vec<RustcOptGroup> {
                      "[rust]")
         }),
         stable("w", |o| {
-            o.optopt("w", "output-format", "the output type to write", "[html]")
+            o.optopt("w", "output-format", "the output type to write", "[html|json]")
         }),
         stable("o", |o| o.optopt("o", "output", "where to place the output", "PATH")),
         stable("crate-name", |o| {
@@ -243,6 +248,12 @@ pub fn opts() -> Vec<RustcOptGroup> {
         unstable("display-warnings", |o| {
             o.optflag("", "display-warnings", "to print code warnings when testing doc")
         }),
+        unstable("cap-lints", |o| {
+            o.optopt("", "cap-lints",
+                     "set the most restrictive lint level. More restrictive lints are capped at \
+                      this level",
+                     "LEVEL")
+        }),
         unstable("crate-version", |o| {
             o.optopt("", "crate-version", "crate version to print into documentation", "VERSION")
         }),
@@ -275,6 +286,45 @@ pub fn opts() -> Vec<RustcOptGroup> {
                      "edition to use when compiling rust code (default: 2015)",
                      "EDITION")
         }),
+        unstable("show-coverage", |o| {
+            o.optflag("", "show-coverage",
+                      "report how many public items of each module are documented and have \
+                       examples, instead of generating documentation")
+        }),
+        unstable("scrape-examples-output-path", |o| {
+            o.optopt("", "scrape-examples-output-path",
+                     "write the calls the crate makes to the functions of the crates given with \
+                      `--scrape-examples-target-crate` to PATH, instead of generating \
+                      documentation",
+                     "PATH")
+        }),
+        unstable("scrape-examples-target-crate", |o| {
+            o.optmulti("", "scrape-examples-target-crate",
+                       "crate whose functions the calls are scraped for",
+                       "CRATE")
+        }),
+        unstable("full-text-search", |o| {
+            o.optflag("", "full-text-search",
+                      "also index the text of the documentation, so that searches can find \
+                       items by what their documentation says")
+        }),
+        unstable("with-examples", |o| {
+            o.optmulti("", "with-examples",
+                       "show the calls scraped into PATH on the pages of the functions called",
+                       "PATH")
+        }),
+        unstable("doctest-cache-dir", |o| {
+            o.optopt("", "doctest-cache-dir",
+                     "remember the doctests that passed in DIR, and do not run them again until \
+                      they or the crate change",
+                     "DIR")
+        }),
+        unstable("api-diff", |o| {
+            o.optopt("", "api-diff",
+                     "compare the JSON documentation of a crate with that of an older version, \
+                      given in PATH, and list the changes to its public API",
+                     "PATH")
+        }),
     ]
 }
 
@@ -359,6 +409,17 @@ pub fn main_args(args: &[String]) -> isize {
     }
     let input = &matches.free[0];
 
+    if let Some(old) = matches.opt_str("api-diff") {
+        let json = matches.opt_str("w").map_or(false, |w| w == "json");
+        return match api_diff::run(Path::new(&old), Path::new(input), json) {
+            Ok(()) => 0,
+            Err(err) => {
+                print_error(err);
+                1
+            }
+        };
+    }
+
     let mut libs = SearchPaths::new();
     for s in &matches.opt_strs("L") {
         libs.add_path(s, ErrorOutputType::default());
@@ -432,6 +493,8 @@ pub fn main_args(args: &[String]) -> isize {
     let linker = matches.opt_str("linker").map(PathBuf::from);
     let sort_modules_alphabetically = !matches.opt_present("sort-modules-by-appearance");
     let resource_suffix = matches.opt_str("resource-suffix");
+    let full_text_search = matches.opt_present("full-text-search");
+    let doctest_cache_dir = matches.opt_str("doctest-cache-dir").map(PathBuf::from);
 
     let edition = matches.opt_str("edition").unwrap_or("2015".to_string());
     let edition = match edition.parse() {
@@ -442,14 +505,23 @@ pub fn main_args(args: &[String]) -> isize {
         }
     };
 
+    if let Some(cap) = matches.opt_str("cap-lints") {
+        if lint::Level::from_str(&cap).is_none() {
+            print_error(format!("unknown lint level: `{}`", cap));
+            return 1;
+        }
+    }
+
     match (should_test, markdown_input) {
         (true, true) => {
-            return markdown::test(input, cfgs, libs, externs, test_args, maybe_sysroot,
-                                  display_warnings, linker, edition)
+            return markdown::test(input, cfgs, libs, externs, test_args, crate_name,
+                                  maybe_sysroot, display_warnings, linker, edition,
+                                  doctest_cache_dir)
         }
         (true, false) => {
             return test::run(Path::new(input), cfgs, libs, externs, test_args, crate_name,
-                             maybe_sysroot, display_warnings, linker, edition)
+                             maybe_sysroot, display_warnings, linker, edition,
+                             doctest_cache_dir)
         }
         (false, true) => return markdown::render(Path::new(input),
                                                  output.unwrap_or(PathBuf::from("doc")),
@@ -459,8 +531,27 @@ pub fn main_args(args: &[String]) -> isize {
     }
 
     let output_format = matches.opt_str("w");
+    let show_coverage = matches.opt_present("show-coverage");
+    let scraping = matches.opt_present("scrape-examples-output-path");
+    if scraping && !matches.opt_present("scrape-examples-target-crate") {
+        print_error("`--scrape-examples-output-path` requires at least one \
+                     `--scrape-examples-target-crate`");
+        return 1;
+    }
+    if output_format.as_ref().map(|s| &**s) == Some("json") &&
+        !nightly_options::is_unstable_enabled(&matches) {
+        print_error("the `json` output format is unstable, pass `-Z unstable-options` to use it");
+        return 1;
+    }
     let res = acquire_input(PathBuf::from(input), externs, edition, &matches, move |out| {
         let Output { krate, passes, renderinfo } = out;
+        if scraping {
+            return 0;
+        }
+        if show_coverage {
+            coverage::run(&krate, output_format.as_ref().map(|s| &**s) == Some("json"));
+            return 0;
+        }
         info!("going to format");
         match output_format.as_ref().map(|s| &**s) {
             Some("html") | None => {
@@ -471,10 +562,16 @@ pub fn main_args(args: &[String]) -> isize {
                                   css_file_extension,
                                   renderinfo,
                                   sort_modules_alphabetically,
-                                  themes)
+                                  themes,
+                                  full_text_search)
                     .expect("failed to generate documentation");
                 0
             }
+            Some("json") => {
+                json::run(krate, renderinfo, output.unwrap_or(PathBuf::from("doc")))
+                    .expect("failed to generate JSON documentation");
+                0
+            }
             Some(s) => {
                 print_error(format!("unknown output format: {}", s));
                 1
@@ -574,9 +671,15 @@ where R: 'static + Send,
     let crate_name = matches.opt_str("crate-name");
     let crate_version = matches.opt_str("crate-version");
     let plugin_path = matches.opt_str("plugin-path");
+    let scrape_options = scrape_examples::Options {
+        output_path: matches.opt_str("scrape-examples-output-path").map(PathBuf::from),
+        target_crates: matches.opt_strs("scrape-examples-target-crate"),
+        with_examples: matches.opt_strs("with-examples").into_iter().map(PathBuf::from).collect(),
+    };
 
     info!("starting to run rustc");
     let display_warnings = matches.opt_present("display-warnings");
+    let lint_cap = matches.opt_str("cap-lints").and_then(|cap| lint::Level::from_str(&cap));
 
     let force_unstable_if_unmarked = matches.opt_strs("Z").iter().any(|x| {
         *x == "force-unstable-if-unmarked"
@@ -589,8 +692,8 @@ where R: 'static + Send,
 
         let (mut krate, renderinfo) =
             core::run_core(paths, cfgs, externs, Input::File(cratefile), triple, maybe_sysroot,
-                           display_warnings, crate_name.clone(),
-                           force_unstable_if_unmarked, edition);
+                           display_warnings, lint_cap, crate_name.clone(),
+                           force_unstable_if_unmarked, edition, scrape_options);
 
         info!("finished with rustc");
 
@@ -669,6 +772,11 @@ fn check_deprecated_options(matches: &getopts::Matches) {
     ];
 
     for flag in deprecated_flags.into_iter() {
+        // Asking for the JSON output is the one use of `--output-format` that
+        // is not deprecated.
+        if *flag == "output-format" && matches.opt_str("w").as_ref().map(|s| &**s) == Some("json") {
+            continue;
+        }
         if matches.opt_present(flag) {
             eprintln!("WARNING: the '{}' flag is considered deprecated", flag);
             eprintln!("WARNING: please see https://github.com/rust-lang/rust/issues/44136");
@@ -26,7 +26,7 @@ use html::render::reset_ids;
 use html::escape::Escape;
 use html::markdown;
 use html::markdown::{Markdown, MarkdownWithToc, find_testable_code};
-use test::{TestOptions, Collector};
+use test::{TestCache, TestOptions, Collector};
 
 /// Separate any lines at the start of the file that begin with `# ` or `%`.
 fn extract_leading_metadata<'a>(s: &'a str) -> (Vec<&'a str>, &'a str) {
@@ -139,8 +139,10 @@ pub fn render(input: &Path, mut output: PathBuf, matches: &getopts::Matches,
 
 /// Run any tests/code examples in the markdown file `input`.
 pub fn test(input: &str, cfgs: Vec<String>, libs: SearchPaths, externs: Externs,
-            mut test_args: Vec<String>, maybe_sysroot: Option<PathBuf>,
-            display_warnings: bool, linker: Option<PathBuf>, edition: Edition) -> isize {
+            mut test_args: Vec<String>, crate_name: Option<String>,
+            maybe_sysroot: Option<PathBuf>, display_warnings: bool,
+            linker: Option<PathBuf>, edition: Edition,
+            cache_dir: Option<PathBuf>) -> isize {
     let input_str = match load_string(input) {
         Ok(s) => s,
         Err(LoadStringError::ReadFail) => return 1,
@@ -150,12 +152,25 @@ pub fn test(input: &str, cfgs: Vec<String>, libs: SearchPaths, externs: Externs,
     let mut opts = TestOptions::default();
     opts.no_crate_inject = true;
     opts.display_warnings = display_warnings;
+    let cratename = crate_name.as_ref().map(|s| &s[..]);
+    let cache = match cache_dir {
+        Some(dir) => match TestCache::new(dir, cratename, &libs, &externs) {
+            Ok(cache) => Some(cache),
+            Err(e) => {
+                eprintln!("rustdoc: could not open the doctest cache: {}", e);
+                return 1;
+            }
+        },
+        None => None,
+    };
     let mut collector = Collector::new(input.to_owned(), cfgs, libs, externs,
                                        true, opts, maybe_sysroot, None,
                                        Some(PathBuf::from(input)),
-                                       linker, edition);
+                                       linker, edition, cache);
     find_testable_code(&input_str, &mut collector, DUMMY_SP, None);
     test_args.insert(0, "rustdoctest".to_string());
+    collector.select_tests(&test_args);
+    collector.report_cached();
     testing::test_main(&test_args, collector.tests,
                        testing::Options::new().display_output(display_warnings));
     0
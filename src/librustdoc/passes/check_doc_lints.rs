@@ -0,0 +1,152 @@
+// Copyright 2018 The Rust Project Developers. See the COPYRIGHT
+// file at the top-level directory of this distribution and at
+// http://rust-lang.org/COPYRIGHT.
+//
+// Licensed under the Apache License, Version 2.0 <LICENSE-APACHE or
+// http://www.apache.org/licenses/LICENSE-2.0> or the MIT license
+// <LICENSE-MIT or http://opensource.org/licenses/MIT>, at your
+// option. This file may not be copied, modified, or distributed
+// except according to those terms.
+
+//! Checks the documentation of the local items for mistakes. They are
+//! reported through lints, so that the crate can allow or deny them like any
+//! other lint.
+//!
+//! Unlike the other passes, this one needs the type context to find the lint
+//! levels, so it runs right after the crate is cleaned instead of being one
+//! of the `--passes`.
+
+use std::io;
+use std::panic::{self, AssertUnwindSafe};
+use std::sync::Arc;
+
+use errors;
+use errors::emitter::EmitterWriter;
+use rustc::lint::Lint;
+use rustc::lint::builtin::{BARE_URLS, INTRA_DOC_LINK_RESOLUTION_FAILURE};
+use rustc::lint::builtin::{INVALID_RUST_CODEBLOCKS, PRIVATE_INTRA_DOC_LINKS};
+use rustc::hir::def_id::DefId;
+use rustc::middle::privacy::AccessLevels;
+use rustc_data_structures::sync::Lrc;
+use syntax::ast::NodeId;
+use syntax::codemap::{CodeMap, FilePathMapping};
+use syntax::parse::{self, ParseSess};
+use syntax_pos::{FileName, Span};
+
+use clean::{self, Item, LinkFailure};
+use core::DocContext;
+use fold::DocFolder;
+use html::markdown;
+use test::{self, TestOptions};
+
+/// The lints reported by this pass, which are the only ones rustdoc shows.
+pub const DOC_LINTS: &[&Lint] = &[
+    INTRA_DOC_LINK_RESOLUTION_FAILURE,
+    PRIVATE_INTRA_DOC_LINKS,
+    BARE_URLS,
+    INVALID_RUST_CODEBLOCKS,
+];
+
+pub fn check_doc_lints(cx: &DocContext, krate: clean::Crate) -> clean::Crate {
+    let access_levels = krate.access_levels.clone();
+    DocLintChecker { cx, access_levels }.fold_crate(krate)
+}
+
+struct DocLintChecker<'a, 'tcx: 'a, 'rcx: 'a> {
+    cx: &'a DocContext<'a, 'tcx, 'rcx>,
+    access_levels: Arc<AccessLevels<DefId>>,
+}
+
+impl<'a, 'tcx, 'rcx> DocLintChecker<'a, 'tcx, 'rcx> {
+    fn check(&self, item: &Item) {
+        let node_id = match self.cx.tcx.hir.as_local_node_id(item.def_id) {
+            Some(node_id) => node_id,
+            None => return,
+        };
+        let span = match (item.attrs.doc_strings.first(), item.attrs.doc_strings.last()) {
+            (Some(first), Some(last)) => first.span().to(last.span()),
+            _ => return,
+        };
+        let dox = item.attrs.collapsed_doc_value().unwrap_or_default();
+
+        for failure in &item.attrs.link_failures {
+            match *failure {
+                LinkFailure::Unresolved(ref link) => {
+                    self.lint(INTRA_DOC_LINK_RESOLUTION_FAILURE, node_id, span,
+                              &format!("`[{}]` cannot be resolved, ignoring it", link),
+                              "to write brackets that are not a link, escape them as `\\[` \
+                               and `\\]`");
+                }
+                LinkFailure::Ambiguous { ref message, ref help, .. } => {
+                    self.lint(INTRA_DOC_LINK_RESOLUTION_FAILURE, node_id, span, message, help);
+                }
+            }
+        }
+
+        if self.access_levels.is_exported(item.def_id) {
+            for &(ref link, target, _) in &item.attrs.links {
+                let target = match target {
+                    Some(target) => target,
+                    None => continue,
+                };
+                if target.is_local() && !self.access_levels.is_exported(target) {
+                    self.lint(PRIVATE_INTRA_DOC_LINKS, node_id, span,
+                              &format!("public documentation links to `{}`, which is private",
+                                       link.replace("`", "")),
+                              "the link leads to a page that is not generated unless private \
+                               items are documented");
+                }
+            }
+        }
+
+        for url in markdown::bare_urls(&dox) {
+            self.lint(BARE_URLS, node_id, span,
+                      &format!("this URL is not a link: {}", url),
+                      &format!("write it as `<{}>` to make it one", url));
+        }
+
+        for code in markdown::rust_code_blocks(&dox) {
+            if !parses(&code) {
+                self.lint(INVALID_RUST_CODEBLOCKS, node_id, span,
+                          "could not parse code block as Rust code",
+                          "mark blocks that do not contain Rust code as `text`");
+            }
+        }
+    }
+
+    fn lint(&self, lint: &'static Lint, node_id: NodeId, span: Span, msg: &str, help: &str) {
+        self.cx.tcx.struct_span_lint_node(lint, node_id, span, msg)
+                   .help(help)
+                   .emit();
+    }
+}
+
+impl<'a, 'tcx, 'rcx> DocFolder for DocLintChecker<'a, 'tcx, 'rcx> {
+    fn fold_item(&mut self, item: Item) -> Option<Item> {
+        self.check(&item);
+        self.fold_item_recur(item)
+    }
+}
+
+/// Whether `code` parses as a doctest, that is, once wrapped the way doctests
+/// are.
+fn parses(code: &str) -> bool {
+    let (source, _) = test::make_test(code, None, false, &TestOptions::default());
+    let codemap = Lrc::new(CodeMap::new(FilePathMapping::empty()));
+    let emitter = EmitterWriter::new(box io::sink(), Some(codemap.clone()), false, false);
+    let handler = errors::Handler::with_emitter(false, false, box emitter);
+    let sess = ParseSess::with_span_handler(handler, codemap);
+    let name = FileName::Custom("code block".to_owned());
+
+    // Some errors, like those of the lexer, unwind instead of being returned.
+    let result = panic::catch_unwind(AssertUnwindSafe(|| {
+        match parse::parse_crate_from_source_str(name, source, &sess) {
+            Ok(_) => !sess.span_diagnostic.has_errors(),
+            Err(mut diag) => {
+                diag.cancel();
+                false
+            }
+        }
+    }));
+    result.unwrap_or(false)
+}
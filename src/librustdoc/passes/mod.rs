@@ -36,6 +36,9 @@ pub use self::unindent_comments::unindent_comments;
 mod propagate_doc_cfg;
 pub use self::propagate_doc_cfg::propagate_doc_cfg;
 
+mod check_doc_lints;
+pub use self::check_doc_lints::{check_doc_lints, DOC_LINTS};
+
 type Pass = (&'static str,                                      // name
              fn(clean::Crate) -> plugins::PluginResult,         // fn
              &'static str);                                     // description
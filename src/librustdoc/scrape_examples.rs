@@ -0,0 +1,330 @@
+// Copyright 2018 The Rust Project Developers. See the COPYRIGHT
+// file at the top-level directory of this distribution and at
+// http://rust-lang.org/COPYRIGHT.
+//
+// Licensed under the Apache License, Version 2.0 <LICENSE-APACHE or
+// http://www.apache.org/licenses/LICENSE-2.0> or the MIT license
+// <LICENSE-MIT or http://opensource.org/licenses/MIT>, at your
+// option. This file may not be copied, modified, or distributed
+// except according to those terms.
+
+//! Examples of real uses of a crate's functions, scraped from the crates that
+//! use it.
+//!
+//! Scraping is done by running rustdoc with `--scrape-examples-output-path` on
+//! a crate that uses the documented one, such as one of its examples or tests.
+//! Every call it makes to a function or method of the target crates is
+//! written to a JSON file, keyed by the def path of the function, which is the
+//! same whichever crate it is computed from. Documenting the target crate with
+//! `--with-examples` then shows a few of those calls on the page of each
+//! function.
+
+use std::cmp;
+use std::collections::BTreeMap;
+use std::fs::{self, File};
+use std::io::{self, BufWriter, Write};
+use std::mem;
+use std::path::{Path, PathBuf};
+
+use rustc::hir;
+use rustc::hir::def::Def;
+use rustc::hir::def_id::{CrateNum, DefId};
+use rustc::hir::intravisit::{self, NestedVisitorMap, Visitor};
+use rustc::ty::{self, TyCtxt};
+use rustc::util::nodemap::{FxHashMap, FxHashSet};
+use serialize::json::{Json, ToJson};
+use syntax::ext::hygiene::SyntaxContext;
+use syntax_pos::{FileName, Span};
+
+/// The version of the format of the files of scraped calls.
+const FORMAT_VERSION: u64 = 1;
+
+/// How many lines of the item a call is in are shown before and after it.
+const CONTEXT_LINES: usize = 5;
+
+#[derive(Clone, Default)]
+pub struct Options {
+    /// Where to write the calls found in the crate, if it is being scraped.
+    pub output_path: Option<PathBuf>,
+    /// The crates whose functions the calls are scraped for.
+    pub target_crates: Vec<String>,
+    /// Files of calls written by earlier scraping runs, to show in the
+    /// documentation.
+    pub with_examples: Vec<PathBuf>,
+}
+
+/// A call to a function, as shown on the function's page.
+#[derive(Clone, Debug)]
+pub struct CallExample {
+    /// The file the call is in, as the scraping run was given it.
+    pub path: PathBuf,
+    /// The first and last line of the call, starting at 1.
+    pub call_lines: (usize, usize),
+    /// The source around the call, within the item it is in.
+    pub snippet: String,
+}
+
+/// Where a call was found, as written to the file of scraped calls.
+#[derive(Clone, PartialEq, Eq, PartialOrd, Ord)]
+struct CallLocation {
+    path: PathBuf,
+    /// The first and last line of the item the call is in.
+    item_lines: (usize, usize),
+    /// The first and last line of the call itself.
+    call_lines: (usize, usize),
+}
+
+impl ToJson for CallLocation {
+    fn to_json(&self) -> Json {
+        let mut data = BTreeMap::new();
+        data.insert("path".to_owned(), self.path.to_string_lossy().to_json());
+        data.insert("item".to_owned(), vec![self.item_lines.0, self.item_lines.1].to_json());
+        data.insert("call".to_owned(), vec![self.call_lines.0, self.call_lines.1].to_json());
+        Json::Object(data)
+    }
+}
+
+impl CallLocation {
+    fn from_json(json: &Json) -> Option<CallLocation> {
+        fn lines(json: Option<&Json>) -> Option<(usize, usize)> {
+            let lines = json?.as_array()?;
+            if lines.len() != 2 {
+                return None;
+            }
+            Some((lines[0].as_u64()? as usize, lines[1].as_u64()? as usize))
+        }
+
+        let object = json.as_object()?;
+        Some(CallLocation {
+            path: PathBuf::from(object.get("path")?.as_string()?),
+            item_lines: lines(object.get("item"))?,
+            call_lines: lines(object.get("call"))?,
+        })
+    }
+}
+
+/// The key the calls to `def_id` are recorded under: the name of its crate
+/// followed by its def path.
+fn def_key(tcx: TyCtxt, def_id: DefId) -> String {
+    format!("{}{}",
+            tcx.original_crate_name(def_id.krate),
+            tcx.def_path(def_id).to_string_no_crate())
+}
+
+struct CallFinder<'a, 'tcx: 'a> {
+    tcx: TyCtxt<'a, 'tcx, 'tcx>,
+    target_crates: FxHashSet<CrateNum>,
+    /// The typeck tables of the body being visited.
+    tables: Option<&'a ty::TypeckTables<'tcx>>,
+    /// The span of the outermost item whose body is being visited.
+    item_span: Option<Span>,
+    calls: BTreeMap<String, Vec<CallLocation>>,
+}
+
+impl<'a, 'tcx> Visitor<'tcx> for CallFinder<'a, 'tcx> {
+    fn nested_visit_map<'this>(&'this mut self) -> NestedVisitorMap<'this, 'tcx> {
+        NestedVisitorMap::OnlyBodies(&self.tcx.hir)
+    }
+
+    fn visit_nested_body(&mut self, id: hir::BodyId) {
+        let orig_tables = mem::replace(&mut self.tables, Some(self.tcx.body_tables(id)));
+        let orig_item_span = self.item_span;
+        if orig_item_span.is_none() {
+            self.item_span = Some(self.tcx.hir.span(self.tcx.hir.body_owner(id)));
+        }
+        let body = self.tcx.hir.body(id);
+        self.visit_body(body);
+        self.tables = orig_tables;
+        self.item_span = orig_item_span;
+    }
+
+    fn visit_expr(&mut self, ex: &'tcx hir::Expr) {
+        intravisit::walk_expr(self, ex);
+
+        let (tables, item_span) = match (self.tables, self.item_span) {
+            (Some(tables), Some(item_span)) => (tables, item_span),
+            _ => return,
+        };
+        // Calls written by a macro have no source of their own to show.
+        if ex.span.ctxt() != SyntaxContext::empty() {
+            return;
+        }
+        let def = match ex.node {
+            hir::ExprCall(ref f, _) => match f.node {
+                hir::ExprPath(ref qpath) => tables.qpath_def(qpath, f.hir_id),
+                _ => return,
+            },
+            hir::ExprMethodCall(..) => match tables.type_dependent_defs().get(ex.hir_id) {
+                Some(&def) => def,
+                None => return,
+            },
+            _ => return,
+        };
+        let def_id = match def {
+            Def::Fn(def_id) | Def::Method(def_id) => def_id,
+            _ => return,
+        };
+        if !self.target_crates.contains(&def_id.krate) {
+            return;
+        }
+
+        let codemap = self.tcx.sess.codemap();
+        let call_lo = codemap.lookup_char_pos(ex.span.lo());
+        let path = match call_lo.file.name {
+            FileName::Real(ref path) => path.clone(),
+            _ => return,
+        };
+        let location = CallLocation {
+            path,
+            item_lines: (codemap.lookup_char_pos(item_span.lo()).line,
+                         codemap.lookup_char_pos(item_span.hi()).line),
+            call_lines: (call_lo.line, codemap.lookup_char_pos(ex.span.hi()).line),
+        };
+        self.calls.entry(def_key(self.tcx, def_id)).or_insert_with(Vec::new).push(location);
+    }
+}
+
+/// Writes the calls the local crate makes to functions of the target crates
+/// to `path`.
+pub fn scrape<'a, 'tcx>(tcx: TyCtxt<'a, 'tcx, 'tcx>, options: &Options, path: &Path)
+                        -> io::Result<()> {
+    let target_crates = tcx.crates().iter().cloned().filter(|&cnum| {
+        let name = tcx.original_crate_name(cnum);
+        options.target_crates.iter().any(|target| *target == *name.as_str())
+    }).collect();
+    let mut finder = CallFinder {
+        tcx,
+        target_crates,
+        tables: None,
+        item_span: None,
+        calls: BTreeMap::new(),
+    };
+    tcx.hir.krate().visit_all_item_likes(&mut finder.as_deep_visitor());
+
+    let calls = finder.calls.into_iter().map(|(key, mut locations)| {
+        locations.sort();
+        locations.dedup();
+        (key, locations.to_json())
+    }).collect::<BTreeMap<_, _>>();
+    let mut data = BTreeMap::new();
+    data.insert("format_version".to_owned(), FORMAT_VERSION.to_json());
+    data.insert("calls".to_owned(), Json::Object(calls));
+
+    let mut out = BufWriter::new(File::create(path)?);
+    write!(out, "{}", Json::Object(data))?;
+    out.flush()
+}
+
+fn read_calls(path: &Path, calls: &mut BTreeMap<String, Vec<CallLocation>>)
+              -> Result<(), String> {
+    let contents = fs::read_to_string(path).map_err(|e| e.to_string())?;
+    let json = Json::from_str(&contents).map_err(|e| e.to_string())?;
+    match json.find("format_version").and_then(|v| v.as_u64()) {
+        Some(FORMAT_VERSION) => {}
+        Some(v) => return Err(format!("unsupported format version {}", v)),
+        None => return Err("missing format version".to_owned()),
+    }
+    let found = json.find("calls")
+                    .and_then(|c| c.as_object())
+                    .ok_or_else(|| "missing calls".to_owned())?;
+    for (key, locations) in found {
+        let locations = locations.as_array().ok_or_else(|| format!("invalid calls of `{}`", key))?;
+        let entry = calls.entry(key.clone()).or_insert_with(Vec::new);
+        for location in locations {
+            entry.push(CallLocation::from_json(location)
+                           .ok_or_else(|| format!("invalid call of `{}`", key))?);
+        }
+    }
+    Ok(())
+}
+
+/// Extracts the lines of `location` that are shown on the function's page,
+/// reading its file through `sources`.
+fn call_example(location: &CallLocation,
+                sources: &mut FxHashMap<PathBuf, Option<Vec<String>>>)
+                -> Option<CallExample> {
+    let lines = sources.entry(location.path.clone()).or_insert_with(|| {
+        match fs::read_to_string(&location.path) {
+            Ok(contents) => Some(contents.lines().map(|l| l.to_owned()).collect()),
+            Err(e) => {
+                info!("could not read the example `{}`: {}", location.path.display(), e);
+                None
+            }
+        }
+    }).as_ref()?;
+
+    let (call_lo, call_hi) = location.call_lines;
+    let first_line = cmp::max(location.item_lines.0, call_lo.saturating_sub(CONTEXT_LINES));
+    let last_line = cmp::min(location.item_lines.1, call_hi + CONTEXT_LINES);
+    if first_line == 0 || last_line < first_line || last_line > lines.len() {
+        return None;
+    }
+    Some(CallExample {
+        path: location.path.clone(),
+        call_lines: location.call_lines,
+        snippet: lines[first_line - 1..last_line].join("\n"),
+    })
+}
+
+/// Loads the files of scraped calls in `paths`, returning the calls found to
+/// every function of the local crate.
+pub fn load<'a, 'tcx>(tcx: TyCtxt<'a, 'tcx, 'tcx>, paths: &[PathBuf])
+                      -> Result<FxHashMap<DefId, Vec<CallExample>>, String> {
+    let mut calls = BTreeMap::new();
+    for path in paths {
+        read_calls(path, &mut calls)
+            .map_err(|e| format!("could not load the examples in `{}`: {}", path.display(), e))?;
+    }
+
+    let krate = tcx.hir.krate();
+    let functions = krate.items.values().filter_map(|item| match item.node {
+        hir::ItemFn(..) => Some(item.id),
+        _ => None,
+    }).chain(krate.trait_items.values().filter_map(|item| match item.node {
+        hir::TraitItemKind::Method(..) => Some(item.id),
+        _ => None,
+    })).chain(krate.impl_items.values().filter_map(|item| match item.node {
+        hir::ImplItemKind::Method(..) => Some(item.id),
+        _ => None,
+    }));
+
+    let mut examples = FxHashMap();
+    let mut sources = FxHashMap();
+    for id in functions {
+        let def_id = tcx.hir.local_def_id(id);
+        if let Some(locations) = calls.get_mut(&def_key(tcx, def_id)) {
+            locations.sort();
+            locations.dedup();
+            let found = locations.iter()
+                                 .filter_map(|l| call_example(l, &mut sources))
+                                 .collect::<Vec<_>>();
+            if !found.is_empty() {
+                examples.insert(def_id, found);
+            }
+        }
+    }
+    Ok(examples)
+}
+
+#[cfg(test)]
+mod tests {
+    use std::path::PathBuf;
+
+    use serialize::json::ToJson;
+
+    use super::CallLocation;
+
+    #[test]
+    fn call_location_round_trip() {
+        let location = CallLocation {
+            path: PathBuf::from("examples/ex.rs"),
+            item_lines: (3, 12),
+            call_lines: (7, 8),
+        };
+        let json = location.to_json();
+        assert_eq!(json.to_string(),
+                   r#"{"call":[7,8],"item":[3,12],"path":"examples/ex.rs"}"#);
+        assert!(CallLocation::from_json(&json) == Some(location));
+        assert!(CallLocation::from_json(&"examples/ex.rs".to_json()).is_none());
+    }
+}
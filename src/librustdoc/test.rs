@@ -8,8 +8,11 @@
 // option. This file may not be copied, modified, or distributed
 // except according to those terms.
 
+use std::collections::hash_map::DefaultHasher;
 use std::env;
-use std::ffi::OsString;
+use std::ffi::{OsStr, OsString};
+use std::fs::{self, File};
+use std::hash::{Hash, Hasher};
 use std::io::prelude::*;
 use std::io;
 use std::path::{Path, PathBuf};
@@ -26,6 +29,7 @@ use rustc::hir::intravisit;
 use rustc::session::{self, CompileIncomplete, config};
 use rustc::session::config::{OutputType, OutputTypes, Externs};
 use rustc::session::search_paths::{SearchPaths, PathKind};
+use rustc::util::nodemap::FxHashSet;
 use rustc_metadata::dynamic_lib::DynamicLibrary;
 use tempdir::TempDir;
 use rustc_driver::{self, driver, Compilation};
@@ -44,7 +48,7 @@ use errors::emitter::ColorConfig;
 use clean::Attributes;
 use html::markdown;
 
-#[derive(Clone, Default)]
+#[derive(Clone, Default, Hash)]
 pub struct TestOptions {
     /// Whether to disable the default `extern crate my_crate;` when creating doctests.
     pub no_crate_inject: bool,
@@ -64,7 +68,8 @@ pub fn run(input_path: &Path,
            maybe_sysroot: Option<PathBuf>,
            display_warnings: bool,
            linker: Option<PathBuf>,
-           edition: Edition)
+           edition: Edition,
+           cache_dir: Option<PathBuf>)
            -> isize {
     let input = config::Input::File(input_path.to_owned());
 
@@ -120,6 +125,16 @@ pub fn run(input_path: &Path,
     });
     let mut opts = scrape_test_config(hir_forest.krate());
     opts.display_warnings |= display_warnings;
+    let cache = match cache_dir {
+        Some(dir) => match TestCache::new(dir, Some(&crate_name), &libs, &externs) {
+            Ok(cache) => Some(cache),
+            Err(e) => {
+                eprintln!("rustdoc: could not open the doctest cache: {}", e);
+                return 1;
+            }
+        },
+        None => None,
+    };
     let mut collector = Collector::new(crate_name,
                                        cfgs,
                                        libs,
@@ -130,7 +145,8 @@ pub fn run(input_path: &Path,
                                        Some(codemap),
                                        None,
                                        linker,
-                                       edition);
+                                       edition,
+                                       cache);
 
     {
         let map = hir::map::map_crate(&sess, &cstore, &mut hir_forest, &defs);
@@ -147,6 +163,8 @@ pub fn run(input_path: &Path,
 
     test_args.insert(0, "rustdoctest".to_string());
 
+    collector.select_tests(&test_args);
+    collector.report_cached();
     testing::test_main(&test_args,
                        collector.tests.into_iter().collect(),
                        testing::Options::new().display_output(display_warnings));
@@ -201,28 +219,7 @@ fn run_test(test: &str, cratename: &str, filename: &FileName, line: usize,
         name: filename.to_owned(),
         input: test.to_owned(),
     };
-    let outputs = OutputTypes::new(&[(OutputType::Exe, None)]);
-
-    let sessopts = config::Options {
-        maybe_sysroot: maybe_sysroot.or_else(
-            || Some(env::current_exe().unwrap().parent().unwrap().parent().unwrap().to_path_buf())),
-        search_paths: libs,
-        crate_types: vec![config::CrateTypeExecutable],
-        output_types: outputs,
-        externs,
-        cg: config::CodegenOptions {
-            prefer_dynamic: true,
-            linker,
-            .. config::basic_codegen_options()
-        },
-        test: as_test_harness,
-        unstable_features: UnstableFeatures::from_environment(),
-        debugging_opts: config::DebuggingOptions {
-            edition,
-            ..config::basic_debugging_options()
-        },
-        ..config::basic_options().clone()
-    };
+    let sessopts = test_options(libs, externs, as_test_harness, maybe_sysroot, linker, edition);
 
     // Shuffle around a few input and output handles here. We're going to pass
     // an explicit handle into rustc to collect output messages, but we also
@@ -261,6 +258,68 @@ fn run_test(test: &str, cratename: &str, filename: &FileName, line: usize,
     // Compile the code
     let diagnostic_handler = errors::Handler::with_emitter(true, false, box emitter);
 
+    let outdir = TempDir::new("rustdoctest").ok().expect("rustdoc needs a tempdir");
+    let (compile_result, libdir) = compile(input, sessopts, diagnostic_handler, codemap, cfgs,
+                                           outdir.path(), no_run);
+
+    match (compile_result, compile_fail) {
+        (Ok(()), true) => {
+            panic!("test compiled while it wasn't supposed to")
+        }
+        (Ok(()), false) => {}
+        (Err(()), true) => {
+            if error_codes.len() > 0 {
+                let out = String::from_utf8(data.lock().unwrap().to_vec()).unwrap();
+                error_codes.retain(|err| !out.contains(err));
+            }
+        }
+        (Err(()), false) => {
+            panic!("couldn't compile the test")
+        }
+    }
+
+    if error_codes.len() > 0 {
+        panic!("Some expected error codes were not found: {:?}", error_codes);
+    }
+
+    if no_run { return }
+
+    run_executable(&outdir.path().join("rust_out"), None, &libdir, should_panic);
+}
+
+fn test_options(libs: SearchPaths, externs: Externs, as_test_harness: bool,
+                maybe_sysroot: Option<PathBuf>, linker: Option<PathBuf>,
+                edition: Edition) -> config::Options {
+    let outputs = OutputTypes::new(&[(OutputType::Exe, None)]);
+
+    config::Options {
+        maybe_sysroot: maybe_sysroot.or_else(
+            || Some(env::current_exe().unwrap().parent().unwrap().parent().unwrap().to_path_buf())),
+        search_paths: libs,
+        crate_types: vec![config::CrateTypeExecutable],
+        output_types: outputs,
+        externs,
+        cg: config::CodegenOptions {
+            prefer_dynamic: true,
+            linker,
+            .. config::basic_codegen_options()
+        },
+        test: as_test_harness,
+        unstable_features: UnstableFeatures::from_environment(),
+        debugging_opts: config::DebuggingOptions {
+            edition,
+            ..config::basic_debugging_options()
+        },
+        ..config::basic_options().clone()
+    }
+}
+
+/// Compiles `input` to an executable named `rust_out` in `outdir`. Returns
+/// whether it compiled, and the directory of the target libraries the
+/// executable needs to run.
+fn compile(input: config::Input, sessopts: config::Options,
+           diagnostic_handler: errors::Handler, codemap: Lrc<CodeMap>,
+           cfgs: Vec<String>, outdir: &Path, no_run: bool) -> (Result<(), ()>, PathBuf) {
     let mut sess = session::build_session_(
         sessopts, None, diagnostic_handler, codemap,
     );
@@ -268,12 +327,11 @@ fn run_test(test: &str, cratename: &str, filename: &FileName, line: usize,
     let cstore = CStore::new(trans.metadata_loader());
     rustc_lint::register_builtins(&mut sess.lint_store.borrow_mut(), Some(&sess));
 
-    let outdir = Mutex::new(TempDir::new("rustdoctest").ok().expect("rustdoc needs a tempdir"));
     let libdir = sess.target_filesearch(PathKind::All).get_lib_path();
     let mut control = driver::CompileController::basic();
     sess.parse_sess.config =
-        config::build_configuration(&sess, config::parse_cfgspecs(cfgs.clone()));
-    let out = Some(outdir.lock().unwrap().path().to_path_buf());
+        config::build_configuration(&sess, config::parse_cfgspecs(cfgs));
+    let out = Some(outdir.to_path_buf());
 
     if no_run {
         control.after_analysis.stop = Compilation::Stop;
@@ -297,41 +355,23 @@ fn run_test(test: &str, cratename: &str, filename: &FileName, line: usize,
         Ok(Ok(())) | Ok(Err(CompileIncomplete::Stopped)) => Ok(()),
         Err(_) | Ok(Err(CompileIncomplete::Errored(_))) => Err(())
     };
+    (compile_result, libdir)
+}
 
-    match (compile_result, compile_fail) {
-        (Ok(()), true) => {
-            panic!("test compiled while it wasn't supposed to")
-        }
-        (Ok(()), false) => {}
-        (Err(()), true) => {
-            if error_codes.len() > 0 {
-                let out = String::from_utf8(data.lock().unwrap().to_vec()).unwrap();
-                error_codes.retain(|err| !out.contains(err));
-            }
-        }
-        (Err(()), false) => {
-            panic!("couldn't compile the test")
-        }
-    }
-
-    if error_codes.len() > 0 {
-        panic!("Some expected error codes were not found: {:?}", error_codes);
-    }
-
-    if no_run { return }
-
-    // Run the code!
-    //
+/// Runs a compiled test, passing it `arg` if there is one, and panics if it
+/// does not succeed or fail as expected.
+fn run_executable(exe: &Path, arg: Option<&str>, libdir: &Path, should_panic: bool) {
     // We're careful to prepend the *target* dylib search path to the child's
     // environment to ensure that the target loads the right libraries at
     // runtime. It would be a sad day if the *host* libraries were loaded as a
     // mistake.
-    let mut cmd = Command::new(&outdir.lock().unwrap().path().join("rust_out"));
+    let mut cmd = Command::new(exe);
+    cmd.args(arg);
     let var = DynamicLibrary::envvar();
     let newpath = {
         let path = env::var_os(var).unwrap_or(OsString::new());
         let mut path = env::split_paths(&path).collect::<Vec<_>>();
-        path.insert(0, libdir.clone());
+        path.insert(0, libdir.to_path_buf());
         env::join_paths(path).unwrap()
     };
     cmd.env(var, &newpath);
@@ -394,9 +434,24 @@ pub fn make_test(s: &str,
         }
     }
 
+    if dont_insert_main || has_main(s) {
+        prog.push_str(everything_else);
+    } else {
+        prog.push_str("fn main() {\n");
+        line_offset += 1;
+        prog.push_str(everything_else);
+        prog.push_str("\n}");
+    }
+
+    info!("final test program: {}", prog);
+
+    (prog, line_offset)
+}
+
+fn has_main(s: &str) -> bool {
     // FIXME (#21299): prefer libsyntax or some other actual parser over this
     // best-effort ad hoc approach
-    let already_has_main = s.lines()
+    s.lines()
         .map(|line| {
             let comment = line.find("//");
             if let Some(comment_begins) = comment {
@@ -405,20 +460,136 @@ pub fn make_test(s: &str,
                 line
             }
         })
-        .any(|code| code.contains("fn main"));
+        .any(|code| code.contains("fn main"))
+}
 
-    if dont_insert_main || already_has_main {
-        prog.push_str(everything_else);
-    } else {
-        prog.push_str("fn main() {\n");
-        line_offset += 1;
-        prog.push_str(everything_else);
-        prog.push_str("\n}");
+/// Whether a test can be compiled together with the other tests of the crate
+/// in a single binary, rather than on its own. That is the case for tests
+/// that are run and that are nothing but the body of their `main` function:
+/// no crate attributes, no `extern crate` and no `fn main` of their own.
+fn can_merge(test: &str, should_ignore: bool, no_run: bool, as_test_harness: bool,
+             compile_fail: bool, error_codes: &[String]) -> bool {
+    !should_ignore && !no_run && !as_test_harness && !compile_fail && error_codes.is_empty() &&
+        partition_source(test).0.trim().is_empty() &&
+        !test.contains("extern crate") &&
+        !has_main(test)
+}
+
+/// A test compiled together with the other tests of the crate into a single
+/// binary (see `can_merge`).
+struct MergedTest {
+    name: String,
+    code: String,
+    /// The file the test comes from, and the line of that file its code
+    /// starts on.
+    filename: FileName,
+    line: usize,
+    /// Whether the test runner runs the test, given its arguments.
+    selected: bool,
+}
+
+/// Makes the source of the binary that runs the merged tests `tests`, given
+/// with their index and the file their module is written to (see
+/// `make_merged_test_module`). The binary runs the test whose index it is
+/// given as its argument.
+fn make_merged_test(tests: &[(usize, &str, PathBuf)], cratename: &str,
+                    opts: &TestOptions) -> String {
+    let mut prog = String::new();
+
+    // The same crate attributes and `extern crate` as `make_test` uses for a
+    // single test.
+    if opts.attrs.is_empty() && !opts.display_warnings {
+        prog.push_str("#![allow(unused)]\n");
+    }
+    for attr in &opts.attrs {
+        prog.push_str(&format!("#![{}]\n", attr));
+    }
+    if !opts.no_crate_inject && cratename != "std" &&
+        tests.iter().any(|&(_, code, _)| code.contains(cratename)) {
+        prog.push_str(&format!("extern crate {};\n", cratename));
     }
 
-    info!("final test program: {}", prog);
+    for &(i, _, ref path) in tests {
+        prog.push_str(&format!("#[path = {:?}]\nmod __doctest_{};\n",
+                               path.display().to_string(), i));
+    }
 
-    (prog, line_offset)
+    prog.push_str("fn main() {\n");
+    prog.push_str("    match ::std::env::args().nth(1).as_ref().map(|s| &s[..]) {\n");
+    for &(i, _, _) in tests {
+        prog.push_str(&format!("        Some(\"{0}\") => __doctest_{0}::main(),\n", i));
+    }
+    prog.push_str("        _ => panic!(\"unknown doctest\"),\n");
+    prog.push_str("    }\n");
+    prog.push_str("}\n");
+
+    info!("merged test program: {}", prog);
+
+    prog
+}
+
+/// Makes the module of a merged test, whose `code` starts on line `line` of
+/// its file. The code is put on the same lines as in that file, so that
+/// panics and `line!()` within the test give the lines of that file.
+fn make_merged_test_module(code: &str, line: usize) -> String {
+    let blank_lines = code.lines().take_while(|line| line.trim().is_empty()).count();
+    let mut module = "\n".repeat((line + blank_lines).saturating_sub(1));
+    // The glob import lets the test name the crate the way it could at the
+    // crate root.
+    module.push_str("#[allow(unused_imports)] use super::*; pub fn main() { ");
+    module.push_str(code.trim());
+    module.push_str("\n}\n");
+    module
+}
+
+/// Compiles the selected merged tests into a binary, returning the directory
+/// it is in and the directory of the target libraries it needs, or `None` if
+/// they do not compile together.
+fn compile_merged_test(tests: &[MergedTest], cratename: &str, cfgs: Vec<String>,
+                       libs: SearchPaths, externs: Externs, opts: &TestOptions,
+                       maybe_sysroot: Option<PathBuf>, linker: Option<PathBuf>,
+                       edition: Edition) -> Option<(TempDir, PathBuf)> {
+    let outdir = TempDir::new("rustdoctest").ok().expect("rustdoc needs a tempdir");
+    let mut sessopts = test_options(libs, externs, false, maybe_sysroot, linker, edition);
+
+    // Every test is written to a file named like the one it comes from, in a
+    // directory of its own that is remapped to the directory of that file, so
+    // that `file!()` gives the file the test comes from.
+    let mut modules = Vec::new();
+    for (i, test) in tests.iter().enumerate().filter(|&(_, test)| test.selected) {
+        let filename = PathBuf::from(test.filename.to_string());
+        let dir = outdir.path().join("src").join(i.to_string());
+        let path = dir.join(filename.file_name().unwrap_or(OsStr::new("doctest.rs")));
+        let module = make_merged_test_module(&test.code, test.line);
+        if let Err(e) = fs::create_dir_all(&dir).and_then(|_| fs::write(&path, module)) {
+            info!("could not write merged doctest to {}: {}", path.display(), e);
+            return None;
+        }
+        let filedir = filename.parent().map(|dir| dir.to_path_buf()).unwrap_or_default();
+        sessopts.remap_path_prefix.push((dir, filedir));
+        modules.push((i, &test.code[..], path));
+    }
+
+    let input = config::Input::Str {
+        name: FileName::Custom("merged doctests".to_owned()),
+        input: make_merged_test(&modules, cratename, opts),
+    };
+    let codemap = Lrc::new(CodeMap::new(sessopts.file_path_mapping()));
+    // The errors are of no interest: every test is then compiled on its own,
+    // which reports them with the right line numbers.
+    let emitter = errors::emitter::EmitterWriter::new(box io::sink(),
+                                                      Some(codemap.clone()),
+                                                      false,
+                                                      false);
+    let diagnostic_handler = errors::Handler::with_emitter(true, false, box emitter);
+
+    match compile(input, sessopts, diagnostic_handler, codemap, cfgs, outdir.path(), false) {
+        (Ok(()), libdir) => Some((outdir, libdir)),
+        (Err(()), _) => {
+            info!("merged doctests failed to compile, compiling them one by one");
+            None
+        }
+    }
 }
 
 // FIXME(aburka): use a real parser to deal with multiline attributes
@@ -446,6 +617,84 @@ fn partition_source(s: &str) -> (String, String) {
     (before, after)
 }
 
+/// The doctests that passed in earlier runs, kept with `--doctest-cache-dir`
+/// as an empty file for every test that passed, named after a hash of
+/// everything its outcome depends on.
+pub struct TestCache {
+    dir: PathBuf,
+    /// A hash of the version of rustdoc and of the libraries the tests are
+    /// linked against.
+    libs_hash: u64,
+}
+
+impl TestCache {
+    /// Opens the cache in `dir`, creating it if needed. The libraries hashed
+    /// are those given with `--extern` and those of `cratename` found in the
+    /// search paths, so that the tests run again whenever the crate is
+    /// rebuilt.
+    pub fn new(dir: PathBuf, cratename: Option<&str>, libs: &SearchPaths,
+               externs: &Externs) -> io::Result<TestCache> {
+        fs::create_dir_all(&dir)?;
+
+        let mut paths = Vec::new();
+        if let Some(cratename) = cratename {
+            let prefix = format!("lib{}", cratename.replace('-', "_"));
+            for (search_dir, _) in libs.iter(PathKind::All) {
+                let entries = match fs::read_dir(search_dir) {
+                    Ok(entries) => entries,
+                    Err(_) => continue,
+                };
+                for entry in entries.filter_map(|e| e.ok()) {
+                    if is_library_of(&entry.file_name().to_string_lossy(), &prefix) {
+                        paths.push(entry.path());
+                    }
+                }
+            }
+        }
+        for (_, locations) in externs.iter() {
+            paths.extend(locations.iter().map(PathBuf::from));
+        }
+        paths.sort();
+
+        let mut hasher = DefaultHasher::new();
+        option_env!("CFG_VERSION").hash(&mut hasher);
+        for path in &paths {
+            path.hash(&mut hasher);
+            // Rebuilding a library changes its length or its modification
+            // time, which is much cheaper to look at than its contents. A
+            // library that cannot be read is hashed as missing, which is how
+            // the compilation of the test will see it too.
+            fs::metadata(path).ok().map(|m| (m.len(), m.modified().ok())).hash(&mut hasher);
+        }
+        Ok(TestCache { dir, libs_hash: hasher.finish() })
+    }
+
+    fn key<T: Hash>(&self, test: &T) -> String {
+        let mut hasher = DefaultHasher::new();
+        self.libs_hash.hash(&mut hasher);
+        test.hash(&mut hasher);
+        format!("{:016x}", hasher.finish())
+    }
+
+    fn passed(&self, key: &str) -> bool {
+        self.dir.join(key).is_file()
+    }
+
+    fn record_pass(&self, key: &str) {
+        // Failing to record the test only means it is run again next time.
+        if let Err(e) = File::create(self.dir.join(key)) {
+            info!("could not record doctest {} as passed: {}", key, e);
+        }
+    }
+}
+
+/// Whether `file_name` is that of a library named `prefix`, such as
+/// `libfoo.rlib` or `libfoo-0123abcd.so` for `libfoo`, but not `libfoobar.rlib`.
+fn is_library_of(file_name: &str, prefix: &str) -> bool {
+    file_name.starts_with(prefix) &&
+        (file_name[prefix.len()..].starts_with('-') || file_name[prefix.len()..].starts_with('.'))
+}
+
 pub struct Collector {
     pub tests: Vec<testing::TestDescAndFn>,
 
@@ -483,13 +732,27 @@ pub struct Collector {
     filename: Option<PathBuf>,
     linker: Option<PathBuf>,
     edition: Edition,
+    merged: Arc<Mutex<MergedTests>>,
+    cache: Option<Arc<TestCache>>,
+    /// The names of the tests that are not run because they passed before.
+    cached: Vec<String>,
+}
+
+/// The tests that are compiled together into a single binary, which is built
+/// by whichever of them runs first.
+#[derive(Default)]
+struct MergedTests {
+    tests: Vec<MergedTest>,
+    /// Set once the binary has been built, to `None` if it failed to.
+    compiled: Option<Option<(TempDir, PathBuf)>>,
 }
 
 impl Collector {
     pub fn new(cratename: String, cfgs: Vec<String>, libs: SearchPaths, externs: Externs,
                use_headers: bool, opts: TestOptions, maybe_sysroot: Option<PathBuf>,
                codemap: Option<Lrc<CodeMap>>, filename: Option<PathBuf>,
-               linker: Option<PathBuf>, edition: Edition) -> Collector {
+               linker: Option<PathBuf>, edition: Edition,
+               cache: Option<TestCache>) -> Collector {
         Collector {
             tests: Vec::new(),
             names: Vec::new(),
@@ -505,9 +768,46 @@ impl Collector {
             filename,
             linker,
             edition,
+            merged: Default::default(),
+            cache: cache.map(Arc::new),
+            cached: Vec::new(),
         }
     }
 
+    /// Tells how many tests passed in an earlier run and were not run again.
+    /// This goes to stderr, as tools parse the output of the test runner.
+    pub fn report_cached(&self) {
+        if !self.cached.is_empty() {
+            eprintln!("{} doctests are unchanged since they last passed and were not run again",
+                      self.cached.len());
+        }
+    }
+
+    /// Leaves the tests that the test runner filters out with `test_args` out
+    /// of the binary of the merged tests, so that they neither slow down nor
+    /// break its build, and out of the count of cached tests.
+    pub fn select_tests(&mut self, test_args: &[String]) {
+        if test_args.iter().any(|arg| arg == "-h" || arg == "--help") {
+            return;
+        }
+        let test_opts = match testing::parse_opts(test_args) {
+            Some(Ok(test_opts)) => test_opts,
+            // reported by the test runner
+            _ => return,
+        };
+        let tests = self.tests.iter().map(|test| testing::TestDescAndFn {
+            desc: test.desc.clone(),
+            testfn: testing::DynTestFn(box || {}),
+        }).collect();
+        let selected = testing::filter_tests(&test_opts, tests).into_iter()
+            .map(|test| test.desc.name.to_string())
+            .collect::<FxHashSet<_>>();
+        for test in &mut self.merged.lock().unwrap().tests {
+            test.selected = selected.contains(&test.name);
+        }
+        self.cached.retain(|name| selected.contains(name));
+    }
+
     fn generate_name(&self, line: usize, filename: &FileName) -> String {
         format!("{} - {} (line {})", filename, self.names.join("::"), line)
     }
@@ -525,6 +825,44 @@ impl Collector {
         let maybe_sysroot = self.maybe_sysroot.clone();
         let linker = self.linker.clone();
         let edition = self.edition;
+        let cache = match self.cache {
+            Some(ref cache) if !should_ignore => {
+                let key = cache.key(&(&test, &cratename, should_panic, no_run, as_test_harness,
+                                      compile_fail, &error_codes, &opts, &cfgs, edition,
+                                      &linker, &maybe_sysroot));
+                if cache.passed(&key) {
+                    debug!("Skipping test {}, which passed before", name);
+                    self.cached.push(name.clone());
+                    self.tests.push(testing::TestDescAndFn {
+                        desc: testing::TestDesc {
+                            name: testing::DynTestName(name),
+                            ignore: false,
+                            should_panic: testing::ShouldPanic::No,
+                            allow_fail,
+                        },
+                        testfn: testing::DynTestFn(box || {}),
+                    });
+                    return;
+                }
+                Some((cache.clone(), key))
+            }
+            _ => None,
+        };
+        let merged = self.merged.clone();
+        let merged_index = if can_merge(&test, should_ignore, no_run, as_test_harness,
+                                        compile_fail, &error_codes) {
+            let mut merged = self.merged.lock().unwrap();
+            merged.tests.push(MergedTest {
+                name: name.clone(),
+                code: test.clone(),
+                filename: filename.clone(),
+                line: line + 1,
+                selected: true,
+            });
+            Some(merged.tests.len() - 1)
+        } else {
+            None
+        };
         debug!("Creating test {}: {}", name, test);
         self.tests.push(testing::TestDescAndFn {
             desc: testing::TestDesc {
@@ -541,6 +879,30 @@ impl Collector {
                     rustc_driver::in_rustc_thread(move || with_globals(move || {
                         io::set_panic(panic);
                         io::set_print(print);
+                        if let Some(index) = merged_index {
+                            let compiled = {
+                                let mut merged = merged.lock().unwrap();
+                                if merged.compiled.is_none() {
+                                    let compiled = compile_merged_test(&merged.tests,
+                                                                       &cratename,
+                                                                       cfgs.clone(),
+                                                                       libs.clone(),
+                                                                       externs.clone(),
+                                                                       &opts,
+                                                                       maybe_sysroot.clone(),
+                                                                       linker.clone(),
+                                                                       edition);
+                                    merged.compiled = Some(compiled);
+                                }
+                                merged.compiled.as_ref().unwrap().as_ref().map(|c| {
+                                    (c.0.path().join("rust_out"), c.1.clone())
+                                })
+                            };
+                            if let Some((exe, libdir)) = compiled {
+                                return run_executable(&exe, Some(&index.to_string()), &libdir,
+                                                      should_panic);
+                            }
+                        }
                         run_test(&test,
                                  &cratename,
                                  &filename,
@@ -559,7 +921,11 @@ impl Collector {
                                  edition)
                     }))
                 } {
-                    Ok(()) => (),
+                    Ok(()) => {
+                        if let Some((cache, key)) = cache {
+                            cache.record_pass(&key);
+                        }
+                    }
                     Err(err) => panic::resume_unwind(err),
                 }
             }),
@@ -736,7 +1102,9 @@ impl<'a, 'hir> intravisit::Visitor<'hir> for HirCollector<'a, 'hir> {
 
 #[cfg(test)]
 mod tests {
-    use super::{TestOptions, make_test};
+    use super::{TestOptions, can_merge, is_library_of, make_merged_test, make_merged_test_module,
+                make_test};
+    use std::path::PathBuf;
 
     #[test]
     fn make_test_basic() {
@@ -980,4 +1348,65 @@ assert_eq!(2+2, 4);
         let output = make_test(input, None, false, &opts);
         assert_eq!(output, (expected.clone(), 1));
     }
+
+    #[test]
+    fn can_merge_plain_tests_only() {
+        let merge = |test: &str| can_merge(test, false, false, false, false, &[]);
+        assert!(merge("assert_eq!(2+2, 4);"));
+        assert!(merge("//Ceci n'est pas une `fn main`\nassert_eq!(2+2, 4);"));
+        assert!(!merge("#![feature(sure)]\nassert_eq!(2+2, 4);"));
+        assert!(!merge("extern crate asdf;\nassert_eq!(2+2, 4);"));
+        assert!(!merge("fn main() {\nassert_eq!(2+2, 4);\n}"));
+        assert!(!can_merge("assert_eq!(2+2, 4);", false, true, false, false, &[]));
+        assert!(!can_merge("assert_eq!(2+2, 4);", false, false, false, true, &[]));
+        assert!(!can_merge("assert_eq!(2+2, 4);", false, false, false, false,
+                           &["E0308".to_string()]));
+    }
+
+    #[test]
+    fn make_merged_test_basic() {
+        let opts = TestOptions::default();
+        let tests = vec![(0, "assert_eq!(2+2, 4);", PathBuf::from("/tmp/0/lib.rs")),
+                         (2, "use asdf::qwop;\nqwop();", PathBuf::from("/tmp/2/lib.rs"))];
+        let expected =
+"#![allow(unused)]
+extern crate asdf;
+#[path = \"/tmp/0/lib.rs\"]
+mod __doctest_0;
+#[path = \"/tmp/2/lib.rs\"]
+mod __doctest_2;
+fn main() {
+    match ::std::env::args().nth(1).as_ref().map(|s| &s[..]) {
+        Some(\"0\") => __doctest_0::main(),
+        Some(\"2\") => __doctest_2::main(),
+        _ => panic!(\"unknown doctest\"),
+    }
+}
+".to_string();
+        assert_eq!(make_merged_test(&tests, "asdf", &opts), expected);
+    }
+
+    #[test]
+    fn make_merged_test_module_keeps_lines() {
+        let expected =
+"
+
+
+#[allow(unused_imports)] use super::*; pub fn main() { use asdf::qwop;
+qwop();
+}
+".to_string();
+        assert_eq!(make_merged_test_module("use asdf::qwop;\nqwop();", 4), expected);
+        assert_eq!(make_merged_test_module("\nuse asdf::qwop;\nqwop();", 3), expected);
+    }
+
+    #[test]
+    fn cache_hashes_only_libraries_of_the_crate() {
+        assert!(is_library_of("libfoo.rlib", "libfoo"));
+        assert!(is_library_of("libfoo-0123abcd.rlib", "libfoo"));
+        assert!(is_library_of("libfoo.so", "libfoo"));
+        assert!(!is_library_of("libfoobar.rlib", "libfoo"));
+        assert!(!is_library_of("libfoo_bar-0123abcd.rlib", "libfoo"));
+        assert!(!is_library_of("libfo.rlib", "libfoo"));
+    }
 }
@@ -109,12 +109,18 @@ impl FileLoader for RealFileLoader {
 pub struct StableFilemapId(u128);
 
 impl StableFilemapId {
-    pub fn new(filemap: &FileMap) -> StableFilemapId {
+    pub fn new(filemap: &FileMap, path_mapping: &FilePathMapping) -> StableFilemapId {
         let mut hasher = StableHasher::new();
 
+        // The name of a file under the root given with
+        // `-Z incremental-path-root` is already relative to it, and so is made
+        // the unmapped path, so that the ID does not change when the sources
+        // are moved along with the root.
         filemap.name.hash(&mut hasher);
         filemap.name_was_remapped.hash(&mut hasher);
-        filemap.unmapped_path.hash(&mut hasher);
+        filemap.unmapped_path.as_ref()
+                             .map(|path| path_mapping.stable_name(path))
+                             .hash(&mut hasher);
 
         StableFilemapId(hasher.finish())
     }
@@ -133,7 +139,8 @@ pub struct CodeMap {
     pub(super) files: Lock<CodeMapFiles>,
     file_loader: Box<FileLoader + Sync + Send>,
     // This is used to apply the file path remapping as specified via
-    // --remap-path-prefix to all FileMaps allocated within this CodeMap.
+    // --remap-path-prefix and -Z incremental-path-root to all FileMaps
+    // allocated within this CodeMap.
     path_mapping: FilePathMapping,
     /// In case we are in a doctest, replace all file names with the PathBuf,
     /// and add the given offsets to the line info
@@ -242,7 +249,8 @@ impl CodeMap {
         let mut files = self.files.borrow_mut();
 
         files.file_maps.push(filemap.clone());
-        files.stable_id_to_filemap.insert(StableFilemapId::new(&filemap), filemap.clone());
+        let stable_id = StableFilemapId::new(&filemap, &self.path_mapping);
+        files.stable_id_to_filemap.insert(stable_id, filemap.clone());
 
         filemap
     }
@@ -314,7 +322,8 @@ impl CodeMap {
         let mut files = self.files.borrow_mut();
 
         files.file_maps.push(filemap.clone());
-        files.stable_id_to_filemap.insert(StableFilemapId::new(&filemap), filemap.clone());
+        let stable_id = StableFilemapId::new(&filemap, &self.path_mapping);
+        files.stable_id_to_filemap.insert(stable_id, filemap.clone());
 
         filemap
     }
@@ -917,21 +926,36 @@ impl CodeMapper for CodeMap {
 #[derive(Clone)]
 pub struct FilePathMapping {
     mapping: Vec<(PathBuf, PathBuf)>,
+    // The root given with `-Z incremental-path-root`, made absolute, and the
+    // working directory that relative paths are relative to.
+    stable_root: Option<(PathBuf, PathBuf)>,
 }
 
 impl FilePathMapping {
     pub fn empty() -> FilePathMapping {
         FilePathMapping {
-            mapping: vec![]
+            mapping: vec![],
+            stable_root: None,
         }
     }
 
     pub fn new(mapping: Vec<(PathBuf, PathBuf)>) -> FilePathMapping {
         FilePathMapping {
             mapping,
+            stable_root: None,
         }
     }
 
+    /// Makes the files under `root` be named by their path relative to `root`,
+    /// as if `root` was remapped to an empty path before any other mapping,
+    /// so that neither their identity across compilation sessions nor the
+    /// paths in the output depend on where `root` is. `root` and the paths of
+    /// the files are relative to `working_dir` if they are relative.
+    pub fn with_stable_root(mut self, root: PathBuf, working_dir: PathBuf) -> FilePathMapping {
+        self.stable_root = Some((working_dir.join(root), working_dir));
+        self
+    }
+
     /// Applies any path prefix substitution as defined by the mapping.
     /// The return value is the remapped path and a boolean indicating whether
     /// the path was affected by the mapping.
@@ -945,8 +969,32 @@ impl FilePathMapping {
             }
         }
 
+        if let Some((ref root, ref working_dir)) = self.stable_root {
+            if let Ok(rest) = working_dir.join(&path).strip_prefix(root) {
+                return (rest.to_path_buf(), true);
+            }
+        }
+
         (path, false)
     }
+
+    /// Returns the name that identifies the file `name`, as it was before
+    /// `map_prefix`, across compilation sessions, in the hashes of incremental
+    /// compilation. This is the path relative to the root given to
+    /// `with_stable_root` for the files under it, so that the incremental
+    /// compilation cache can be used when the root is somewhere else, and
+    /// `name` itself for all other files. Symbolic links are not resolved.
+    pub fn stable_name(&self, name: &FileName) -> FileName {
+        match (name, &self.stable_root) {
+            (&FileName::Real(ref path), &Some((ref root, ref working_dir))) => {
+                match working_dir.join(path).strip_prefix(root) {
+                    Ok(relative) => FileName::Real(relative.to_path_buf()),
+                    Err(_) => name.clone(),
+                }
+            }
+            _ => name.clone(),
+        }
+    }
 }
 
 // _____________________________________________________________________________
@@ -1174,6 +1222,48 @@ mod tests {
         assert!(cm.merge_spans(span1, span2).is_none());
     }
 
+    /// Test that files under the stable root are identified the same way
+    /// wherever the root is
+    #[test]
+    fn stable_root() {
+        let first = FilePathMapping::empty()
+            .with_stable_root(PathBuf::from("/first"), PathBuf::from("/work"));
+        let second = FilePathMapping::empty()
+            .with_stable_root(PathBuf::from("second"), PathBuf::from("/work"));
+
+        assert_eq!(first.stable_name(&PathBuf::from("/first/src/lib.rs").into()),
+                   FileName::Real(PathBuf::from("src/lib.rs")));
+        assert_eq!(second.stable_name(&PathBuf::from("second/src/lib.rs").into()),
+                   FileName::Real(PathBuf::from("src/lib.rs")));
+        assert_eq!(first.stable_name(&PathBuf::from("/other/lib.rs").into()),
+                   FileName::Real(PathBuf::from("/other/lib.rs")));
+        assert_eq!(first.stable_name(&FileName::Anon), FileName::Anon);
+
+        // The root is remapped to nothing, unless `--remap-path-prefix` says
+        // otherwise.
+        let remapped = FilePathMapping::new(vec![(PathBuf::from("/first/src"),
+                                                  PathBuf::from("/src"))])
+            .with_stable_root(PathBuf::from("/first"), PathBuf::from("/work"));
+        assert_eq!(remapped.map_prefix(PathBuf::from("/first/build.rs")),
+                   (PathBuf::from("build.rs"), true));
+        assert_eq!(remapped.map_prefix(PathBuf::from("/first/src/lib.rs")),
+                   (PathBuf::from("/src/lib.rs"), true));
+        assert_eq!(remapped.map_prefix(PathBuf::from("/other/lib.rs")),
+                   (PathBuf::from("/other/lib.rs"), false));
+
+        let cm1 = CodeMap::new(first);
+        let cm2 = CodeMap::new(second);
+        let fm1 = cm1.new_filemap(PathBuf::from("/first/src/lib.rs").into(), "fn main() {}".into());
+        let fm2 = cm2.new_filemap(PathBuf::from("/work/second/src/lib.rs").into(),
+                                  "fn main() {}".into());
+
+        assert_eq!(fm1.name, FileName::Real(PathBuf::from("src/lib.rs")));
+        assert!(fm1.name_was_remapped);
+        assert_eq!(fm1.name_hash, fm2.name_hash);
+        assert_eq!(StableFilemapId::new(&fm1, cm1.path_mapping()),
+                   StableFilemapId::new(&fm2, cm2.path_mapping()));
+    }
+
     /// Returns the span corresponding to the `n`th occurrence of
     /// `substring` in `source_text`.
     trait CodeMapExtension {
@@ -446,6 +446,12 @@ declare_features! (
 
     // Allows macro invocations in `extern {}` blocks
     (active, macros_in_extern, "1.27.0", Some(49476), None),
+
+    // Allows `#[test_setup]`, `#[test_teardown]` and tests taking fixtures
+    (active, test_fixtures, "1.27.0", None, None),
+
+    // Allows `#[test_case(..)]` to run a test function with several argument sets
+    (active, test_cases, "1.27.0", None, None),
 );
 
 declare_features! (
@@ -1002,6 +1008,21 @@ pub const BUILTIN_ATTRIBUTES: &'static [(&'static str, AttributeType, AttributeG
                                  "allow_fail attribute is currently unstable",
                                  cfg_fn!(allow_fail))),
 
+    ("test_setup", Normal, Gated(Stability::Unstable,
+                                 "test_fixtures",
+                                 "test_setup attribute is currently unstable",
+                                 cfg_fn!(test_fixtures))),
+
+    ("test_teardown", Normal, Gated(Stability::Unstable,
+                                    "test_fixtures",
+                                    "test_teardown attribute is currently unstable",
+                                    cfg_fn!(test_fixtures))),
+
+    ("test_case", Normal, Gated(Stability::Unstable,
+                                "test_cases",
+                                "test_case attribute is currently unstable",
+                                cfg_fn!(test_cases))),
+
     ("rustc_std_internal_symbol", Whitelisted, Gated(Stability::Unstable,
                                      "rustc_attrs",
                                      "this is an internal attribute that will \
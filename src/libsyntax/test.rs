@@ -46,13 +46,34 @@ enum ShouldPanic {
     Yes(Option<Symbol>),
 }
 
+/// The `#[test_setup]` and `#[test_teardown]` functions of a module, which
+/// run around every test in that module and its submodules.
+#[derive(Clone, Default)]
+struct TestHooks {
+    setup: Option<Vec<Ident>>,
+    teardown: Option<Vec<Ident>>,
+}
+
+/// One argument set of a `#[test_case(..)]` function, which the harness
+/// calls through a generated wrapper function.
+struct TestCase {
+    wrapper: Ident,
+    // the arguments as written, used in the test name
+    args: String,
+}
+
 struct Test {
     span: Span,
     path: Vec<Ident> ,
+    case: Option<TestCase>,
     bench: bool,
     ignore: bool,
     should_panic: ShouldPanic,
     allow_fail: bool,
+    // number of arguments, each of which is a `test::TestFixture`
+    fixtures: usize,
+    // hooks of the enclosing modules, outermost first
+    hooks: Vec<TestHooks>,
 }
 
 struct TestCtxt<'a> {
@@ -60,6 +81,8 @@ struct TestCtxt<'a> {
     path: Vec<Ident>,
     ext_cx: ExtCtxt<'a>,
     testfns: Vec<Test>,
+    // hooks of the modules currently being folded, outermost first
+    hooks: Vec<TestHooks>,
     reexport_test_harness_main: Option<Symbol>,
     is_libtest: bool,
     ctxt: SyntaxContext,
@@ -99,10 +122,15 @@ struct TestHarnessGenerator<'a> {
 
     // submodule name, gensym'd identifier for re-exports
     tested_submods: Vec<(Ident, Ident)>,
+
+    // wrapper functions of the `#[test_case]`s in the current module
+    test_cases: Vec<P<ast::Item>>,
 }
 
 impl<'a> fold::Folder for TestHarnessGenerator<'a> {
     fn fold_crate(&mut self, c: ast::Crate) -> ast::Crate {
+        // The hooks of the crate root are collected by `fold_item`, which the
+        // root module is folded with.
         let mut folded = fold::noop_fold_crate(c, self);
 
         // Add a special __test module to the crate that will contain code
@@ -122,7 +150,27 @@ impl<'a> fold::Folder for TestHarnessGenerator<'a> {
         }
         debug!("current path: {}", path_name_i(&self.cx.path));
 
-        if is_test_fn(&self.cx, &i) || is_bench_fn(&self.cx, &i) {
+        if is_test_case_fn(&self.cx, &i) {
+            debug!("this is a test case function");
+            let args = test_case_args(&self.cx, &i);
+            for (idx, args) in args.into_iter().enumerate() {
+                let (wrapper, args) = mk_test_case_fn(&self.cx, &i, idx, args);
+                let test = Test {
+                    span: i.span,
+                    path: self.cx.path.clone(),
+                    case: Some(TestCase { wrapper: wrapper.ident, args }),
+                    bench: false,
+                    ignore: is_ignored(&i),
+                    should_panic: should_panic(&i, &self.cx),
+                    allow_fail: is_allowed_fail(&i),
+                    fixtures: 0,
+                    hooks: self.cx.hooks.clone(),
+                };
+                self.cx.testfns.push(test);
+                self.tests.push(wrapper.ident);
+                self.test_cases.push(wrapper);
+            }
+        } else if is_test_fn(&self.cx, &i) || is_bench_fn(&self.cx, &i) {
             match i.node {
                 ast::ItemKind::Fn(_, ast::Unsafety::Unsafe, _, _, _, _) => {
                     let diag = self.cx.span_diagnostic;
@@ -133,15 +181,22 @@ impl<'a> fold::Folder for TestHarnessGenerator<'a> {
                     let test = Test {
                         span: i.span,
                         path: self.cx.path.clone(),
+                        case: None,
                         bench: is_bench_fn(&self.cx, &i),
                         ignore: is_ignored(&i),
                         should_panic: should_panic(&i, &self.cx),
                         allow_fail: is_allowed_fail(&i),
+                        fixtures: fixture_count(&i),
+                        hooks: self.cx.hooks.clone(),
                     };
                     self.cx.testfns.push(test);
                     self.tests.push(i.ident);
                 }
             }
+        } else if is_test_hook(&self.cx, &i) {
+            // Hooks are called by the harness and need to be re-exported
+            // just like tests.
+            self.tests.push(i.ident);
         }
 
         let mut item = i.into_inner();
@@ -150,9 +205,19 @@ impl<'a> fold::Folder for TestHarnessGenerator<'a> {
         if let ast::ItemKind::Mod(module) = item.node {
             let tests = mem::replace(&mut self.tests, Vec::new());
             let tested_submods = mem::replace(&mut self.tested_submods, Vec::new());
+            let test_cases = mem::replace(&mut self.test_cases, Vec::new());
+            let hooks = module_hooks(&self.cx, &module);
+            self.cx.hooks.push(hooks);
             let mut mod_folded = fold::noop_fold_mod(module, self);
+            self.cx.hooks.pop();
             let tests = mem::replace(&mut self.tests, tests);
             let tested_submods = mem::replace(&mut self.tested_submods, tested_submods);
+            let test_cases = mem::replace(&mut self.test_cases, test_cases);
+
+            if !test_cases.is_empty() {
+                let wrappers = expand_test_case_fns(&mut self.cx, item.id, test_cases);
+                mod_folded.items.extend(wrappers);
+            }
 
             if !tests.is_empty() || !tested_submods.is_empty() {
                 let (it, sym) = mk_reexport_mod(&mut self.cx, item.id, tests, tested_submods);
@@ -285,6 +350,7 @@ fn generate_test_harness(sess: &ParseSess,
         ext_cx: ExtCtxt::new(sess, econfig, resolver),
         path: Vec::new(),
         testfns: Vec::new(),
+        hooks: Vec::new(),
         reexport_test_harness_main,
         // NB: doesn't consider the value of `--crate-name` passed on the command line.
         is_libtest: attr::find_crate_name(&krate.attrs).map(|s| s == "test").unwrap_or(false),
@@ -307,6 +373,7 @@ fn generate_test_harness(sess: &ParseSess,
         cx,
         tests: Vec::new(),
         tested_submods: Vec::new(),
+        test_cases: Vec::new(),
     }.fold_crate(krate)
 }
 
@@ -321,6 +388,8 @@ fn ignored_span(cx: &TestCtxt, sp: Span) -> Span {
 enum HasTestSignature {
     Yes,
     No,
+    /// An argument is not a fixture; the error has been reported already.
+    BadFixture,
     NotEvenAFunction,
 }
 
@@ -344,7 +413,24 @@ fn is_test_fn(cx: &TestCtxt, i: &ast::Item) -> bool {
                     no_output && !generics.is_parameterized()
                 };
 
-                if decl.inputs.is_empty() && output_matches {
+                // Arguments are fixtures, which libtest sets up for the test.
+                if cx.features.test_fixtures && !decl.inputs.is_empty() {
+                    let mut fixtures_match = true;
+                    for arg in &decl.inputs {
+                        if !is_fixture_type(&arg.ty) {
+                            cx.span_diagnostic.span_err(arg.ty.span,
+                                "test fixtures must be taken as `&mut T`, \
+                                 where `T` implements `test::TestFixture`");
+                            fixtures_match = false;
+                        }
+                    }
+                    if !fixtures_match {
+                        return BadFixture;
+                    }
+                }
+                let inputs_match = decl.inputs.is_empty() || cx.features.test_fixtures;
+
+                if inputs_match && output_matches {
                     Yes
                 } else {
                     No
@@ -366,6 +452,7 @@ fn is_test_fn(cx: &TestCtxt, i: &ast::Item) -> bool {
                 }
                 false
             },
+            BadFixture => false,
             NotEvenAFunction => {
                 diag.span_err(i.span, "only functions may be used as tests");
                 false
@@ -428,6 +515,162 @@ fn is_bench_fn(cx: &TestCtxt, i: &ast::Item) -> bool {
     has_bench_attr && has_bench_signature
 }
 
+/// Whether `i` is a `#[test_setup]` or `#[test_teardown]` function.
+fn is_test_hook(cx: &TestCtxt, i: &ast::Item) -> bool {
+    let attr = match i.attrs.iter().find(|a| is_hook_attr(a)) {
+        Some(attr) => attr,
+        None => return false,
+    };
+
+    if !has_hook_signature(i) {
+        let diag = cx.span_diagnostic;
+        diag.span_err(i.span, &format!("functions used as `#[{}]` must have signature `fn()`",
+                                       attr.name()));
+        return false;
+    }
+
+    true
+}
+
+fn is_hook_attr(attr: &ast::Attribute) -> bool {
+    attr.check_name("test_setup") || attr.check_name("test_teardown")
+}
+
+fn has_hook_signature(i: &ast::Item) -> bool {
+    match i.node {
+        ast::ItemKind::Fn(ref decl, ast::Unsafety::Normal, _, _, ref generics, _) => {
+            let no_output = match decl.output {
+                ast::FunctionRetTy::Default(..) => true,
+                ast::FunctionRetTy::Ty(ref t) if t.node == ast::TyKind::Tup(vec![]) => true,
+                _ => false
+            };
+
+            decl.inputs.is_empty() && no_output && !generics.is_parameterized()
+        }
+        _ => false,
+    }
+}
+
+/// Collects the hooks defined directly in `module`. Invalid hooks are
+/// reported when the items themselves are folded.
+fn module_hooks(cx: &TestCtxt, module: &ast::Mod) -> TestHooks {
+    let mut hooks = TestHooks::default();
+    for item in &module.items {
+        if !has_hook_signature(item) {
+            continue;
+        }
+
+        let mut path = cx.path.clone();
+        path.push(item.ident);
+
+        if attr::contains_name(&item.attrs, "test_setup") {
+            if hooks.setup.is_some() {
+                cx.span_diagnostic.span_err(item.span,
+                    "a module can only have one `#[test_setup]` function");
+            }
+            hooks.setup = Some(path.clone());
+        }
+        if attr::contains_name(&item.attrs, "test_teardown") {
+            if hooks.teardown.is_some() {
+                cx.span_diagnostic.span_err(item.span,
+                    "a module can only have one `#[test_teardown]` function");
+            }
+            hooks.teardown = Some(path);
+        }
+    }
+    hooks
+}
+
+/// Whether `ty` is `&mut T`, the only way a test can take a fixture.
+fn is_fixture_type(ty: &ast::Ty) -> bool {
+    match ty.node {
+        ast::TyKind::Rptr(_, ast::MutTy { mutbl: ast::Mutability::Mutable, .. }) => true,
+        _ => false,
+    }
+}
+
+fn fixture_count(i: &ast::Item) -> usize {
+    match i.node {
+        ast::ItemKind::Fn(ref decl, ..) => decl.inputs.len(),
+        _ => 0,
+    }
+}
+
+/// Whether `i` is a function with `#[test_case(..)]` attributes.
+fn is_test_case_fn(cx: &TestCtxt, i: &ast::Item) -> bool {
+    if !attr::contains_name(&i.attrs, "test_case") {
+        return false;
+    }
+
+    match i.node {
+        ast::ItemKind::Fn(_, ast::Unsafety::Normal, _, _, ref generics, _)
+            if !generics.is_parameterized() => true,
+        _ => {
+            let diag = cx.span_diagnostic;
+            diag.span_err(i.span, "only non-generic, safe functions can have `#[test_case]` \
+                                   attributes");
+            false
+        }
+    }
+}
+
+/// The argument lists of every `#[test_case(..)]` attribute of `i`.
+fn test_case_args(cx: &TestCtxt, i: &ast::Item) -> Vec<Vec<P<ast::Expr>>> {
+    i.attrs.iter().filter(|attr| attr.check_name("test_case")).filter_map(|attr| {
+        match attr.parse_list(cx.ext_cx.parse_sess, |parser| parser.parse_expr()) {
+            Ok(args) => Some(args),
+            Err(mut e) => {
+                e.emit();
+                None
+            }
+        }
+    }).collect()
+}
+
+/// Creates `fn <gensym>() -> <output> { real_function(args..) }` for one
+/// argument set of the `#[test_case]` function `i`, and returns it along with
+/// the printed arguments.
+fn mk_test_case_fn(cx: &TestCtxt,
+                   i: &ast::Item,
+                   idx: usize,
+                   args: Vec<P<ast::Expr>>) -> (P<ast::Item>, String) {
+    let ecx = &cx.ext_cx;
+    let span = i.span;
+    // the pretty printer breaks long arguments over several lines, but the
+    // test name is printed on one
+    let printed = args.iter().map(|arg| {
+        pprust::expr_to_string(arg).lines().map(|line| line.trim()).collect::<Vec<_>>().join(" ")
+    }).collect::<Vec<_>>();
+
+    let output = match i.node {
+        ast::ItemKind::Fn(ref decl, ..) => match decl.output {
+            ast::FunctionRetTy::Ty(ref ty) => ty.clone(),
+            ast::FunctionRetTy::Default(..) => ecx.ty(span, ast::TyKind::Tup(vec![])),
+        },
+        _ => unreachable!(),
+    };
+    let name = Ident::with_empty_ctxt(Symbol::gensym(&format!("{}_case{}", i.ident, idx)));
+    let body = ecx.block_expr(ecx.expr_call_ident(span, i.ident, args));
+    let wrapper = ecx.item_fn(span, name, vec![], output, body).map(|mut item| {
+        // re-exported by the harness, just like the tests themselves
+        item.vis = dummy_spanned(ast::VisibilityKind::Public);
+        item
+    });
+
+    (wrapper, printed.join(", "))
+}
+
+/// Assigns node ids to the `#[test_case]` wrappers of the module `parent`,
+/// expanding any macros in their arguments along the way.
+fn expand_test_case_fns(cx: &mut TestCtxt,
+                        parent: ast::NodeId,
+                        wrappers: Vec<P<ast::Item>>) -> Vec<P<ast::Item>> {
+    let parent = if parent == ast::DUMMY_NODE_ID { ast::CRATE_NODE_ID } else { parent };
+    cx.ext_cx.current_expansion.mark = cx.ext_cx.resolver.get_module_scope(parent);
+    let mut expander = cx.ext_cx.monotonic_expander();
+    wrappers.into_iter().flat_map(|wrapper| expander.fold_item(wrapper)).collect()
+}
+
 fn is_ignored(i: &ast::Item) -> bool {
     attr::contains_name(&i.attrs, "ignore")
 }
@@ -700,8 +943,12 @@ fn mk_test_desc_and_fn_rec(cx: &TestCtxt, test: &Test) -> P<ast::Expr> {
     // creates $name: $expr
     let field = |name, expr| ecx.field_imm(span, ecx.ident_of(name), expr);
 
-    // path to the #[test] function: "foo::bar::baz"
-    let path_string = path_name_i(&test.path[..]);
+    // path to the #[test] function: "foo::bar::baz", followed by the
+    // arguments for test cases: "foo::bar::baz(1, 2)"
+    let mut path_string = path_name_i(&test.path[..]);
+    if let Some(ref case) = test.case {
+        path_string.push_str(&format!("({})", case.args));
+    }
 
     debug!("encoding {}", path_string);
 
@@ -740,18 +987,11 @@ fn mk_test_desc_and_fn_rec(cx: &TestCtxt, test: &Test) -> P<ast::Expr> {
              field("should_panic", fail_expr),
              field("allow_fail", allow_fail_expr)]);
 
-    let mut visible_path = vec![];
-    if cx.features.extern_absolute_paths {
-        visible_path.push(keywords::Crate.ident());
+    let mut fn_path = test.path.clone();
+    if let Some(ref case) = test.case {
+        *fn_path.last_mut().unwrap() = case.wrapper;
     }
-    match cx.toplevel_reexport {
-        Some(id) => visible_path.push(id),
-        None => {
-            let diag = cx.span_diagnostic;
-            diag.bug("expected to find top-level re-export name, but found None");
-        }
-    };
-    visible_path.extend_from_slice(&test.path[..]);
+    let visible_path = visible_path(cx, &fn_path);
 
     // Rather than directly give the test function to the test
     // harness, we create a wrapper like one of the following:
@@ -759,6 +999,13 @@ fn mk_test_desc_and_fn_rec(cx: &TestCtxt, test: &Test) -> P<ast::Expr> {
     //     || test::assert_test_result(real_function()) // for test
     //     |b| test::assert_test_result(real_function(b)) // for bench
     //
+    // tests taking fixtures, or in modules with `#[test_setup]` or
+    // `#[test_teardown]` functions, additionally get wrapped like
+    //
+    //     || test::assert_test_result(test::with_setup(setup, || {
+    //         test::with_fixture(|f0| real_function(f0))
+    //     }))
+    //
     // this will coerce into a fn pointer that is specialized to the
     // actual return type of `real_function` (Typically `()`, but not always).
     let fn_expr = {
@@ -788,6 +1035,44 @@ fn mk_test_desc_and_fn_rec(cx: &TestCtxt, test: &Test) -> P<ast::Expr> {
                 ),
             )
         } else {
+            // construct `real_function(f0, ..)`, where `f0, ..` are the fixtures
+            let fixture_idents: Vec<_> = (0..test.fixtures).map(|i| {
+                Ident::with_empty_ctxt(Symbol::gensym(&format!("f{}", i)))
+            }).collect();
+            let mut test_expr = ecx.expr_call(
+                span,
+                real_function_expr,
+                fixture_idents.iter().map(|&f| ecx.expr_ident(span, f)).collect(),
+            );
+            // construct `test::with_fixture(|f0| ..)` for every fixture
+            for &f in fixture_idents.iter().rev() {
+                test_expr = ecx.expr_call(
+                    span,
+                    ecx.expr_path(test_path("with_fixture")),
+                    vec![ecx.lambda(span, vec![f], test_expr)],
+                );
+            }
+            // construct `test::with_teardown(teardown, || ..)` and
+            // `test::with_setup(setup, || ..)` for every enclosing module,
+            // so that the outermost setup runs first and its teardown last
+            for hooks in test.hooks.iter().rev() {
+                if let Some(ref teardown) = hooks.teardown {
+                    let teardown = ecx.path_global(span, visible_path(cx, teardown));
+                    test_expr = ecx.expr_call(
+                        span,
+                        ecx.expr_path(test_path("with_teardown")),
+                        vec![ecx.expr_path(teardown), ecx.lambda(span, vec![], test_expr)],
+                    );
+                }
+                if let Some(ref setup) = hooks.setup {
+                    let setup = ecx.path_global(span, visible_path(cx, setup));
+                    test_expr = ecx.expr_call(
+                        span,
+                        ecx.expr_path(test_path("with_setup")),
+                        vec![ecx.expr_path(setup), ecx.lambda(span, vec![], test_expr)],
+                    );
+                }
+            }
             // construct `|| {..}`
             ecx.lambda(
                 span,
@@ -796,14 +1081,7 @@ fn mk_test_desc_and_fn_rec(cx: &TestCtxt, test: &Test) -> P<ast::Expr> {
                 ecx.expr_call(
                     span,
                     ecx.expr_path(assert_test_result),
-                    vec![
-                        // construct `real_function()`
-                        ecx.expr_call(
-                            span,
-                            real_function_expr,
-                            vec![],
-                        )
-                    ],
+                    vec![test_expr],
                 ),
             )
         }
@@ -820,3 +1098,21 @@ fn mk_test_desc_and_fn_rec(cx: &TestCtxt, test: &Test) -> P<ast::Expr> {
                     vec![field("desc", desc_expr),
                          field("testfn", testfn_expr)])
 }
+
+/// The path through which the harness reaches the item at `path`, using the
+/// re-export modules generated for every module containing tests.
+fn visible_path(cx: &TestCtxt, path: &[Ident]) -> Vec<Ident> {
+    let mut visible_path = vec![];
+    if cx.features.extern_absolute_paths {
+        visible_path.push(keywords::Crate.ident());
+    }
+    match cx.toplevel_reexport {
+        Some(id) => visible_path.push(id),
+        None => {
+            let diag = cx.span_diagnostic;
+            diag.bug("expected to find top-level re-export name, but found None");
+        }
+    };
+    visible_path.extend_from_slice(path);
+    visible_path
+}
@@ -923,11 +923,7 @@ impl FileMap {
             hasher.write(src.as_bytes());
             hasher.finish()
         };
-        let name_hash = {
-            let mut hasher: StableHasher<u128> = StableHasher::new();
-            name.hash(&mut hasher);
-            hasher.finish()
-        };
+        let name_hash = FileMap::hash_name(&name);
         let end_pos = start_pos.to_usize() + src.len();
 
         FileMap {
@@ -947,6 +943,13 @@ impl FileMap {
         }
     }
 
+    /// Computes the `name_hash` of a file named `name`.
+    pub fn hash_name(name: &FileName) -> u128 {
+        let mut hasher: StableHasher<u128> = StableHasher::new();
+        name.hash(&mut hasher);
+        hasher.finish()
+    }
+
     /// EFFECT: register a start-of-line offset in the
     /// table of line-beginnings.
     /// UNCHECKED INVARIANT: these offsets must be added in the right
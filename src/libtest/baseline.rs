@@ -0,0 +1,216 @@
+// Copyright 2018 The Rust Project Developers. See the COPYRIGHT
+// file at the top-level directory of this distribution and at
+// http://rust-lang.org/COPYRIGHT.
+//
+// Licensed under the Apache License, Version 2.0 <LICENSE-APACHE or
+// http://www.apache.org/licenses/LICENSE-2.0> or the MIT license
+// <LICENSE-MIT or http://opensource.org/licenses/MIT>, at your
+// option. This file may not be copied, modified, or distributed
+// except according to those terms.
+
+//! Benchmark baselines: the samples of a benchmark run saved to disk under
+//! a name with `--save-baseline`, so that later runs can be compared against
+//! them with `--baseline`.
+//!
+//! Baselines are stored as one text file per test binary, in a directory
+//! named after the baseline, inside `$RUST_BENCH_BASELINE_DIR` or, by
+//! default, inside a `bench-baselines` directory next to the test binary.
+//! Each line holds the name of a benchmark, a tab, and its samples in
+//! nanoseconds per iteration separated by spaces.
+
+use std::collections::BTreeMap;
+use std::env;
+use std::fmt;
+use std::fs::{self, File};
+use std::io::prelude::*;
+use std::io::{self, BufReader, BufWriter};
+use std::path::PathBuf;
+
+use stats::{self, Stats};
+use BenchSamples;
+
+/// Changes with a p-value below this are considered significant.
+const SIGNIFICANCE_LEVEL: f64 = 0.05;
+
+/// The result of comparing a benchmark against its baseline.
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub struct BenchComparison {
+    /// Change of the median time per iteration, in percent of the baseline.
+    pub change_pct: f64,
+    /// p-value of the Mann-Whitney U test between the baseline samples and
+    /// the samples of this run.
+    pub p_value: f64,
+}
+
+impl BenchComparison {
+    pub fn is_significant(&self) -> bool {
+        self.p_value < SIGNIFICANCE_LEVEL
+    }
+
+    pub fn verdict(&self) -> &'static str {
+        if !self.is_significant() {
+            "no change"
+        } else if self.change_pct > 0.0 {
+            "regressed"
+        } else {
+            "improved"
+        }
+    }
+}
+
+impl fmt::Display for BenchComparison {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(
+            f,
+            "{:+.2}%, p = {:.3}, {}",
+            self.change_pct,
+            self.p_value,
+            self.verdict()
+        )
+    }
+}
+
+/// The samples of every benchmark of a run, by benchmark name.
+pub struct Baseline {
+    samples: BTreeMap<String, Vec<f64>>,
+}
+
+impl Baseline {
+    pub fn new() -> Baseline {
+        Baseline {
+            samples: BTreeMap::new(),
+        }
+    }
+
+    pub fn insert(&mut self, name: &str, samples: &[f64]) {
+        self.samples.insert(name.to_owned(), samples.to_vec());
+    }
+
+    pub fn load(name: &str) -> io::Result<Baseline> {
+        let path = baseline_path(name)?;
+        let file = File::open(&path).map_err(|e| {
+            io::Error::new(
+                e.kind(),
+                format!("could not open baseline `{}` at {}: {}", name, path.display(), e),
+            )
+        })?;
+
+        let mut baseline = Baseline::new();
+        for line in BufReader::new(file).lines() {
+            let line = line?;
+            let mut parts = line.splitn(2, '\t');
+            let bench = parts.next().unwrap();
+            let samples = parts
+                .next()
+                .unwrap_or("")
+                .split_whitespace()
+                .map(|s| s.parse::<f64>())
+                .collect::<Result<Vec<_>, _>>()
+                .map_err(|e| {
+                    io::Error::new(
+                        io::ErrorKind::InvalidData,
+                        format!(
+                            "malformed samples for `{}` in {}: {}",
+                            bench,
+                            path.display(),
+                            e
+                        ),
+                    )
+                })?;
+            baseline.samples.insert(bench.to_owned(), samples);
+        }
+        Ok(baseline)
+    }
+
+    pub fn save(&self, name: &str) -> io::Result<()> {
+        let path = baseline_path(name)?;
+        if let Some(dir) = path.parent() {
+            fs::create_dir_all(dir)?;
+        }
+
+        let mut out = BufWriter::new(File::create(&path)?);
+        for (bench, samples) in &self.samples {
+            let samples = samples
+                .iter()
+                .map(|s| s.to_string())
+                .collect::<Vec<_>>();
+            writeln!(out, "{}\t{}", bench, samples.join(" "))?;
+        }
+        out.flush()
+    }
+
+    /// Compares the samples of `bs` with the ones recorded for the benchmark
+    /// `name`, if this baseline has any.
+    pub fn compare(&self, name: &str, bs: &BenchSamples) -> Option<BenchComparison> {
+        let old = match self.samples.get(name) {
+            Some(old) if !old.is_empty() => old,
+            _ => return None,
+        };
+        if bs.samples.is_empty() {
+            return None;
+        }
+
+        let old_median = old[..].median();
+        let new_median = bs.samples[..].median();
+        let change_pct = if old_median == 0.0 {
+            0.0
+        } else {
+            (new_median - old_median) / old_median * 100.0
+        };
+
+        Some(BenchComparison {
+            change_pct,
+            p_value: stats::mann_whitney_u(old, &bs.samples),
+        })
+    }
+}
+
+fn baseline_path(name: &str) -> io::Result<PathBuf> {
+    let exe = env::current_exe()?;
+    let dir = match env::var_os("RUST_BENCH_BASELINE_DIR") {
+        Some(dir) => PathBuf::from(dir),
+        None => exe.with_file_name("bench-baselines"),
+    };
+    let exe_name = exe.file_stem()
+        .map(|s| s.to_string_lossy().into_owned())
+        .unwrap_or_else(|| "bench".to_owned());
+    Ok(dir.join(name).join(format!("{}.txt", exe_name)))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::Baseline;
+    use stats;
+    use BenchSamples;
+
+    fn bench_samples(samples: Vec<f64>) -> BenchSamples {
+        BenchSamples {
+            ns_iter_summ: stats::Summary::new(&samples),
+            mb_s: 0,
+            samples,
+            comparison: None,
+        }
+    }
+
+    #[test]
+    fn compare_against_baseline() {
+        let old: Vec<f64> = (0..50).map(|i| 1000.0 + i as f64).collect();
+        let mut baseline = Baseline::new();
+        baseline.insert("bench", &old);
+
+        let same = baseline.compare("bench", &bench_samples(old.clone())).unwrap();
+        assert_eq!(same.change_pct, 0.0);
+        assert!(!same.is_significant());
+
+        let slower = old.iter().map(|s| s * 1.5).collect();
+        let slower = baseline.compare("bench", &bench_samples(slower)).unwrap();
+        assert!((slower.change_pct - 50.0).abs() < 1.0);
+        assert_eq!(slower.verdict(), "regressed");
+
+        let faster = old.iter().map(|s| s / 2.0).collect();
+        let faster = baseline.compare("bench", &bench_samples(faster)).unwrap();
+        assert_eq!(faster.verdict(), "improved");
+
+        assert!(baseline.compare("other", &bench_samples(old)).is_none());
+    }
+}
@@ -12,11 +12,12 @@ use super::*;
 
 pub(crate) struct JsonFormatter<T> {
     out: OutputLocation<T>,
+    time_options: TestTimeOptions,
 }
 
 impl<T: Write> JsonFormatter<T> {
-    pub fn new(out: OutputLocation<T>) -> Self {
-        Self { out }
+    pub fn new(out: OutputLocation<T>, time_options: TestTimeOptions) -> Self {
+        Self { out, time_options }
     }
 
     fn write_message(&mut self, s: &str) -> io::Result<()> {
@@ -31,34 +32,54 @@ impl<T: Write> JsonFormatter<T> {
         ty: &str,
         name: &str,
         evt: &str,
+        exec_time: Option<&TestExecTime>,
         extra: Option<String>,
     ) -> io::Result<()> {
+        let time = match exec_time {
+            Some(t) if self.time_options.is_slow(t) => {
+                Some(format!(r#""exec_time": {:.3}, "slow": true"#, t.as_secs_f64()))
+            }
+            Some(t) if self.time_options.report_time => {
+                Some(format!(r#""exec_time": {:.3}"#, t.as_secs_f64()))
+            }
+            _ => None,
+        };
+        let extra = match (time, extra) {
+            (Some(time), Some(extra)) => Some(format!("{}, {}", time, extra)),
+            (time, None) => time,
+            (None, extra) => extra,
+        };
+
         if let Some(extras) = extra {
             self.write_message(&*format!(
                 r#"{{ "type": "{}", "name": "{}", "event": "{}", {} }}"#,
-                ty, name, evt, extras
+                ty, EscapedString(name), evt, extras
             ))
         } else {
             self.write_message(&*format!(
                 r#"{{ "type": "{}", "name": "{}", "event": "{}" }}"#,
-                ty, name, evt
+                ty, EscapedString(name), evt
             ))
         }
     }
 }
 
 impl<T: Write> OutputFormatter for JsonFormatter<T> {
-    fn write_run_start(&mut self, test_count: usize) -> io::Result<()> {
+    fn write_run_start(&mut self, test_count: usize, shuffle_seed: Option<u64>) -> io::Result<()> {
+        let shuffle_seed_json = match shuffle_seed {
+            Some(seed) => format!(r#", "shuffle_seed": {}"#, seed),
+            None => String::new(),
+        };
         self.write_message(&*format!(
-            r#"{{ "type": "suite", "event": "started", "test_count": "{}" }}"#,
-            test_count
+            r#"{{ "type": "suite", "event": "started", "test_count": "{}"{} }}"#,
+            test_count, shuffle_seed_json
         ))
     }
 
     fn write_test_start(&mut self, desc: &TestDesc) -> io::Result<()> {
         self.write_message(&*format!(
             r#"{{ "type": "test", "event": "started", "name": "{}" }}"#,
-            desc.name
+            EscapedString(desc.name.as_slice())
         ))
     }
 
@@ -66,10 +87,11 @@ impl<T: Write> OutputFormatter for JsonFormatter<T> {
         &mut self,
         desc: &TestDesc,
         result: &TestResult,
+        exec_time: Option<&TestExecTime>,
         stdout: &[u8],
     ) -> io::Result<()> {
         match *result {
-            TrOk => self.write_event("test", desc.name.as_slice(), "ok", None),
+            TrOk => self.write_event("test", desc.name.as_slice(), "ok", exec_time, None),
 
             TrFailed => {
                 let extra_data = if stdout.len() > 0 {
@@ -81,22 +103,37 @@ impl<T: Write> OutputFormatter for JsonFormatter<T> {
                     None
                 };
 
-                self.write_event("test", desc.name.as_slice(), "failed", extra_data)
+                self.write_event("test", desc.name.as_slice(), "failed", exec_time, extra_data)
             }
 
             TrFailedMsg(ref m) => self.write_event(
                 "test",
                 desc.name.as_slice(),
                 "failed",
+                exec_time,
                 Some(format!(r#""message": "{}""#, EscapedString(m))),
             ),
 
-            TrIgnored => self.write_event("test", desc.name.as_slice(), "ignored", None),
-
-            TrAllowedFail => {
-                self.write_event("test", desc.name.as_slice(), "allowed_failure", None)
+            TrIgnored => {
+                self.write_event("test", desc.name.as_slice(), "ignored", exec_time, None)
             }
 
+            TrAllowedFail => self.write_event(
+                "test",
+                desc.name.as_slice(),
+                "allowed_failure",
+                exec_time,
+                None,
+            ),
+
+            TrFlaky(attempts) => self.write_event(
+                "test",
+                desc.name.as_slice(),
+                "flaky",
+                exec_time,
+                Some(format!(r#""attempts": {}"#, attempts)),
+            ),
+
             TrBench(ref bs) => {
                 let median = bs.ns_iter_summ.median as usize;
                 let deviation = (bs.ns_iter_summ.max - bs.ns_iter_summ.min) as usize;
@@ -107,12 +144,24 @@ impl<T: Write> OutputFormatter for JsonFormatter<T> {
                     format!(r#", "mib_per_second": {}"#, bs.mb_s)
                 };
 
+                let baseline = match bs.comparison {
+                    Some(ref c) => format!(
+                        ", \"baseline_change_pct\": {:.2}, \
+                         \"baseline_p_value\": {:.4}, \
+                         \"baseline_verdict\": \"{}\"",
+                        c.change_pct,
+                        c.p_value,
+                        c.verdict()
+                    ),
+                    None => "".into(),
+                };
+
                 let line = format!(
                     "{{ \"type\": \"bench\", \
                      \"name\": \"{}\", \
                      \"median\": {}, \
-                     \"deviation\": {}{} }}",
-                    desc.name, median, deviation, mbps
+                     \"deviation\": {}{}{} }}",
+                    EscapedString(desc.name.as_slice()), median, deviation, mbps, baseline
                 );
 
                 self.write_message(&*line)
@@ -123,7 +172,7 @@ impl<T: Write> OutputFormatter for JsonFormatter<T> {
     fn write_timeout(&mut self, desc: &TestDesc) -> io::Result<()> {
         self.write_message(&*format!(
             r#"{{ "type": "test", "event": "timeout", "name": "{}" }}"#,
-            desc.name
+            EscapedString(desc.name.as_slice())
         ))
     }
 
@@ -134,16 +183,22 @@ impl<T: Write> OutputFormatter for JsonFormatter<T> {
              \"passed\": {}, \
              \"failed\": {}, \
              \"allowed_fail\": {}, \
+             \"flaky\": {}, \
              \"ignored\": {}, \
              \"measured\": {}, \
-             \"filtered_out\": \"{}\" }}",
+             \"filtered_out\": \"{}\"{} }}",
             if state.failed == 0 { "ok" } else { "failed" },
             state.passed,
             state.failed + state.allowed_fail,
             state.allowed_fail,
+            state.flaky,
             state.ignored,
             state.measured,
-            state.filtered_out
+            state.filtered_out,
+            match state.exec_time {
+                Some(ref t) => format!(r#", "exec_time": {:.3}"#, t.as_secs_f64()),
+                None => String::new(),
+            }
         ))?;
 
         Ok(state.failed == 0)
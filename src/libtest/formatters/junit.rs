@@ -0,0 +1,205 @@
+// Copyright 2018 The Rust Project Developers. See the COPYRIGHT
+// file at the top-level directory of this distribution and at
+// http://rust-lang.org/COPYRIGHT.
+//
+// Licensed under the Apache License, Version 2.0 <LICENSE-APACHE or
+// http://www.apache.org/licenses/LICENSE-2.0> or the MIT license
+// <LICENSE-MIT or http://opensource.org/licenses/MIT>, at your
+// option. This file may not be copied, modified, or distributed
+// except according to those terms.
+
+use super::*;
+use std::mem;
+
+pub(crate) struct JunitFormatter<T> {
+    out: OutputLocation<T>,
+
+    /// Results are buffered because the `<testsuite>` element has to carry
+    /// the totals, which are only known once the run has finished.
+    results: Vec<(TestDesc, TestResult, Duration, Vec<u8>)>,
+}
+
+impl<T: Write> JunitFormatter<T> {
+    pub fn new(out: OutputLocation<T>) -> Self {
+        Self {
+            out,
+            results: Vec::new(),
+        }
+    }
+
+    #[cfg(test)]
+    pub fn output_location(&self) -> &OutputLocation<T> {
+        &self.out
+    }
+
+    fn write_message(&mut self, s: &str) -> io::Result<()> {
+        assert!(!s.contains('\n'));
+
+        self.out.write_all(s.as_ref())?;
+        self.out.write_all(b"\n")
+    }
+
+    fn write_testcase_start(&mut self, desc: &TestDesc, duration: Duration) -> io::Result<()> {
+        let (class_name, test_name) = split_class_name(desc);
+        self.write_message(&*format!(
+            r#"<testcase classname="{}" name="{}" time="{}">"#,
+            EscapedXml(class_name),
+            EscapedXml(test_name),
+            fmt_seconds(duration)
+        ))
+    }
+
+    fn write_system_out(&mut self, stdout: &[u8]) -> io::Result<()> {
+        if stdout.is_empty() {
+            return Ok(());
+        }
+
+        // Newlines are escaped so that every element stays on a single line.
+        self.write_message(&*format!(
+            "<system-out>{}</system-out>",
+            EscapedXml(String::from_utf8_lossy(stdout))
+        ))
+    }
+}
+
+impl<T: Write> OutputFormatter for JunitFormatter<T> {
+    fn write_run_start(
+        &mut self,
+        _test_count: usize,
+        _shuffle_seed: Option<u64>,
+    ) -> io::Result<()> {
+        // Nothing is written until the totals are known.
+        Ok(())
+    }
+
+    fn write_test_start(&mut self, _desc: &TestDesc) -> io::Result<()> {
+        Ok(())
+    }
+
+    fn write_timeout(&mut self, _desc: &TestDesc) -> io::Result<()> {
+        Ok(())
+    }
+
+    fn write_result(
+        &mut self,
+        desc: &TestDesc,
+        result: &TestResult,
+        exec_time: Option<&TestExecTime>,
+        stdout: &[u8],
+    ) -> io::Result<()> {
+        let duration = exec_time.map(|t| t.0).unwrap_or(Duration::new(0, 0));
+        self.results
+            .push((desc.clone(), result.clone(), duration, stdout.to_vec()));
+        Ok(())
+    }
+
+    fn write_run_finish(&mut self, state: &ConsoleTestState) -> io::Result<bool> {
+        let total_time = self.results
+            .iter()
+            .fold(Duration::new(0, 0), |acc, &(_, _, duration, _)| acc + duration);
+
+        self.write_message(r#"<?xml version="1.0" encoding="UTF-8"?>"#)?;
+        self.write_message("<testsuites>")?;
+        self.write_message(&*format!(
+            "<testsuite name=\"test\" package=\"test\" id=\"0\" \
+             errors=\"0\" \
+             failures=\"{}\" \
+             tests=\"{}\" \
+             skipped=\"{}\" \
+             time=\"{}\">",
+            state.failed,
+            state.total,
+            state.ignored,
+            fmt_seconds(total_time)
+        ))?;
+
+        for (desc, result, duration, stdout) in mem::replace(&mut self.results, Vec::new()) {
+            self.write_testcase_start(&desc, duration)?;
+            match result {
+                TrOk | TrAllowedFail | TrFlaky(_) | TrBench(_) => {}
+
+                TrIgnored => self.write_message("<skipped/>")?,
+
+                TrFailed => {
+                    self.write_message(r#"<failure type="assert"/>"#)?;
+                    self.write_system_out(&stdout)?;
+                }
+
+                TrFailedMsg(ref m) => {
+                    self.write_message(&*format!(
+                        r#"<failure type="assert" message="{}"/>"#,
+                        EscapedXml(m)
+                    ))?;
+                    self.write_system_out(&stdout)?;
+                }
+            }
+            self.write_message("</testcase>")?;
+        }
+
+        self.write_message("<system-out/>")?;
+        self.write_message("<system-err/>")?;
+        self.write_message("</testsuite>")?;
+        self.write_message("</testsuites>")?;
+
+        Ok(state.failed == 0)
+    }
+}
+
+/// Splits a test path into the module path, which JUnit consumers use as the
+/// class name, and the name of the test function itself. Only the part before
+/// any arguments is split, so that `a::case(b::C)` is the test `case(b::C)`
+/// of the module `a`.
+fn split_class_name(desc: &TestDesc) -> (&str, &str) {
+    let name = desc.name.as_slice();
+    let path_end = name.find('(').unwrap_or(name.len());
+    match name[..path_end].rfind("::") {
+        Some(i) => (&name[..i], &name[i + 2..]),
+        None => ("crate", name),
+    }
+}
+
+fn fmt_seconds(duration: Duration) -> String {
+    format!("{:.3}", TestExecTime(duration).as_secs_f64())
+}
+
+/// A formatting utility used to print strings as XML attribute values or
+/// character data.
+struct EscapedXml<S: AsRef<str>>(S);
+
+impl<S: AsRef<str>> ::std::fmt::Display for EscapedXml<S> {
+    fn fmt(&self, f: &mut ::std::fmt::Formatter) -> ::std::fmt::Result {
+        let mut start = 0;
+
+        for (i, byte) in self.0.as_ref().bytes().enumerate() {
+            let escaped = match byte {
+                b'<' => "&lt;",
+                b'>' => "&gt;",
+                b'&' => "&amp;",
+                b'"' => "&quot;",
+                b'\'' => "&apos;",
+                b'\n' => "&#10;",
+                b'\r' => "&#13;",
+                b'\t' => "&#9;",
+                // Other control characters are not allowed in XML 1.0 at all.
+                _ if byte < b' ' => "\u{fffd}",
+                _ => {
+                    continue;
+                }
+            };
+
+            if start < i {
+                f.write_str(&self.0.as_ref()[start..i])?;
+            }
+
+            f.write_str(escaped)?;
+
+            start = i + 1;
+        }
+
+        if start != self.0.as_ref().len() {
+            f.write_str(&self.0.as_ref()[start..])?;
+        }
+
+        Ok(())
+    }
+}
@@ -18,6 +18,8 @@ pub(crate) struct PrettyFormatter<T> {
     max_name_len: usize,
 
     is_multithreaded: bool,
+
+    time_options: TestTimeOptions,
 }
 
 impl<T: Write> PrettyFormatter<T> {
@@ -26,12 +28,14 @@ impl<T: Write> PrettyFormatter<T> {
         use_color: bool,
         max_name_len: usize,
         is_multithreaded: bool,
+        time_options: TestTimeOptions,
     ) -> Self {
         PrettyFormatter {
             out,
             use_color,
             max_name_len,
             is_multithreaded,
+            time_options,
         }
     }
 
@@ -41,32 +45,40 @@ impl<T: Write> PrettyFormatter<T> {
     }
 
     pub fn write_ok(&mut self) -> io::Result<()> {
-        self.write_short_result("ok", term::color::GREEN)
+        self.write_pretty("ok", term::color::GREEN)
     }
 
     pub fn write_failed(&mut self) -> io::Result<()> {
-        self.write_short_result("FAILED", term::color::RED)
+        self.write_pretty("FAILED", term::color::RED)
     }
 
     pub fn write_ignored(&mut self) -> io::Result<()> {
-        self.write_short_result("ignored", term::color::YELLOW)
+        self.write_pretty("ignored", term::color::YELLOW)
     }
 
     pub fn write_allowed_fail(&mut self) -> io::Result<()> {
-        self.write_short_result("FAILED (allowed)", term::color::YELLOW)
+        self.write_pretty("FAILED (allowed)", term::color::YELLOW)
+    }
+
+    pub fn write_flaky(&mut self, attempts: usize) -> io::Result<()> {
+        self.write_pretty("flaky", term::color::YELLOW)?;
+        self.write_plain(&format!(" ({} attempts)", attempts))
     }
 
     pub fn write_bench(&mut self) -> io::Result<()> {
         self.write_pretty("bench", term::color::CYAN)
     }
 
-    pub fn write_short_result(
-        &mut self,
-        result: &str,
-        color: term::color::Color,
-    ) -> io::Result<()> {
-        self.write_pretty(result, color)?;
-        self.write_plain("\n")
+    pub fn write_time(&mut self, exec_time: Option<&TestExecTime>) -> io::Result<()> {
+        if let Some(exec_time) = exec_time {
+            if self.time_options.is_slow(exec_time) {
+                self.write_plain(" ")?;
+                self.write_pretty(&format!("<{}, slow>", exec_time), term::color::YELLOW)?;
+            } else if self.time_options.report_time {
+                self.write_plain(&format!(" <{}>", exec_time))?;
+            }
+        }
+        Ok(())
     }
 
     pub fn write_pretty(&mut self, word: &str, color: term::color::Color) -> io::Result<()> {
@@ -146,6 +158,26 @@ impl<T: Write> PrettyFormatter<T> {
         Ok(())
     }
 
+    pub fn write_slow_tests(&mut self, state: &ConsoleTestState) -> io::Result<()> {
+        self.write_plain("\nslow tests:\n")?;
+        let mut slow_tests = state.slow_tests.clone();
+        slow_tests.sort_by(|a, b| b.1.cmp(&a.1));
+        for (desc, exec_time) in slow_tests {
+            self.write_plain(&format!("    {} <{}>\n", desc.name, exec_time))?;
+        }
+        Ok(())
+    }
+
+    pub fn write_flaky_tests(&mut self, state: &ConsoleTestState) -> io::Result<()> {
+        self.write_plain("\nflaky tests:\n")?;
+        let mut flaky_tests = state.flaky_tests.clone();
+        flaky_tests.sort_by(|a, b| a.0.name.as_slice().cmp(b.0.name.as_slice()));
+        for (desc, attempts) in flaky_tests {
+            self.write_plain(&format!("    {} ({} attempts)\n", desc.name, attempts))?;
+        }
+        Ok(())
+    }
+
     fn write_test_name(&mut self, desc: &TestDesc) -> io::Result<()> {
         let name = desc.padded_name(self.max_name_len, desc.name.padding());
         self.write_plain(&format!("test {} ... ", name))?;
@@ -155,9 +187,16 @@ impl<T: Write> PrettyFormatter<T> {
 }
 
 impl<T: Write> OutputFormatter for PrettyFormatter<T> {
-    fn write_run_start(&mut self, test_count: usize) -> io::Result<()> {
+    fn write_run_start(&mut self, test_count: usize, shuffle_seed: Option<u64>) -> io::Result<()> {
         let noun = if test_count != 1 { "tests" } else { "test" };
-        self.write_plain(&format!("\nrunning {} {}\n", test_count, noun))
+        let shuffle_seed_msg = match shuffle_seed {
+            Some(seed) => format!(" (shuffle seed: {})", seed),
+            None => String::new(),
+        };
+        self.write_plain(&format!(
+            "\nrunning {} {}{}\n",
+            test_count, noun, shuffle_seed_msg
+        ))
     }
 
     fn write_test_start(&mut self, desc: &TestDesc) -> io::Result<()> {
@@ -172,21 +211,31 @@ impl<T: Write> OutputFormatter for PrettyFormatter<T> {
         Ok(())
     }
 
-    fn write_result(&mut self, desc: &TestDesc, result: &TestResult, _: &[u8]) -> io::Result<()> {
+    fn write_result(
+        &mut self,
+        desc: &TestDesc,
+        result: &TestResult,
+        exec_time: Option<&TestExecTime>,
+        _: &[u8],
+    ) -> io::Result<()> {
         if self.is_multithreaded {
             self.write_test_name(desc)?;
         }
 
         match *result {
-            TrOk => self.write_ok(),
-            TrFailed | TrFailedMsg(_) => self.write_failed(),
-            TrIgnored => self.write_ignored(),
-            TrAllowedFail => self.write_allowed_fail(),
+            TrOk => self.write_ok()?,
+            TrFailed | TrFailedMsg(_) => self.write_failed()?,
+            TrIgnored => self.write_ignored()?,
+            TrAllowedFail => self.write_allowed_fail()?,
+            TrFlaky(attempts) => self.write_flaky(attempts)?,
             TrBench(ref bs) => {
                 self.write_bench()?;
-                self.write_plain(&format!(": {}\n", fmt_bench_samples(bs)))
+                self.write_plain(&format!(": {}", fmt_bench_samples(bs)))?;
             }
         }
+
+        self.write_time(exec_time)?;
+        self.write_plain("\n")
     }
 
     fn write_timeout(&mut self, desc: &TestDesc) -> io::Result<()> {
@@ -204,6 +253,12 @@ impl<T: Write> OutputFormatter for PrettyFormatter<T> {
         if state.options.display_output {
             self.write_successes(state)?;
         }
+        if !state.slow_tests.is_empty() {
+            self.write_slow_tests(state)?;
+        }
+        if !state.flaky_tests.is_empty() {
+            self.write_flaky_tests(state)?;
+        }
         let success = state.failed == 0;
         if !success {
             self.write_failures(state)?;
@@ -218,10 +273,17 @@ impl<T: Write> OutputFormatter for PrettyFormatter<T> {
             self.write_pretty("FAILED", term::color::RED)?;
         }
 
-        let s = if state.allowed_fail > 0 {
+        let flaky = if state.flaky > 0 {
+            format!("; {} flaky", state.flaky)
+        } else {
+            String::new()
+        };
+
+        let mut s = if state.allowed_fail > 0 {
             format!(
-                ". {} passed; {} failed ({} allowed); {} ignored; {} measured; {} filtered out\n\n",
+                ". {} passed{}; {} failed ({} allowed); {} ignored; {} measured; {} filtered out",
                 state.passed,
+                flaky,
                 state.failed + state.allowed_fail,
                 state.allowed_fail,
                 state.ignored,
@@ -230,11 +292,21 @@ impl<T: Write> OutputFormatter for PrettyFormatter<T> {
             )
         } else {
             format!(
-                ". {} passed; {} failed; {} ignored; {} measured; {} filtered out\n\n",
-                state.passed, state.failed, state.ignored, state.measured, state.filtered_out
+                ". {} passed{}; {} failed; {} ignored; {} measured; {} filtered out",
+                state.passed,
+                flaky,
+                state.failed,
+                state.ignored,
+                state.measured,
+                state.filtered_out
             )
         };
 
+        if let Some(ref exec_time) = state.exec_time {
+            s.push_str(&format!("; finished in {}", exec_time));
+        }
+        s.push_str("\n\n");
+
         self.write_plain(&s)?;
 
         Ok(success)
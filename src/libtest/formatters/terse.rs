@@ -16,8 +16,12 @@ pub(crate) struct TerseFormatter<T> {
     is_multithreaded: bool,
     /// Number of columns to fill when aligning names
     max_name_len: usize,
+    time_options: TestTimeOptions,
 
     test_count: usize,
+    /// Execution times printed at the end with `--report-time`, as they do
+    /// not fit between the dots
+    test_times: Vec<(TestDesc, TestExecTime)>,
 }
 
 impl<T: Write> TerseFormatter<T> {
@@ -26,16 +30,24 @@ impl<T: Write> TerseFormatter<T> {
         use_color: bool,
         max_name_len: usize,
         is_multithreaded: bool,
+        time_options: TestTimeOptions,
     ) -> Self {
         TerseFormatter {
             out,
             use_color,
             max_name_len,
             is_multithreaded,
+            time_options,
             test_count: 0,
+            test_times: Vec::new(),
         }
     }
 
+    #[cfg(test)]
+    pub fn output_location(&self) -> &OutputLocation<T> {
+        &self.out
+    }
+
     pub fn write_ok(&mut self) -> io::Result<()> {
         self.write_short_result(".", term::color::GREEN)
     }
@@ -52,6 +64,10 @@ impl<T: Write> TerseFormatter<T> {
         self.write_short_result("a", term::color::YELLOW)
     }
 
+    pub fn write_flaky(&mut self) -> io::Result<()> {
+        self.write_short_result("f", term::color::YELLOW)
+    }
+
     pub fn write_bench(&mut self) -> io::Result<()> {
         self.write_pretty("bench", term::color::CYAN)
     }
@@ -150,6 +166,36 @@ impl<T: Write> TerseFormatter<T> {
         Ok(())
     }
 
+    pub fn write_slow_tests(&mut self, state: &ConsoleTestState) -> io::Result<()> {
+        self.write_plain("\nslow tests:\n")?;
+        let mut slow_tests = state.slow_tests.clone();
+        slow_tests.sort_by(|a, b| b.1.cmp(&a.1));
+        for (desc, exec_time) in slow_tests {
+            self.write_plain(&format!("    {} <{}>\n", desc.name, exec_time))?;
+        }
+        Ok(())
+    }
+
+    pub fn write_test_times(&mut self) -> io::Result<()> {
+        self.write_plain("\ntest times:\n")?;
+        let mut test_times = self.test_times.clone();
+        test_times.sort_by(|a, b| b.1.cmp(&a.1));
+        for (desc, exec_time) in test_times {
+            self.write_plain(&format!("    {} <{}>\n", desc.name, exec_time))?;
+        }
+        Ok(())
+    }
+
+    pub fn write_flaky_tests(&mut self, state: &ConsoleTestState) -> io::Result<()> {
+        self.write_plain("\nflaky tests:\n")?;
+        let mut flaky_tests = state.flaky_tests.clone();
+        flaky_tests.sort_by(|a, b| a.0.name.as_slice().cmp(b.0.name.as_slice()));
+        for (desc, attempts) in flaky_tests {
+            self.write_plain(&format!("    {} ({} attempts)\n", desc.name, attempts))?;
+        }
+        Ok(())
+    }
+
     fn write_test_name(&mut self, desc: &TestDesc) -> io::Result<()> {
         let name = desc.padded_name(self.max_name_len, desc.name.padding());
         self.write_plain(&format!("test {} ... ", name))?;
@@ -159,9 +205,16 @@ impl<T: Write> TerseFormatter<T> {
 }
 
 impl<T: Write> OutputFormatter for TerseFormatter<T> {
-    fn write_run_start(&mut self, test_count: usize) -> io::Result<()> {
+    fn write_run_start(&mut self, test_count: usize, shuffle_seed: Option<u64>) -> io::Result<()> {
         let noun = if test_count != 1 { "tests" } else { "test" };
-        self.write_plain(&format!("\nrunning {} {}\n", test_count, noun))
+        let shuffle_seed_msg = match shuffle_seed {
+            Some(seed) => format!(" (shuffle seed: {})", seed),
+            None => String::new(),
+        };
+        self.write_plain(&format!(
+            "\nrunning {} {}{}\n",
+            test_count, noun, shuffle_seed_msg
+        ))
     }
 
     fn write_test_start(&mut self, desc: &TestDesc) -> io::Result<()> {
@@ -176,12 +229,25 @@ impl<T: Write> OutputFormatter for TerseFormatter<T> {
         Ok(())
     }
 
-    fn write_result(&mut self, desc: &TestDesc, result: &TestResult, _: &[u8]) -> io::Result<()> {
+    fn write_result(
+        &mut self,
+        desc: &TestDesc,
+        result: &TestResult,
+        exec_time: Option<&TestExecTime>,
+        _: &[u8],
+    ) -> io::Result<()> {
+        if let Some(exec_time) = exec_time {
+            if self.time_options.report_time {
+                self.test_times.push((desc.clone(), *exec_time));
+            }
+        }
+
         match *result {
             TrOk => self.write_ok(),
             TrFailed | TrFailedMsg(_) => self.write_failed(),
             TrIgnored => self.write_ignored(),
             TrAllowedFail => self.write_allowed_fail(),
+            TrFlaky(_) => self.write_flaky(),
             TrBench(ref bs) => {
                 if self.is_multithreaded {
                     self.write_test_name(desc)?;
@@ -203,6 +269,15 @@ impl<T: Write> OutputFormatter for TerseFormatter<T> {
         if state.options.display_output {
             self.write_outputs(state)?;
         }
+        if !self.test_times.is_empty() {
+            self.write_test_times()?;
+        }
+        if !state.slow_tests.is_empty() {
+            self.write_slow_tests(state)?;
+        }
+        if !state.flaky_tests.is_empty() {
+            self.write_flaky_tests(state)?;
+        }
         let success = state.failed == 0;
         if !success {
             self.write_failures(state)?;
@@ -217,10 +292,17 @@ impl<T: Write> OutputFormatter for TerseFormatter<T> {
             self.write_pretty("FAILED", term::color::RED)?;
         }
 
-        let s = if state.allowed_fail > 0 {
+        let flaky = if state.flaky > 0 {
+            format!("; {} flaky", state.flaky)
+        } else {
+            String::new()
+        };
+
+        let mut s = if state.allowed_fail > 0 {
             format!(
-                ". {} passed; {} failed ({} allowed); {} ignored; {} measured; {} filtered out\n\n",
+                ". {} passed{}; {} failed ({} allowed); {} ignored; {} measured; {} filtered out",
                 state.passed,
+                flaky,
                 state.failed + state.allowed_fail,
                 state.allowed_fail,
                 state.ignored,
@@ -229,11 +311,21 @@ impl<T: Write> OutputFormatter for TerseFormatter<T> {
             )
         } else {
             format!(
-                ". {} passed; {} failed; {} ignored; {} measured; {} filtered out\n\n",
-                state.passed, state.failed, state.ignored, state.measured, state.filtered_out
+                ". {} passed{}; {} failed; {} ignored; {} measured; {} filtered out",
+                state.passed,
+                flaky,
+                state.failed,
+                state.ignored,
+                state.measured,
+                state.filtered_out
             )
         };
 
+        if let Some(ref exec_time) = state.exec_time {
+            s.push_str(&format!("; finished in {}", exec_time));
+        }
+        s.push_str("\n\n");
+
         self.write_plain(&s)?;
 
         Ok(success)
@@ -53,14 +53,16 @@ use self::TestEvent::*;
 use self::NamePadding::*;
 use self::OutputLocation::*;
 
-use std::panic::{catch_unwind, AssertUnwindSafe};
+use std::panic::{catch_unwind, resume_unwind, AssertUnwindSafe};
 use std::any::Any;
 use std::boxed::FnBox;
 use std::cmp;
 use std::collections::BTreeMap;
+use std::collections::hash_map::DefaultHasher;
 use std::env;
 use std::fmt;
 use std::fs::File;
+use std::hash::{Hash, Hasher};
 use std::io::prelude::*;
 use std::io;
 use std::iter::repeat;
@@ -69,7 +71,7 @@ use std::process::Termination;
 use std::sync::mpsc::{channel, Sender};
 use std::sync::{Arc, Mutex};
 use std::thread;
-use std::time::{Duration, Instant};
+use std::time::{Duration, Instant, SystemTime, UNIX_EPOCH};
 use std::borrow::Cow;
 use std::process;
 
@@ -80,14 +82,18 @@ const QUIET_MODE_MAX_COLUMN: usize = 100; // insert a '\n' after 100 tests in qu
 pub mod test {
     pub use {assert_test_result, filter_tests, parse_opts, run_test, test_main, test_main_static,
              Bencher, DynTestFn, DynTestName, Metric, MetricMap, Options, ShouldPanic,
-             StaticBenchFn, StaticTestFn, StaticTestName, TestDesc, TestDescAndFn, TestName,
-             TestOpts, TestResult, TrFailed, TrFailedMsg, TrIgnored, TrOk};
+             StaticBenchFn, StaticTestFn, StaticTestName, TestDesc, TestDescAndFn, TestExecTime,
+             TestName, TestOpts, TestResult, TestTimeOptions, TrFailed, TrFailedMsg, TrIgnored,
+             TrOk};
 }
 
 pub mod stats;
+mod baseline;
 mod formatters;
 
-use formatters::{JsonFormatter, OutputFormatter, PrettyFormatter, TerseFormatter};
+use baseline::{Baseline, BenchComparison};
+use formatters::{JsonFormatter, JunitFormatter, OutputFormatter, PrettyFormatter,
+                 TerseFormatter};
 
 // The name of a test. By convention this follows the rules for rust
 // paths; i.e. it should be a series of identifiers separated by double
@@ -167,6 +173,8 @@ pub enum TestFn {
     StaticBenchFn(fn(&mut Bencher)),
     DynTestFn(Box<FnBox() + Send>),
     DynBenchFn(Box<TDynBenchFn + 'static>),
+    /// A test which can be run again, and so is retried with `--retries`.
+    DynRetryableTestFn(Box<FnMut() + Send>),
 }
 
 impl TestFn {
@@ -176,6 +184,7 @@ impl TestFn {
             StaticBenchFn(..) => PadOnRight,
             DynTestFn(..) => PadNone,
             DynBenchFn(..) => PadOnRight,
+            DynRetryableTestFn(..) => PadNone,
         }
     }
 }
@@ -187,6 +196,7 @@ impl fmt::Debug for TestFn {
             StaticBenchFn(..) => "StaticBenchFn(..)",
             DynTestFn(..) => "DynTestFn(..)",
             DynBenchFn(..) => "DynBenchFn(..)",
+            DynRetryableTestFn(..) => "DynRetryableTestFn(..)",
         })
     }
 }
@@ -200,6 +210,7 @@ impl fmt::Debug for TestFn {
 pub struct Bencher {
     mode: BenchMode,
     summary: Option<stats::Summary>,
+    samples: Vec<f64>,
     pub bytes: u64,
 }
 
@@ -248,12 +259,14 @@ impl Metric {
 #[derive(Copy, Clone, Debug)]
 pub struct Options {
     display_output: bool,
+    preserve_order: bool,
 }
 
 impl Options {
     pub fn new() -> Options {
         Options {
             display_output: false,
+            preserve_order: false,
         }
     }
 
@@ -261,11 +274,22 @@ impl Options {
         self.display_output = display_output;
         self
     }
+
+    /// Run the tests in the order they were given in, rather than sorted by
+    /// name.
+    pub fn preserve_order(mut self, preserve_order: bool) -> Options {
+        self.preserve_order = preserve_order;
+        self
+    }
 }
 
 // The default console test runner. It accepts the command line
 // arguments and a vector of test_descs.
 pub fn test_main(args: &[String], tests: Vec<TestDescAndFn>, options: Options) {
+    if let Ok(name) = env::var(ISOLATED_TEST_VAR) {
+        run_isolated_test(&name, tests);
+    }
+
     let mut opts = match parse_opts(args) {
         Some(Ok(o)) => o,
         Some(Err(msg)) => {
@@ -326,6 +350,61 @@ pub fn assert_test_result<T: Termination>(result: T) {
     assert_eq!(result.report(), 0);
 }
 
+/// A resource needed by a test, such as a temporary directory or a local
+/// server. Test functions taking arguments get one fixture per argument,
+/// which is set up before the test runs and torn down after it, even if
+/// the test panicked. Requires `#![feature(test_fixtures)]`.
+pub trait TestFixture: Sized {
+    /// Creates the fixture. A panic here fails the test.
+    fn setup() -> Self;
+
+    /// Releases the fixture once the test has finished. Does nothing but
+    /// drop it by default.
+    fn teardown(self) {}
+}
+
+/// Sets up a fixture, passes it to `test` and tears it down again once
+/// `test` has returned or panicked. Invoked by the test harness for every
+/// argument of a test function.
+pub fn with_fixture<F, R, T>(test: T) -> R
+where
+    F: TestFixture,
+    T: FnOnce(&mut F) -> R,
+{
+    let mut fixture = F::setup();
+    let result = catch_unwind(AssertUnwindSafe(|| test(&mut fixture)));
+    fixture.teardown();
+    match result {
+        Ok(r) => r,
+        Err(e) => resume_unwind(e),
+    }
+}
+
+/// Runs a `#[test_setup]` function before `test`. Invoked by the test
+/// harness for every module enclosing a test that has such a function.
+pub fn with_setup<R, T>(setup: fn(), test: T) -> R
+where
+    T: FnOnce() -> R,
+{
+    setup();
+    test()
+}
+
+/// Runs a `#[test_teardown]` function after `test`, even if `test`
+/// panicked. Invoked by the test harness for every module enclosing a test
+/// that has such a function.
+pub fn with_teardown<R, T>(teardown: fn(), test: T) -> R
+where
+    T: FnOnce() -> R,
+{
+    let result = catch_unwind(AssertUnwindSafe(test));
+    teardown();
+    match result {
+        Ok(r) => r,
+        Err(e) => resume_unwind(e),
+    }
+}
+
 #[derive(Copy, Clone, Debug)]
 pub enum ColorConfig {
     AutoColor,
@@ -338,6 +417,28 @@ pub enum OutputFormat {
     Pretty,
     Terse,
     Json,
+    Junit,
+}
+
+/// Thresholds used to report on and enforce the execution time of tests.
+#[derive(Copy, Clone, Debug, Default, PartialEq, Eq)]
+pub struct TestTimeOptions {
+    /// Print the execution time of every test.
+    pub report_time: bool,
+    /// Tests running for longer than this are reported as slow.
+    pub warn_time: Option<Duration>,
+    /// Tests running for longer than this are reported as failed.
+    pub fail_after: Option<Duration>,
+}
+
+impl TestTimeOptions {
+    pub fn is_slow(&self, exec_time: &TestExecTime) -> bool {
+        self.warn_time.map_or(false, |limit| exec_time.0 > limit)
+    }
+
+    pub fn is_too_slow(&self, exec_time: &TestExecTime) -> bool {
+        self.fail_after.map_or(false, |limit| exec_time.0 > limit)
+    }
 }
 
 #[derive(Debug)]
@@ -354,6 +455,18 @@ pub struct TestOpts {
     pub format: OutputFormat,
     pub test_threads: Option<usize>,
     pub skip: Vec<String>,
+    /// Only run the tests of shard `.0` (counting from zero) out of `.1` shards.
+    pub shard: Option<(usize, usize)>,
+    pub shuffle: bool,
+    pub shuffle_seed: Option<u64>,
+    pub save_baseline: Option<String>,
+    pub baseline: Option<String>,
+    pub time_options: TestTimeOptions,
+    /// Run every test in a process of its own.
+    pub isolate: bool,
+    /// Number of times a failed test is run again before it is considered
+    /// failed.
+    pub retries: usize,
     pub options: Options,
 }
 
@@ -373,6 +486,14 @@ impl TestOpts {
             format: OutputFormat::Pretty,
             test_threads: None,
             skip: vec![],
+            shard: None,
+            shuffle: false,
+            shuffle_seed: None,
+            save_baseline: None,
+            baseline: None,
+            time_options: TestTimeOptions::default(),
+            isolate: false,
+            retries: 0,
             options: Options::new(),
         }
     }
@@ -421,6 +542,84 @@ fn optgroups() -> getopts::Options {
             "Display one character per test instead of one line. \
              Alias to --format=terse",
         )
+        .optopt(
+            "",
+            "shard-index",
+            "Only run the tests of shard N (counting from 0) out of the \
+             number given with --shard-count. Only available with \
+             -Z unstable-options",
+            "N",
+        )
+        .optopt(
+            "",
+            "shard-count",
+            "Split the tests into COUNT shards. Only available with \
+             -Z unstable-options",
+            "COUNT",
+        )
+        .optflag(
+            "",
+            "shuffle",
+            "Run tests in a random order. Only available with \
+             -Z unstable-options",
+        )
+        .optopt(
+            "",
+            "shuffle-seed",
+            "Run tests in a random order determined by SEED, which \
+             implies --shuffle. Only available with -Z unstable-options",
+            "SEED",
+        )
+        .optopt(
+            "",
+            "save-baseline",
+            "Save the samples of the benchmarks run as the baseline NAME. \
+             Baselines are stored next to the test binary, or in \
+             RUST_BENCH_BASELINE_DIR. Only available with -Z unstable-options",
+            "NAME",
+        )
+        .optopt(
+            "",
+            "baseline",
+            "Compare the benchmarks run against the baseline NAME. \
+             Only available with -Z unstable-options",
+            "NAME",
+        )
+        .optflag(
+            "",
+            "report-time",
+            "Show the execution time of each test. Only available \
+             with -Z unstable-options",
+        )
+        .optopt(
+            "",
+            "warn-time",
+            "Report tests running for longer than MS milliseconds \
+             as slow. Only available with -Z unstable-options",
+            "MS",
+        )
+        .optopt(
+            "",
+            "fail-after",
+            "Fail tests running for longer than MS milliseconds. They \
+             are not stopped, and fail once they finish. Only available \
+             with -Z unstable-options",
+            "MS",
+        )
+        .optflag(
+            "",
+            "isolate",
+            "Run every test in a separate process, so that crashes and \
+             changes to global state only affect that test. Only available \
+             with -Z unstable-options",
+        )
+        .optopt(
+            "",
+            "retries",
+            "Run failed tests again up to N times, reporting tests which \
+             pass on a retry as flaky. Only available with -Z unstable-options",
+            "N",
+        )
         .optflag(
             "",
             "exact",
@@ -441,8 +640,9 @@ fn optgroups() -> getopts::Options {
             "Configure formatting of output:
             pretty = Print verbose output;
             terse  = Display one character per test;
-            json   = Output a json document",
-            "pretty|terse|json",
+            json   = Output a json document;
+            junit  = Output a JUnit XML document",
+            "pretty|terse|json|junit",
         )
         .optopt(
             "Z",
@@ -498,6 +698,112 @@ fn is_nightly() -> bool {
     bootstrap || !disable_unstable_features
 }
 
+fn parse_millis(matches: &getopts::Matches, name: &str) -> Result<Option<Duration>, String> {
+    match matches.opt_str(name) {
+        Some(ms) => match ms.parse::<u64>() {
+            Ok(ms) => Ok(Some(Duration::from_millis(ms))),
+            Err(e) => Err(format!(
+                "argument for --{} must be a number of milliseconds (error: {})",
+                name, e
+            )),
+        },
+        None => Ok(None),
+    }
+}
+
+fn get_time_options(
+    matches: &getopts::Matches,
+    allow_unstable: bool,
+) -> Result<TestTimeOptions, String> {
+    let time_options = TestTimeOptions {
+        report_time: matches.opt_present("report-time"),
+        warn_time: parse_millis(matches, "warn-time")?,
+        fail_after: parse_millis(matches, "fail-after")?,
+    };
+
+    if !allow_unstable && time_options != TestTimeOptions::default() {
+        return Err(
+            "The \"report-time\", \"warn-time\" and \"fail-after\" options are only \
+             accepted on the nightly compiler"
+                .into(),
+        );
+    }
+
+    Ok(time_options)
+}
+
+fn parse_usize(matches: &getopts::Matches, name: &str) -> Result<Option<usize>, String> {
+    match matches.opt_str(name) {
+        Some(n) => match n.parse::<usize>() {
+            Ok(n) => Ok(Some(n)),
+            Err(e) => Err(format!("argument for --{} must be a number (error: {})", name, e)),
+        },
+        None => Ok(None),
+    }
+}
+
+fn get_shard(
+    matches: &getopts::Matches,
+    allow_unstable: bool,
+) -> Result<Option<(usize, usize)>, String> {
+    let shard = match (
+        parse_usize(matches, "shard-index")?,
+        parse_usize(matches, "shard-count")?,
+    ) {
+        (None, None) => return Ok(None),
+        (Some(_), Some(0)) => return Err("argument for --shard-count must not be 0".into()),
+        (Some(index), Some(count)) if index >= count => {
+            return Err(format!(
+                "argument for --shard-index must be less than --shard-count ({})",
+                count
+            ))
+        }
+        (Some(index), Some(count)) => (index, count),
+        _ => {
+            return Err("--shard-index and --shard-count must be used together".into());
+        }
+    };
+
+    if !allow_unstable {
+        return Err(
+            "The \"shard-index\" and \"shard-count\" options are only accepted on \
+             the nightly compiler"
+                .into(),
+        );
+    }
+
+    Ok(Some(shard))
+}
+
+fn get_shuffle_opts(
+    matches: &getopts::Matches,
+    allow_unstable: bool,
+) -> Result<(bool, Option<u64>), String> {
+    let shuffle_seed = match matches.opt_str("shuffle-seed") {
+        Some(seed) => match seed.parse::<u64>() {
+            Ok(seed) => Some(seed),
+            Err(e) => {
+                return Err(format!(
+                    "argument for --shuffle-seed must be a number (error: {})",
+                    e
+                ))
+            }
+        },
+        None => None,
+    };
+    let shuffle = matches.opt_present("shuffle") || shuffle_seed.is_some();
+
+    if shuffle && !allow_unstable {
+        return Err(
+            "The \"shuffle\" and \"shuffle-seed\" options are only accepted on the \
+             nightly compiler"
+                .into(),
+        );
+    }
+
+    Ok((shuffle, shuffle_seed))
+}
+
 // Parses command line arguments into test options
 pub fn parse_opts(args: &[String]) -> Option<OptRes> {
     let mut allow_unstable = false;
@@ -570,6 +876,48 @@ pub fn parse_opts(args: &[String]) -> Option<OptRes> {
         None => None,
     };
 
+    let shard = match get_shard(&matches, allow_unstable) {
+        Ok(shard) => shard,
+        Err(e) => return Some(Err(e)),
+    };
+
+    let (shuffle, shuffle_seed) = match get_shuffle_opts(&matches, allow_unstable) {
+        Ok(opts) => opts,
+        Err(e) => return Some(Err(e)),
+    };
+
+    let save_baseline = matches.opt_str("save-baseline");
+    let baseline = matches.opt_str("baseline");
+    if (save_baseline.is_some() || baseline.is_some()) && !allow_unstable {
+        return Some(Err(
+            "The \"save-baseline\" and \"baseline\" options are only accepted on the \
+             nightly compiler"
+                .into(),
+        ));
+    }
+
+    let time_options = match get_time_options(&matches, allow_unstable) {
+        Ok(time_options) => time_options,
+        Err(e) => return Some(Err(e)),
+    };
+
+    let isolate = matches.opt_present("isolate");
+    if isolate && !allow_unstable {
+        return Some(Err(
+            "The \"isolate\" option is only accepted on the nightly compiler".into(),
+        ));
+    }
+
+    let retries = match parse_usize(&matches, "retries") {
+        Ok(Some(_)) if !allow_unstable => {
+            return Some(Err(
+                "The \"retries\" option is only accepted on the nightly compiler".into(),
+            ))
+        }
+        Ok(retries) => retries.unwrap_or(0),
+        Err(e) => return Some(Err(e)),
+    };
+
     let color = match matches.opt_str("color").as_ref().map(|s| &**s) {
         Some("auto") | None => AutoColor,
         Some("always") => AlwaysColor,
@@ -596,10 +944,18 @@ pub fn parse_opts(args: &[String]) -> Option<OptRes> {
             }
             OutputFormat::Json
         }
+        Some("junit") => {
+            if !allow_unstable {
+                return Some(Err(
+                    "The \"junit\" format is only accepted on the nightly compiler".into(),
+                ));
+            }
+            OutputFormat::Junit
+        }
 
         Some(v) => {
             return Some(Err(format!(
-                "argument for --format must be pretty, terse, or json (was \
+                "argument for --format must be pretty, terse, json, or junit (was \
                  {})",
                 v
             )))
@@ -619,6 +975,14 @@ pub fn parse_opts(args: &[String]) -> Option<OptRes> {
         format,
         test_threads,
         skip: matches.opt_strs("skip"),
+        shard,
+        shuffle,
+        shuffle_seed,
+        save_baseline,
+        baseline,
+        time_options,
+        isolate,
+        retries,
         options: Options::new(),
     };
 
@@ -629,6 +993,8 @@ pub fn parse_opts(args: &[String]) -> Option<OptRes> {
 pub struct BenchSamples {
     ns_iter_summ: stats::Summary,
     mb_s: usize,
+    samples: Vec<f64>,
+    comparison: Option<BenchComparison>,
 }
 
 #[derive(Clone, PartialEq)]
@@ -638,11 +1004,29 @@ pub enum TestResult {
     TrFailedMsg(String),
     TrIgnored,
     TrAllowedFail,
+    /// Passed only after being retried, taking the given number of attempts.
+    TrFlaky(usize),
     TrBench(BenchSamples),
 }
 
 unsafe impl Send for TestResult {}
 
+/// The wall-clock time a test took to run.
+#[derive(Copy, Clone, Debug, PartialEq, Eq, PartialOrd, Ord)]
+pub struct TestExecTime(pub Duration);
+
+impl TestExecTime {
+    pub fn as_secs_f64(&self) -> f64 {
+        ns_from_dur(self.0) as f64 / 1_000_000_000.0
+    }
+}
+
+impl fmt::Display for TestExecTime {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{:.3}s", self.as_secs_f64())
+    }
+}
+
 enum OutputLocation<T> {
     Pretty(Box<term::StdoutTerminal>),
     Raw(T),
@@ -671,11 +1055,17 @@ struct ConsoleTestState {
     failed: usize,
     ignored: usize,
     allowed_fail: usize,
+    flaky: usize,
     filtered_out: usize,
     measured: usize,
     metrics: MetricMap,
     failures: Vec<(TestDesc, Vec<u8>)>,
     not_failures: Vec<(TestDesc, Vec<u8>)>,
+    slow_tests: Vec<(TestDesc, TestExecTime)>,
+    flaky_tests: Vec<(TestDesc, usize)>,
+    exec_time: Option<TestExecTime>,
+    baseline: Option<Baseline>,
+    new_baseline: Option<Baseline>,
     options: Options,
 }
 
@@ -686,6 +1076,16 @@ impl ConsoleTestState {
             None => None,
         };
 
+        let baseline = match opts.baseline {
+            Some(ref name) => Some(Baseline::load(name)?),
+            None => None,
+        };
+
+        let new_baseline = match opts.save_baseline {
+            Some(_) => Some(Baseline::new()),
+            None => None,
+        };
+
         Ok(ConsoleTestState {
             log_out,
             total: 0,
@@ -693,11 +1093,17 @@ impl ConsoleTestState {
             failed: 0,
             ignored: 0,
             allowed_fail: 0,
+            flaky: 0,
             filtered_out: 0,
             measured: 0,
             metrics: MetricMap::new(),
             failures: Vec::new(),
             not_failures: Vec::new(),
+            slow_tests: Vec::new(),
+            flaky_tests: Vec::new(),
+            exec_time: None,
+            baseline,
+            new_baseline,
             options: opts.options,
         })
     }
@@ -719,6 +1125,7 @@ impl ConsoleTestState {
                 TrFailedMsg(ref msg) => format!("failed: {}", msg),
                 TrIgnored => "ignored".to_owned(),
                 TrAllowedFail => "failed (allowed)".to_owned(),
+                TrFlaky(attempts) => format!("flaky (passed after {} attempts)", attempts),
                 TrBench(ref bs) => fmt_bench_samples(bs),
             },
             test.name
@@ -726,7 +1133,7 @@ impl ConsoleTestState {
     }
 
     fn current_test_count(&self) -> usize {
-        self.passed + self.failed + self.ignored + self.measured + self.allowed_fail
+        self.passed + self.failed + self.ignored + self.measured + self.allowed_fail + self.flaky
     }
 }
 
@@ -773,6 +1180,11 @@ pub fn fmt_bench_samples(bs: &BenchSamples) -> String {
             .write_fmt(format_args!(" = {} MB/s", bs.mb_s))
             .unwrap();
     }
+    if let Some(ref comparison) = bs.comparison {
+        output
+            .write_fmt(format_args!(" (baseline: {})", comparison))
+            .unwrap();
+    }
     output
 }
 
@@ -798,7 +1210,7 @@ pub fn list_tests_console(opts: &TestOpts, tests: Vec<TestDescAndFn>) -> io::Res
         } = test;
 
         let fntype = match testfn {
-            StaticTestFn(..) | DynTestFn(..) => {
+            StaticTestFn(..) | DynTestFn(..) | DynRetryableTestFn(..) => {
                 ntest += 1;
                 "test"
             }
@@ -839,20 +1251,32 @@ pub fn list_tests_console(opts: &TestOpts, tests: Vec<TestDescAndFn>) -> io::Res
 pub fn run_tests_console(opts: &TestOpts, tests: Vec<TestDescAndFn>) -> io::Result<bool> {
     fn callback(
         event: &TestEvent,
+        opts: &TestOpts,
         st: &mut ConsoleTestState,
         out: &mut OutputFormatter,
     ) -> io::Result<()> {
         match (*event).clone() {
-            TeFiltered(ref filtered_tests) => {
+            TeFiltered(ref filtered_tests, shuffle_seed) => {
                 st.total = filtered_tests.len();
-                out.write_run_start(filtered_tests.len())
+                out.write_run_start(filtered_tests.len(), shuffle_seed)
             }
             TeFilteredOut(filtered_out) => Ok(st.filtered_out = filtered_out),
             TeWait(ref test) => out.write_test_start(test),
             TeTimeout(ref test) => out.write_timeout(test),
-            TeResult(test, result, stdout) => {
+            TeResult(test, mut result, exec_time, stdout) => {
+                if let TrBench(ref mut bs) = result {
+                    if let Some(ref baseline) = st.baseline {
+                        let comparison = baseline.compare(test.name.as_slice(), bs);
+                        bs.comparison = comparison;
+                    }
+                }
                 st.write_log_result(&test, &result)?;
-                out.write_result(&test, &result, &*stdout)?;
+                out.write_result(&test, &result, exec_time.as_ref(), &*stdout)?;
+                if let Some(exec_time) = exec_time {
+                    if opts.time_options.is_slow(&exec_time) {
+                        st.slow_tests.push((test.clone(), exec_time));
+                    }
+                }
                 match result {
                     TrOk => {
                         st.passed += 1;
@@ -860,7 +1284,15 @@ pub fn run_tests_console(opts: &TestOpts, tests: Vec<TestDescAndFn>) -> io::Resu
                     }
                     TrIgnored => st.ignored += 1,
                     TrAllowedFail => st.allowed_fail += 1,
+                    TrFlaky(attempts) => {
+                        st.flaky += 1;
+                        st.flaky_tests.push((test.clone(), attempts));
+                        st.not_failures.push((test, stdout));
+                    }
                     TrBench(bs) => {
+                        if let Some(ref mut new_baseline) = st.new_baseline {
+                            new_baseline.insert(test.name.as_slice(), &bs.samples);
+                        }
                         st.metrics.insert_metric(
                             test.name.as_slice(),
                             bs.ns_iter_summ.median,
@@ -903,14 +1335,17 @@ pub fn run_tests_console(opts: &TestOpts, tests: Vec<TestDescAndFn>) -> io::Resu
             use_color(opts),
             max_name_len,
             is_multithreaded,
+            opts.time_options,
         )),
         OutputFormat::Terse => Box::new(TerseFormatter::new(
             output,
             use_color(opts),
             max_name_len,
             is_multithreaded,
+            opts.time_options,
         )),
-        OutputFormat::Json => Box::new(JsonFormatter::new(output)),
+        OutputFormat::Json => Box::new(JsonFormatter::new(output, opts.time_options)),
+        OutputFormat::Junit => Box::new(JunitFormatter::new(output)),
     };
     let mut st = ConsoleTestState::new(opts)?;
     fn len_if_padded(t: &TestDescAndFn) -> usize {
@@ -920,10 +1355,18 @@ pub fn run_tests_console(opts: &TestOpts, tests: Vec<TestDescAndFn>) -> io::Resu
         }
     }
 
-    run_tests(opts, tests, |x| callback(&x, &mut st, &mut *out))?;
+    let start_time = Instant::now();
+    run_tests(opts, tests, |x| callback(&x, opts, &mut st, &mut *out))?;
+    if opts.time_options.report_time {
+        st.exec_time = Some(TestExecTime(start_time.elapsed()));
+    }
 
     assert!(st.current_test_count() == st.total);
 
+    if let (&Some(ref name), &Some(ref new_baseline)) = (&opts.save_baseline, &st.new_baseline) {
+        new_baseline.save(name)?;
+    }
+
     return out.write_run_finish(&st);
 }
 
@@ -943,7 +1386,13 @@ fn should_sort_failures_before_printing_them() {
         allow_fail: false,
     };
 
-    let mut out = PrettyFormatter::new(Raw(Vec::new()), false, 10, false);
+    let mut out = PrettyFormatter::new(
+        Raw(Vec::new()),
+        false,
+        10,
+        false,
+        TestTimeOptions::default(),
+    );
 
     let st = ConsoleTestState {
         log_out: None,
@@ -952,12 +1401,18 @@ fn should_sort_failures_before_printing_them() {
         failed: 0,
         ignored: 0,
         allowed_fail: 0,
+        flaky: 0,
         filtered_out: 0,
         measured: 0,
         metrics: MetricMap::new(),
         failures: vec![(test_b, Vec::new()), (test_a, Vec::new())],
         options: Options::new(),
         not_failures: Vec::new(),
+        slow_tests: Vec::new(),
+        flaky_tests: Vec::new(),
+        exec_time: None,
+        baseline: None,
+        new_baseline: None,
     };
 
     out.write_failures(&st).unwrap();
@@ -971,6 +1426,124 @@ fn should_sort_failures_before_printing_them() {
     assert!(apos < bpos);
 }
 
+#[test]
+fn should_write_junit_testcases() {
+    fn desc(name: &'static str) -> TestDesc {
+        TestDesc {
+            name: StaticTestName(name),
+            ignore: false,
+            should_panic: ShouldPanic::No,
+            allow_fail: false,
+        }
+    }
+
+    let mut out = JunitFormatter::new(Raw(Vec::new()));
+
+    let exec_time = Some(TestExecTime(Duration::from_millis(1500)));
+    let results = vec![
+        (desc("a::ok"), TrOk, exec_time, Vec::new()),
+        (desc("a::b::failed"), TrFailed, exec_time, b"assertion <failed>".to_vec()),
+        (desc("ignored"), TrIgnored, None, Vec::new()),
+        (desc("a::case(b::C, 1)"), TrOk, exec_time, Vec::new()),
+    ];
+    for (desc, result, exec_time, stdout) in results {
+        out.write_test_start(&desc).unwrap();
+        out.write_result(&desc, &result, exec_time.as_ref(), &stdout).unwrap();
+    }
+
+    let st = ConsoleTestState {
+        log_out: None,
+        total: 4,
+        passed: 2,
+        failed: 1,
+        ignored: 1,
+        allowed_fail: 0,
+        flaky: 0,
+        filtered_out: 0,
+        measured: 0,
+        metrics: MetricMap::new(),
+        failures: Vec::new(),
+        options: Options::new(),
+        not_failures: Vec::new(),
+        slow_tests: Vec::new(),
+        flaky_tests: Vec::new(),
+        exec_time: None,
+        baseline: None,
+        new_baseline: None,
+    };
+
+    assert!(!out.write_run_finish(&st).unwrap());
+    let s = match out.output_location() {
+        &Raw(ref m) => String::from_utf8_lossy(&m[..]).into_owned(),
+        &Pretty(_) => unreachable!(),
+    };
+
+    assert!(s.contains(r#"failures="1" tests="4" skipped="1""#));
+    assert!(s.contains(r#"<testcase classname="a" name="ok" time="1.500">"#));
+    assert!(s.contains(r#"<testcase classname="a::b" name="failed""#));
+    assert!(s.contains("<system-out>assertion &lt;failed&gt;</system-out>"));
+    assert!(s.contains(r#"<testcase classname="crate" name="ignored""#));
+    assert!(s.contains(r#"<testcase classname="a" name="case(b::C, 1)""#));
+    assert!(s.contains("<skipped/>"));
+}
+
+#[test]
+fn terse_should_report_test_times() {
+    fn desc(name: &'static str) -> TestDesc {
+        TestDesc {
+            name: StaticTestName(name),
+            ignore: false,
+            should_panic: ShouldPanic::No,
+            allow_fail: false,
+        }
+    }
+
+    let mut time_options = TestTimeOptions::default();
+    time_options.report_time = true;
+    let mut out = TerseFormatter::new(Raw(Vec::new()), false, 10, false, time_options);
+
+    let results = vec![
+        (desc("fast"), TestExecTime(Duration::from_millis(10))),
+        (desc("slow"), TestExecTime(Duration::from_millis(1500))),
+    ];
+    for (desc, exec_time) in results {
+        out.write_test_start(&desc).unwrap();
+        out.write_result(&desc, &TrOk, Some(&exec_time), &[]).unwrap();
+    }
+
+    let st = ConsoleTestState {
+        log_out: None,
+        total: 2,
+        passed: 2,
+        failed: 0,
+        ignored: 0,
+        allowed_fail: 0,
+        flaky: 0,
+        filtered_out: 0,
+        measured: 0,
+        metrics: MetricMap::new(),
+        failures: Vec::new(),
+        options: Options::new(),
+        not_failures: Vec::new(),
+        slow_tests: Vec::new(),
+        flaky_tests: Vec::new(),
+        exec_time: None,
+        baseline: None,
+        new_baseline: None,
+    };
+
+    assert!(out.write_run_finish(&st).unwrap());
+    let s = match out.output_location() {
+        &Raw(ref m) => String::from_utf8_lossy(&m[..]).into_owned(),
+        &Pretty(_) => unreachable!(),
+    };
+
+    assert!(s.starts_with(".."));
+    let slow = s.find("    slow <1.500s>\n").unwrap();
+    let fast = s.find("    fast <0.010s>\n").unwrap();
+    assert!(slow < fast);
+}
+
 fn use_color(opts: &TestOpts) -> bool {
     match opts.color {
         AutoColor => !opts.nocapture && stdout_isatty(),
@@ -1009,14 +1582,14 @@ fn stdout_isatty() -> bool {
 
 #[derive(Clone)]
 pub enum TestEvent {
-    TeFiltered(Vec<TestDesc>),
+    TeFiltered(Vec<TestDesc>, Option<u64>),
     TeWait(TestDesc),
-    TeResult(TestDesc, TestResult, Vec<u8>),
+    TeResult(TestDesc, TestResult, Option<TestExecTime>, Vec<u8>),
     TeTimeout(TestDesc),
     TeFilteredOut(usize),
 }
 
-pub type MonitorMsg = (TestDesc, TestResult, Vec<u8>);
+pub type MonitorMsg = (TestDesc, TestResult, Option<TestExecTime>, Vec<u8>);
 
 struct Sink(Arc<Mutex<Vec<u8>>>);
 impl Write for Sink {
@@ -1054,13 +1627,23 @@ where
     let filtered_out = tests_len - filtered_tests.len();
     callback(TeFilteredOut(filtered_out))?;
 
+    let shuffle_seed = get_shuffle_seed(opts);
+    let filtered_tests = match shuffle_seed {
+        Some(seed) => {
+            let mut filtered_tests = filtered_tests;
+            shuffle_tests(seed, &mut filtered_tests);
+            filtered_tests
+        }
+        None => filtered_tests,
+    };
+
     let filtered_descs = filtered_tests.iter().map(|t| t.desc.clone()).collect();
 
-    callback(TeFiltered(filtered_descs))?;
+    callback(TeFiltered(filtered_descs, shuffle_seed))?;
 
     let (filtered_tests, filtered_benchs): (Vec<_>, _) =
         filtered_tests.into_iter().partition(|e| match e.testfn {
-            StaticTestFn(_) | DynTestFn(_) => true,
+            StaticTestFn(_) | DynTestFn(_) | DynRetryableTestFn(_) => true,
             _ => false,
         });
 
@@ -1108,8 +1691,8 @@ where
             let test = remaining.pop().unwrap();
             callback(TeWait(test.desc.clone()))?;
             run_test(opts, !opts.run_tests, test, tx.clone());
-            let (test, result, stdout) = rx.recv().unwrap();
-            callback(TeResult(test, result, stdout))?;
+            let (test, result, exec_time, stdout) = rx.recv().unwrap();
+            callback(TeResult(test, result, exec_time, stdout))?;
         }
     } else {
         while pending > 0 || !remaining.is_empty() {
@@ -1138,10 +1721,10 @@ where
                 }
             }
 
-            let (desc, result, stdout) = res.unwrap();
+            let (desc, result, exec_time, stdout) = res.unwrap();
             running_tests.remove(&desc);
 
-            callback(TeResult(desc, result, stdout))?;
+            callback(TeResult(desc, result, exec_time, stdout))?;
             pending -= 1;
         }
     }
@@ -1151,8 +1734,8 @@ where
         for b in filtered_benchs {
             callback(TeWait(b.desc.clone()))?;
             run_test(opts, false, b, tx.clone());
-            let (test, result, stdout) = rx.recv().unwrap();
-            callback(TeResult(test, result, stdout))?;
+            let (test, result, exec_time, stdout) = rx.recv().unwrap();
+            callback(TeResult(test, result, exec_time, stdout))?;
         }
     }
     Ok(())
@@ -1339,12 +1922,72 @@ pub fn filter_tests(opts: &TestOpts, tests: Vec<TestDescAndFn>) -> Vec<TestDescA
         filtered.into_iter().filter_map(filter).collect()
     };
 
-    // Sort the tests alphabetically
-    filtered.sort_by(|t1, t2| t1.desc.name.as_slice().cmp(t2.desc.name.as_slice()));
+    // Only keep this shard's tests. Assigning them round-robin by name keeps
+    // shards deterministic and roughly equal in size, even when the caller
+    // orders the tests differently from one run to the next.
+    if let Some((index, count)) = opts.shard {
+        let mut by_name: Vec<usize> = (0..filtered.len()).collect();
+        by_name.sort_by(|&a, &b| {
+            filtered[a].desc.name.as_slice().cmp(filtered[b].desc.name.as_slice())
+        });
+        let mut keep = vec![false; filtered.len()];
+        for (i, &test) in by_name.iter().enumerate() {
+            keep[test] = i % count == index;
+        }
+        filtered = filtered
+            .into_iter()
+            .zip(keep)
+            .filter(|&(_, keep)| keep)
+            .map(|(test, _)| test)
+            .collect();
+    }
+
+    // Sort the tests alphabetically, unless the caller already ordered them
+    if !opts.options.preserve_order {
+        filtered.sort_by(|t1, t2| t1.desc.name.as_slice().cmp(t2.desc.name.as_slice()));
+    }
 
     filtered
 }
 
+/// Returns the seed to shuffle the tests with, picking a new one if
+/// `--shuffle` was passed without an explicit `--shuffle-seed`.
+fn get_shuffle_seed(opts: &TestOpts) -> Option<u64> {
+    opts.shuffle_seed.or_else(|| {
+        if opts.shuffle {
+            let now = SystemTime::now()
+                .duration_since(UNIX_EPOCH)
+                .expect("system time is before the UNIX epoch");
+            Some(ns_from_dur(now))
+        } else {
+            None
+        }
+    })
+}
+
+fn calculate_hash<T: Hash>(t: &T) -> u64 {
+    let mut s = DefaultHasher::new();
+    t.hash(&mut s);
+    s.finish()
+}
+
+/// Shuffles the tests with a Fisher-Yates shuffle driven by `DefaultHasher`,
+/// which is unkeyed and hence gives the same order for the same seed and the
+/// same set of tests on every run.
+fn shuffle_tests(shuffle_seed: u64, tests: &mut [TestDescAndFn]) {
+    let names_hash = {
+        let names: Vec<&str> = tests.iter().map(|t| t.desc.name.as_slice()).collect();
+        calculate_hash(&names)
+    };
+
+    let mut state = shuffle_seed;
+    for i in 0..tests.len() {
+        state = calculate_hash(&(state, names_hash));
+        let j = i + (state % (tests.len() - i) as u64) as usize;
+        tests.swap(i, j);
+    }
+}
+
 pub fn convert_benchmarks_to_tests(tests: Vec<TestDescAndFn>) -> Vec<TestDescAndFn> {
     // convert benchmarks to tests, if we're not benchmarking them
     tests
@@ -1379,7 +2022,7 @@ pub fn run_test(
         && desc.should_panic != ShouldPanic::No;
 
     if force_ignore || desc.ignore || ignore_because_panic_abort {
-        monitor_ch.send((desc, TrIgnored, Vec::new())).unwrap();
+        monitor_ch.send((desc, TrIgnored, None, Vec::new())).unwrap();
         return;
     }
 
@@ -1387,7 +2030,8 @@ pub fn run_test(
         desc: TestDesc,
         monitor_ch: Sender<MonitorMsg>,
         nocapture: bool,
-        testfn: Box<FnBox() + Send>,
+        time_options: TestTimeOptions,
+        attempts: Attempts,
     ) {
         // Buffer for capturing standard I/O
         let data = Arc::new(Mutex::new(Vec::new()));
@@ -1404,17 +2048,45 @@ pub fn run_test(
                 None
             };
 
-            let result = catch_unwind(AssertUnwindSafe(testfn));
+            let attempt_result = |start: Instant, result: Result<(), Box<Any + Send>>| {
+                let exec_time = TestExecTime(start.elapsed());
+
+                let test_result = calc_result(&desc, result);
+                let test_result =
+                    calc_timed_result(&desc, test_result, &exec_time, &time_options);
+                (test_result, exec_time)
+            };
+
+            let (test_result, exec_time, attempts) = match attempts {
+                Attempts::Once(testfn) => {
+                    let start = Instant::now();
+                    let result = catch_unwind(AssertUnwindSafe(testfn));
+                    let (test_result, exec_time) = attempt_result(start, result);
+                    (test_result, exec_time, 1)
+                }
+                Attempts::UpTo(mut testfn, retries) => {
+                    let mut attempts = 0;
+                    loop {
+                        let start = Instant::now();
+                        let result = catch_unwind(AssertUnwindSafe(|| testfn()));
+                        let (test_result, exec_time) = attempt_result(start, result);
+                        attempts += 1;
+                        if !is_failure(&test_result) || attempts > retries {
+                            break (test_result, exec_time, attempts);
+                        }
+                    }
+                }
+            };
+            let test_result = calc_flaky_result(test_result, attempts);
 
             if let Some((printio, panicio)) = oldio {
                 io::set_print(printio);
                 io::set_panic(panicio);
             };
 
-            let test_result = calc_result(&desc, result);
             let stdout = data.lock().unwrap().to_vec();
             monitor_ch
-                .send((desc.clone(), test_result, stdout))
+                .send((desc.clone(), test_result, Some(exec_time), stdout))
                 .unwrap();
         };
 
@@ -1430,6 +2102,15 @@ pub fn run_test(
         }
     }
 
+    let is_bench = match testfn {
+        DynBenchFn(..) | StaticBenchFn(..) => true,
+        DynTestFn(..) | StaticTestFn(..) | DynRetryableTestFn(..) => false,
+    };
+    if opts.isolate && !is_bench {
+        run_test_in_process(desc, monitor_ch, opts.nocapture, opts.time_options, opts.retries);
+        return;
+    }
+
     match testfn {
         DynBenchFn(bencher) => {
             ::bench::benchmark(desc, monitor_ch, opts.nocapture, |harness| {
@@ -1443,17 +2124,190 @@ pub fn run_test(
         }
         DynTestFn(f) => {
             let cb = move || __rust_begin_short_backtrace(f);
-            run_test_inner(desc, monitor_ch, opts.nocapture, Box::new(cb))
+            let attempts = Attempts::Once(Box::new(cb));
+            run_test_inner(desc, monitor_ch, opts.nocapture, opts.time_options, attempts)
+        }
+        DynRetryableTestFn(mut f) => {
+            let cb = move || __rust_begin_short_backtrace(&mut *f);
+            let attempts = Attempts::UpTo(Box::new(cb), opts.retries);
+            run_test_inner(desc, monitor_ch, opts.nocapture, opts.time_options, attempts)
+        }
+        StaticTestFn(f) => {
+            let cb = move || __rust_begin_short_backtrace(f);
+            let attempts = Attempts::UpTo(Box::new(cb), opts.retries);
+            run_test_inner(desc, monitor_ch, opts.nocapture, opts.time_options, attempts)
+        }
+    }
+}
+
+/// How `run_test` runs a test: once, or again while it fails, up to a number
+/// of times.
+enum Attempts {
+    Once(Box<FnBox() + Send>),
+    UpTo(Box<FnMut() + Send>, usize),
+}
+
+/// Environment variable naming the test a test binary re-invoked by
+/// `--isolate` should run.
+const ISOLATED_TEST_VAR: &'static str = "__RUST_TEST_ISOLATED";
+
+/// Starts the line on which an isolated test process prints the result of
+/// its test, at the end of its standard output. A process which ends without
+/// printing it, like with `process::exit(0)` from the test, failed.
+const ISOLATED_RESULT_MARKER: &'static str = "\n__RUST_TEST_ISOLATED_RESULT ";
+
+/// Runs the test in a new instance of the current executable, reporting
+/// crashes as failures of this test and capturing the output of the whole
+/// process.
+fn run_test_in_process(
+    desc: TestDesc,
+    monitor_ch: Sender<MonitorMsg>,
+    nocapture: bool,
+    time_options: TestTimeOptions,
+    retries: usize,
+) {
+    let name = desc.name.clone();
+    let runtest = move || {
+        let mut stdout = Vec::new();
+        // the closure borrows `stdout` until the end of this block
+        let (test_result, exec_time) = {
+            let mut attempt = || {
+                let start = Instant::now();
+                let output = env::current_exe().and_then(|exe| {
+                    let mut command = process::Command::new(exe);
+                    // Harnesses which parse their own command line before
+                    // calling `test_main` get the same one as this process.
+                    command.args(env::args_os().skip(1));
+                    command.env(ISOLATED_TEST_VAR, desc.name.as_slice());
+                    if nocapture {
+                        // The standard output is still read, to take the
+                        // result out of it, and printed once the test is over.
+                        command.stderr(process::Stdio::inherit());
+                    }
+                    command.output()
+                });
+                let exec_time = TestExecTime(start.elapsed());
+
+                let test_result = match output {
+                    Ok(output) => {
+                        let mut output_stdout = output.stdout;
+                        let result = calc_isolated_result(&desc, output.status, &mut output_stdout);
+                        if nocapture {
+                            let _ = io::stdout().write_all(&output_stdout);
+                        } else {
+                            stdout.extend(output_stdout);
+                            stdout.extend(output.stderr);
+                        }
+                        result
+                    }
+                    Err(e) => TrFailedMsg(format!("failed to spawn test process: {}", e)),
+                };
+                let test_result = calc_timed_result(&desc, test_result, &exec_time, &time_options);
+                (test_result, exec_time)
+            };
+
+            let (mut test_result, mut exec_time) = attempt();
+            let mut attempts = 1;
+            while is_failure(&test_result) && attempts <= retries {
+                let (result, time) = attempt();
+                test_result = result;
+                exec_time = time;
+                attempts += 1;
+            }
+            (calc_flaky_result(test_result, attempts), exec_time)
+        };
+        monitor_ch
+            .send((desc.clone(), test_result, Some(exec_time), stdout))
+            .unwrap();
+    };
+
+    let cfg = thread::Builder::new().name(name.as_slice().to_owned());
+    cfg.spawn(runtest).unwrap();
+}
+
+/// Takes the result that an isolated test process printed out of its
+/// standard output `stdout`.
+fn calc_isolated_result(
+    desc: &TestDesc,
+    status: process::ExitStatus,
+    stdout: &mut Vec<u8>,
+) -> TestResult {
+    let marker = ISOLATED_RESULT_MARKER.as_bytes();
+    let position = stdout.windows(marker.len()).rposition(|window| window == marker);
+    let result = match position {
+        Some(position) if status.success() => {
+            let reported = String::from_utf8_lossy(&stdout[position + marker.len()..])
+                .trim_right()
+                .to_string();
+            stdout.truncate(position);
+            match &reported[..] {
+                "ok" => return TrOk,
+                "allowed_fail" => return TrAllowedFail,
+                "failed" => TrFailed,
+                _ if reported.starts_with("failed ") => {
+                    TrFailedMsg(reported["failed ".len()..].to_string())
+                }
+                _ => TrFailedMsg(format!("test process reported `{}`", reported)),
+            }
         }
-        StaticTestFn(f) => run_test_inner(
-            desc,
-            monitor_ch,
-            opts.nocapture,
-            Box::new(move || __rust_begin_short_backtrace(f)),
-        ),
+        _ => TrFailedMsg(format!(
+            "test process exited without a result ({}); either the test ended the process, \
+             or the test harness does not call `test_main` when run again by --isolate",
+            status
+        )),
+    };
+
+    if desc.allow_fail {
+        TrAllowedFail
+    } else {
+        result
     }
 }
 
+/// The entry point of a test binary re-invoked by `--isolate`: runs the test
+/// `name` and prints its result at the end of the standard output.
+fn run_isolated_test(name: &str, tests: Vec<TestDescAndFn>) -> ! {
+    // Processes started by the test are not isolated tests themselves.
+    env::remove_var(ISOLATED_TEST_VAR);
+
+    let test = match tests.into_iter().find(|t| t.desc.name.as_slice() == name) {
+        Some(test) => test,
+        None => {
+            eprintln!("error: no test named `{}`", name);
+            process::exit(101);
+        }
+    };
+
+    let TestDescAndFn { desc, testfn } = test;
+    let testfn: Box<FnBox() + Send> = match testfn {
+        StaticTestFn(f) => Box::new(move || __rust_begin_short_backtrace(f)),
+        DynTestFn(f) => Box::new(move || __rust_begin_short_backtrace(f)),
+        DynRetryableTestFn(mut f) => Box::new(move || __rust_begin_short_backtrace(&mut *f)),
+        StaticBenchFn(..) | DynBenchFn(..) => {
+            eprintln!("error: benchmarks cannot be run in isolation");
+            process::exit(101);
+        }
+    };
+
+    // Run the test on a thread named after it, like the in-process runner.
+    let result = thread::Builder::new()
+        .name(name.to_owned())
+        .spawn(move || testfn())
+        .unwrap()
+        .join();
+    let reported = match calc_result(&desc, result) {
+        TrOk => "ok".to_string(),
+        TrAllowedFail => "allowed_fail".to_string(),
+        TrFailedMsg(msg) => format!("failed {}", msg.replace('\n', " ")),
+        _ => "failed".to_string(),
+    };
+    let stdout = io::stdout();
+    let mut stdout = stdout.lock();
+    let _ = write!(stdout, "{}{}\n", ISOLATED_RESULT_MARKER, reported);
+    let _ = stdout.flush();
+    process::exit(0)
+}
+
 /// Fixed frame used to clean the backtrace with `RUST_BACKTRACE=1`.
 #[inline(never)]
 fn __rust_begin_short_backtrace<F: FnOnce()>(f: F) {
@@ -1484,6 +2338,43 @@ fn calc_result(desc: &TestDesc, task_result: Result<(), Box<Any + Send>>) -> Tes
     }
 }
 
+fn is_failure(result: &TestResult) -> bool {
+    match *result {
+        TrFailed | TrFailedMsg(_) => true,
+        _ => false,
+    }
+}
+
+/// Marks tests which passed, but only after being retried, as flaky.
+fn calc_flaky_result(result: TestResult, attempts: usize) -> TestResult {
+    if result == TrOk && attempts > 1 {
+        TrFlaky(attempts)
+    } else {
+        result
+    }
+}
+
+fn calc_timed_result(
+    desc: &TestDesc,
+    result: TestResult,
+    exec_time: &TestExecTime,
+    time_options: &TestTimeOptions,
+) -> TestResult {
+    if result != TrOk || !time_options.is_too_slow(exec_time) {
+        return result;
+    }
+
+    if desc.allow_fail {
+        TrAllowedFail
+    } else {
+        TrFailedMsg(format!(
+            "test took {}, exceeding the limit of {}",
+            exec_time,
+            TestExecTime(time_options.fail_after.unwrap())
+        ))
+    }
+}
+
 #[derive(Clone, PartialEq)]
 pub struct MetricMap(BTreeMap<String, Metric>);
 
@@ -1550,7 +2441,9 @@ impl Bencher {
             return;
         }
 
-        self.summary = Some(iter(&mut inner));
+        let (summary, samples) = iter_samples(&mut inner);
+        self.summary = Some(summary);
+        self.samples = samples;
     }
 
     pub fn bench<F>(&mut self, mut f: F) -> Option<stats::Summary>
@@ -1578,6 +2471,15 @@ where
 }
 
 pub fn iter<T, F>(inner: &mut F) -> stats::Summary
+where
+    F: FnMut() -> T,
+{
+    iter_samples(inner).0
+}
+
+/// Like `iter`, but also returns the samples the summary was computed from,
+/// in nanoseconds per iteration.
+fn iter_samples<T, F>(inner: &mut F) -> (stats::Summary, Vec<f64>)
 where
     F: FnMut() -> T,
 {
@@ -1623,13 +2525,13 @@ where
         if loop_run > Duration::from_millis(100) && summ.median_abs_dev_pct < 1.0
             && summ.median - summ5.median < summ5.median_abs_dev
         {
-            return summ5;
+            return (summ5, samples.to_vec());
         }
 
         total_run = total_run + loop_run;
         // Longest we ever run for is 3s.
         if total_run > Duration::from_secs(3) {
-            return summ5;
+            return (summ5, samples.to_vec());
         }
 
         // If we overflow here just return the results so far. We check a
@@ -1639,7 +2541,7 @@ where
         n = match n.checked_mul(10) {
             Some(_) => n * 2,
             None => {
-                return summ5;
+                return (summ5, samples.to_vec());
             }
         };
     }
@@ -1649,6 +2551,7 @@ pub mod bench {
     use std::panic::{catch_unwind, AssertUnwindSafe};
     use std::cmp;
     use std::io;
+    use std::mem;
     use std::sync::{Arc, Mutex};
     use stats;
     use super::{BenchMode, BenchSamples, Bencher, MonitorMsg, Sender, Sink, TestDesc, TestResult};
@@ -1660,6 +2563,7 @@ pub mod bench {
         let mut bs = Bencher {
             mode: BenchMode::Auto,
             summary: None,
+            samples: Vec::new(),
             bytes: 0,
         };
 
@@ -1682,6 +2586,7 @@ pub mod bench {
             io::set_panic(panicio);
         };
 
+        let samples = mem::replace(&mut bs.samples, Vec::new());
         let test_result = match result {
             //bs.bench(f) {
             Ok(Some(ns_iter_summ)) => {
@@ -1691,6 +2596,8 @@ pub mod bench {
                 let bs = BenchSamples {
                     ns_iter_summ,
                     mb_s: mb_s as usize,
+                    samples,
+                    comparison: None,
                 };
                 TestResult::TrBench(bs)
             }
@@ -1701,6 +2608,8 @@ pub mod bench {
                 let bs = BenchSamples {
                     ns_iter_summ: stats::Summary::new(samples),
                     mb_s: 0,
+                    samples: Vec::new(),
+                    comparison: None,
                 };
                 TestResult::TrBench(bs)
             }
@@ -1708,7 +2617,7 @@ pub mod bench {
         };
 
         let stdout = data.lock().unwrap().to_vec();
-        monitor_ch.send((desc, test_result, stdout)).unwrap();
+        monitor_ch.send((desc, test_result, None, stdout)).unwrap();
     }
 
     pub fn run_once<F>(f: F)
@@ -1718,6 +2627,7 @@ pub mod bench {
         let mut bs = Bencher {
             mode: BenchMode::Single,
             summary: None,
+            samples: Vec::new(),
             bytes: 0,
         };
         bs.bench(f);
@@ -1727,10 +2637,13 @@ pub mod bench {
 #[cfg(test)]
 mod tests {
     use test::{filter_tests, parse_opts, run_test, DynTestFn, DynTestName, MetricMap, ShouldPanic,
-               StaticTestName, TestDesc, TestDescAndFn, TestOpts, TrFailed, TrFailedMsg,
-               TrIgnored, TrOk};
+               StaticTestName, TestDesc, TestDescAndFn, TestOpts, TestTimeOptions, TrFailed,
+               TrFailedMsg, TrIgnored, TrOk};
     use std::sync::mpsc::channel;
+    use std::thread;
+    use std::time::Duration;
     use bench;
+    use shuffle_tests;
     use Bencher;
 
     #[test]
@@ -1749,7 +2662,7 @@ mod tests {
         };
         let (tx, rx) = channel();
         run_test(&TestOpts::new(), false, desc, tx);
-        let (_, res, _) = rx.recv().unwrap();
+        let (_, res, _, _) = rx.recv().unwrap();
         assert!(res != TrOk);
     }
 
@@ -1767,7 +2680,7 @@ mod tests {
         };
         let (tx, rx) = channel();
         run_test(&TestOpts::new(), false, desc, tx);
-        let (_, res, _) = rx.recv().unwrap();
+        let (_, res, _, _) = rx.recv().unwrap();
         assert!(res == TrIgnored);
     }
 
@@ -1787,7 +2700,7 @@ mod tests {
         };
         let (tx, rx) = channel();
         run_test(&TestOpts::new(), false, desc, tx);
-        let (_, res, _) = rx.recv().unwrap();
+        let (_, res, _, _) = rx.recv().unwrap();
         assert!(res == TrOk);
     }
 
@@ -1807,7 +2720,7 @@ mod tests {
         };
         let (tx, rx) = channel();
         run_test(&TestOpts::new(), false, desc, tx);
-        let (_, res, _) = rx.recv().unwrap();
+        let (_, res, _, _) = rx.recv().unwrap();
         assert!(res == TrOk);
     }
 
@@ -1829,7 +2742,7 @@ mod tests {
         };
         let (tx, rx) = channel();
         run_test(&TestOpts::new(), false, desc, tx);
-        let (_, res, _) = rx.recv().unwrap();
+        let (_, res, _, _) = rx.recv().unwrap();
         assert!(res == TrFailedMsg(format!("{} '{}'", failed_msg, expected)));
     }
 
@@ -1847,10 +2760,127 @@ mod tests {
         };
         let (tx, rx) = channel();
         run_test(&TestOpts::new(), false, desc, tx);
-        let (_, res, _) = rx.recv().unwrap();
+        let (_, res, _, _) = rx.recv().unwrap();
         assert!(res == TrFailed);
     }
 
+    #[test]
+    fn test_exec_time_is_reported() {
+        fn f() {}
+        let desc = TestDescAndFn {
+            desc: TestDesc {
+                name: StaticTestName("whatever"),
+                ignore: false,
+                should_panic: ShouldPanic::No,
+                allow_fail: false,
+            },
+            testfn: DynTestFn(Box::new(f)),
+        };
+        let (tx, rx) = channel();
+        run_test(&TestOpts::new(), false, desc, tx);
+        let (_, res, exec_time, _) = rx.recv().unwrap();
+        assert!(res == TrOk);
+        assert!(exec_time.is_some());
+    }
+
+    #[test]
+    fn test_fail_after() {
+        fn f() {
+            thread::sleep(Duration::from_millis(50));
+        }
+        let desc = TestDescAndFn {
+            desc: TestDesc {
+                name: StaticTestName("whatever"),
+                ignore: false,
+                should_panic: ShouldPanic::No,
+                allow_fail: false,
+            },
+            testfn: DynTestFn(Box::new(f)),
+        };
+        let opts = TestOpts {
+            time_options: TestTimeOptions {
+                fail_after: Some(Duration::from_millis(10)),
+                ..TestTimeOptions::default()
+            },
+            ..TestOpts::new()
+        };
+        let (tx, rx) = channel();
+        run_test(&opts, false, desc, tx);
+        let (_, res, _, _) = rx.recv().unwrap();
+        match res {
+            TrFailedMsg(_) => {}
+            _ => panic!("test exceeding --fail-after should fail"),
+        }
+    }
+
+    #[test]
+    fn parse_time_options() {
+        let args = vec![
+            "progname".to_string(),
+            "--report-time".to_string(),
+            "--warn-time".to_string(),
+            "100".to_string(),
+            "--fail-after".to_string(),
+            "1000".to_string(),
+        ];
+        match parse_opts(&args) {
+            Some(Err(_)) => {}
+            _ => panic!("time options should require -Z unstable-options"),
+        }
+
+        let mut args = args;
+        args.push("-Zunstable-options".to_string());
+        let opts = match parse_opts(&args) {
+            Some(Ok(o)) => o,
+            _ => panic!("Malformed arg in parse_time_options"),
+        };
+        assert!(opts.time_options.report_time);
+        assert_eq!(opts.time_options.warn_time, Some(Duration::from_millis(100)));
+        assert_eq!(opts.time_options.fail_after, Some(Duration::from_millis(1000)));
+    }
+
+    #[test]
+    fn test_fixture_teardown_on_panic() {
+        use std::panic::catch_unwind;
+        use std::sync::atomic::{AtomicUsize, Ordering, ATOMIC_USIZE_INIT};
+        use {with_fixture, with_setup, with_teardown, TestFixture};
+
+        static SETUPS: AtomicUsize = ATOMIC_USIZE_INIT;
+        static TEARDOWNS: AtomicUsize = ATOMIC_USIZE_INIT;
+
+        struct Counted(usize);
+        impl TestFixture for Counted {
+            fn setup() -> Counted {
+                Counted(SETUPS.fetch_add(1, Ordering::SeqCst))
+            }
+            fn teardown(self) {
+                TEARDOWNS.fetch_add(1, Ordering::SeqCst);
+            }
+        }
+
+        fn setup() {
+            SETUPS.fetch_add(1, Ordering::SeqCst);
+        }
+        fn teardown() {
+            TEARDOWNS.fetch_add(1, Ordering::SeqCst);
+        }
+
+        let res = catch_unwind(|| {
+            with_setup(setup, || {
+                with_teardown(teardown, || {
+                    with_fixture(|_: &mut Counted| -> () { panic!("test failed") })
+                })
+            })
+        });
+        assert!(res.is_err());
+        assert_eq!(SETUPS.load(Ordering::SeqCst), 2);
+        assert_eq!(TEARDOWNS.load(Ordering::SeqCst), 2);
+
+        let value = with_fixture(|c: &mut Counted| c.0);
+        assert_eq!(value, 2);
+        assert_eq!(TEARDOWNS.load(Ordering::SeqCst), 3);
+    }
+
     #[test]
     fn parse_ignored_flag() {
         let args = vec![
@@ -2047,6 +3077,262 @@ mod tests {
         }
     }
 
+    fn named_tests(count: usize) -> Vec<TestDescAndFn> {
+        (0..count)
+            .map(|i| TestDescAndFn {
+                desc: TestDesc {
+                    name: DynTestName(format!("test_{:02}", i)),
+                    ignore: false,
+                    should_panic: ShouldPanic::No,
+                    allow_fail: false,
+                },
+                testfn: DynTestFn(Box::new(move || {})),
+            })
+            .collect()
+    }
+
+    #[test]
+    pub fn shards_partition_tests() {
+        let mut seen = Vec::new();
+        for index in 0..3 {
+            let opts = TestOpts {
+                shard: Some((index, 3)),
+                ..TestOpts::new()
+            };
+            let shard = filter_tests(&opts, named_tests(10));
+            assert!(shard.len() == 3 || shard.len() == 4);
+            seen.extend(shard.into_iter().map(|t| t.desc.name.to_string()));
+        }
+        seen.sort();
+        let all: Vec<_> = named_tests(10)
+            .into_iter()
+            .map(|t| t.desc.name.to_string())
+            .collect();
+        assert_eq!(seen, all);
+    }
+
+    #[test]
+    pub fn shards_do_not_depend_on_preserved_order() {
+        for index in 0..3 {
+            let opts = TestOpts {
+                shard: Some((index, 3)),
+                ..TestOpts::new()
+            };
+            let sorted: Vec<_> = filter_tests(&opts, named_tests(10))
+                .into_iter()
+                .map(|t| t.desc.name.to_string())
+                .collect();
+
+            let mut opts = opts;
+            opts.options = opts.options.preserve_order(true);
+            let mut reversed = named_tests(10);
+            reversed.reverse();
+            let mut preserved: Vec<_> = filter_tests(&opts, reversed)
+                .into_iter()
+                .map(|t| t.desc.name.to_string())
+                .collect();
+            let mut expected = sorted.clone();
+            expected.reverse();
+            assert_eq!(preserved, expected);
+
+            preserved.sort();
+            assert_eq!(preserved, sorted);
+        }
+    }
+
+    #[test]
+    pub fn shuffle_is_deterministic() {
+        fn shuffled_names(seed: u64) -> Vec<String> {
+            let mut tests = named_tests(20);
+            shuffle_tests(seed, &mut tests);
+            tests.into_iter().map(|t| t.desc.name.to_string()).collect()
+        }
+
+        let sorted: Vec<_> = named_tests(20)
+            .into_iter()
+            .map(|t| t.desc.name.to_string())
+            .collect();
+        let shuffled = shuffled_names(42);
+        assert_eq!(shuffled, shuffled_names(42));
+        assert!(shuffled != sorted);
+
+        let mut resorted = shuffled;
+        resorted.sort();
+        assert_eq!(resorted, sorted);
+    }
+
+    #[test]
+    fn parse_shard_and_shuffle_options() {
+        let args = |extra: &[&str]| {
+            let mut args = vec!["progname".to_string(), "-Zunstable-options".to_string()];
+            args.extend(extra.iter().map(|s| s.to_string()));
+            args
+        };
+
+        let opts = match parse_opts(&args(&["--shard-index", "2", "--shard-count", "8"])) {
+            Some(Ok(o)) => o,
+            _ => panic!("Malformed arg in parse_shard_and_shuffle_options"),
+        };
+        assert_eq!(opts.shard, Some((2, 8)));
+        assert!(!opts.shuffle);
+
+        let opts = match parse_opts(&args(&["--shuffle-seed", "1234"])) {
+            Some(Ok(o)) => o,
+            _ => panic!("Malformed arg in parse_shard_and_shuffle_options"),
+        };
+        assert!(opts.shuffle);
+        assert_eq!(opts.shuffle_seed, Some(1234));
+
+        for bad in &[
+            &["--shard-index", "8", "--shard-count", "8"][..],
+            &["--shard-index", "0", "--shard-count", "0"][..],
+            &["--shard-count", "8"][..],
+        ] {
+            match parse_opts(&args(bad)) {
+                Some(Err(_)) => {}
+                _ => panic!("invalid shard options should be rejected"),
+            }
+        }
+    }
+
+    #[test]
+    fn retried_tests_are_flaky() {
+        use std::sync::atomic::{AtomicUsize, Ordering, ATOMIC_USIZE_INIT};
+        use {StaticTestFn, TrFlaky};
+
+        static RUNS: AtomicUsize = ATOMIC_USIZE_INIT;
+        fn f() {
+            if RUNS.fetch_add(1, Ordering::SeqCst) < 2 {
+                panic!("not yet");
+            }
+        }
+        let desc = || TestDescAndFn {
+            desc: TestDesc {
+                name: StaticTestName("whatever"),
+                ignore: false,
+                should_panic: ShouldPanic::No,
+                allow_fail: false,
+            },
+            testfn: StaticTestFn(f),
+        };
+
+        let mut opts = TestOpts::new();
+        opts.retries = 1;
+        let (tx, rx) = channel();
+        run_test(&opts, false, desc(), tx);
+        let (_, res, _, _) = rx.recv().unwrap();
+        assert!(res == TrFailed);
+
+        RUNS.store(0, Ordering::SeqCst);
+        opts.retries = 5;
+        let (tx, rx) = channel();
+        run_test(&opts, false, desc(), tx);
+        let (_, res, _, _) = rx.recv().unwrap();
+        assert!(res == TrFlaky(3));
+    }
+
+    #[test]
+    fn only_retryable_dyn_tests_are_retried() {
+        use std::sync::Arc;
+        use std::sync::atomic::{AtomicUsize, Ordering};
+        use {DynRetryableTestFn, TrFlaky};
+
+        let desc = |testfn| TestDescAndFn {
+            desc: TestDesc {
+                name: StaticTestName("whatever"),
+                ignore: false,
+                should_panic: ShouldPanic::No,
+                allow_fail: false,
+            },
+            testfn,
+        };
+        let mut opts = TestOpts::new();
+        opts.retries = 5;
+
+        let runs = Arc::new(AtomicUsize::new(0));
+        let runs2 = runs.clone();
+        let (tx, rx) = channel();
+        run_test(&opts, false, desc(DynTestFn(Box::new(move || {
+            runs2.fetch_add(1, Ordering::SeqCst);
+            panic!("not yet");
+        }))), tx);
+        let (_, res, _, _) = rx.recv().unwrap();
+        assert!(res == TrFailed);
+        assert_eq!(runs.load(Ordering::SeqCst), 1);
+
+        let runs = AtomicUsize::new(0);
+        let (tx, rx) = channel();
+        run_test(&opts, false, desc(DynRetryableTestFn(Box::new(move || {
+            if runs.fetch_add(1, Ordering::SeqCst) < 2 {
+                panic!("not yet");
+            }
+        }))), tx);
+        let (_, res, _, _) = rx.recv().unwrap();
+        assert!(res == TrFlaky(3));
+    }
+
+    #[test]
+    fn parse_isolate_option() {
+        let args = vec![
+            "progname".to_string(),
+            "-Zunstable-options".to_string(),
+            "--isolate".to_string(),
+        ];
+        let opts = match parse_opts(&args) {
+            Some(Ok(o)) => o,
+            _ => panic!("Malformed arg in parse_isolate_option"),
+        };
+        assert!(opts.isolate);
+
+        match parse_opts(&["progname".to_string(), "--isolate".to_string()]) {
+            Some(Err(_)) => {}
+            _ => panic!("--isolate should require -Z unstable-options"),
+        }
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn isolated_test_results() {
+        use std::os::unix::process::ExitStatusExt;
+        use std::process::ExitStatus;
+        use super::{calc_isolated_result, ISOLATED_RESULT_MARKER};
+        use TrAllowedFail;
+
+        let mut desc = TestDesc {
+            name: StaticTestName("whatever"),
+            ignore: false,
+            should_panic: ShouldPanic::No,
+            allow_fail: false,
+        };
+        let exited = |code: i32| ExitStatus::from_raw(code << 8);
+        let output = |result: &str| {
+            format!("some output{}{}\n", ISOLATED_RESULT_MARKER, result).into_bytes()
+        };
+
+        let mut stdout = output("ok");
+        assert!(calc_isolated_result(&desc, exited(0), &mut stdout) == TrOk);
+        assert_eq!(stdout, b"some output");
+        assert!(calc_isolated_result(&desc, exited(0), &mut output("failed")) == TrFailed);
+        assert!(
+            calc_isolated_result(&desc, exited(0), &mut output("failed oops"))
+                == TrFailedMsg("oops".to_string())
+        );
+        // `process::exit(0)` from within the test
+        match calc_isolated_result(&desc, exited(0), &mut b"some output\n".to_vec()) {
+            TrFailedMsg(_) => {}
+            _ => panic!("abnormal exits should be reported as failures"),
+        }
+        // killed by SIGSEGV
+        match calc_isolated_result(&desc, ExitStatus::from_raw(11), &mut Vec::new()) {
+            TrFailedMsg(_) => {}
+            _ => panic!("abnormal exits should be reported as failures"),
+        }
+
+        desc.allow_fail = true;
+        let result = calc_isolated_result(&desc, ExitStatus::from_raw(11), &mut Vec::new());
+        assert!(result == TrAllowedFail);
+    }
+
     #[test]
     pub fn test_metricmap_compare() {
         let mut m1 = MetricMap::new();
@@ -0,0 +1,24 @@
+// Copyright 2018 The Rust Project Developers. See the COPYRIGHT
+// file at the top-level directory of this distribution and at
+// http://rust-lang.org/COPYRIGHT.
+//
+// Licensed under the Apache License, Version 2.0 <LICENSE-APACHE or
+// http://www.apache.org/licenses/LICENSE-2.0> or the MIT license
+// <LICENSE-MIT or http://opensource.org/licenses/MIT>, at your
+// option. This file may not be copied, modified, or distributed
+// except according to those terms.
+
+#![crate_type = "rlib"]
+
+pub mod a;
+
+pub fn add_one(x: u32) -> u32 {
+    assert!(x < u32::max_value(), "cannot add one to {}", x);
+    a::add(x, 1)
+}
+
+#[derive(Debug)]
+pub struct Point {
+    pub x: u32,
+    pub y: u32,
+}
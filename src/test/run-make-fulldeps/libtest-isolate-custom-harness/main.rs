@@ -0,0 +1,47 @@
+// Copyright 2018 The Rust Project Developers. See the COPYRIGHT
+// file at the top-level directory of this distribution and at
+// http://rust-lang.org/COPYRIGHT.
+//
+// Licensed under the Apache License, Version 2.0 <LICENSE-APACHE or
+// http://www.apache.org/licenses/LICENSE-2.0> or the MIT license
+// <LICENSE-MIT or http://opensource.org/licenses/MIT>, at your
+// option. This file may not be copied, modified, or distributed
+// except according to those terms.
+
+// A test harness with a command line of its own, which it checks before
+// handing the rest of it to libtest.
+
+#![feature(test)]
+
+extern crate test;
+
+use std::env;
+use std::process;
+use test::{ShouldPanic, StaticTestFn, StaticTestName, TestDesc, TestDescAndFn};
+
+fn test(name: &'static str, f: fn()) -> TestDescAndFn {
+    TestDescAndFn {
+        desc: TestDesc {
+            name: StaticTestName(name),
+            ignore: false,
+            should_panic: ShouldPanic::No,
+            allow_fail: false,
+        },
+        testfn: StaticTestFn(f),
+    }
+}
+
+fn main() {
+    let mut args: Vec<String> = env::args().collect();
+    if args.get(1).map(|s| &s[..]) != Some("--suite=custom") {
+        eprintln!("usage: main --suite=custom [test options]");
+        process::exit(2);
+    }
+    args.remove(1);
+
+    let tests = vec![
+        test("passes", || {}),
+        test("crashes", || process::abort()),
+    ];
+    test::test_main(&args, tests, test::Options::new());
+}
@@ -0,0 +1,21 @@
+// Copyright 2018 The Rust Project Developers. See the COPYRIGHT
+// file at the top-level directory of this distribution and at
+// http://rust-lang.org/COPYRIGHT.
+//
+// Licensed under the Apache License, Version 2.0 <LICENSE-APACHE or
+// http://www.apache.org/licenses/LICENSE-2.0> or the MIT license
+// <LICENSE-MIT or http://opensource.org/licenses/MIT>, at your
+// option. This file may not be copied, modified, or distributed
+// except according to those terms.
+
+#![feature(test_cases)]
+
+#[test_case("")]
+#[test_case("\"quoted\"")]
+#[test_case({
+    let s = "a block that the pretty printer breaks over several lines";
+    s
+})]
+fn names(s: &str) {
+    assert!(s.len() < 10);
+}
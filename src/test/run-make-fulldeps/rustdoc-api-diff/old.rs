@@ -0,0 +1,32 @@
+// Copyright 2018 The Rust Project Developers. See the COPYRIGHT
+// file at the top-level directory of this distribution and at
+// http://rust-lang.org/COPYRIGHT.
+//
+// Licensed under the Apache License, Version 2.0 <LICENSE-APACHE or
+// http://www.apache.org/licenses/LICENSE-2.0> or the MIT license
+// <LICENSE-MIT or http://opensource.org/licenses/MIT>, at your
+// option. This file may not be copied, modified, or distributed
+// except according to those terms.
+
+pub struct Open {
+    pub x: u32,
+}
+
+pub struct Closed {
+    pub x: u32,
+    y: u32,
+}
+
+pub enum E {
+    A,
+}
+
+pub trait T {
+    fn required(&self);
+}
+
+pub fn gone() {}
+
+pub fn widened(x: u32) -> u32 {
+    x
+}
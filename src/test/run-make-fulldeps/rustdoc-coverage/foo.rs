@@ -0,0 +1,33 @@
+// Copyright 2018 The Rust Project Developers. See the COPYRIGHT
+// file at the top-level directory of this distribution and at
+// http://rust-lang.org/COPYRIGHT.
+//
+// Licensed under the Apache License, Version 2.0 <LICENSE-APACHE or
+// http://www.apache.org/licenses/LICENSE-2.0> or the MIT license
+// <LICENSE-MIT or http://opensource.org/licenses/MIT>, at your
+// option. This file may not be copied, modified, or distributed
+// except according to those terms.
+
+//! The crate root.
+
+/// A documented module.
+pub mod bar {
+    /// A documented function with an example.
+    ///
+    /// ```
+    /// foo::bar::documented();
+    /// ```
+    pub fn documented() {}
+
+    pub fn undocumented() {}
+}
+
+pub struct Undocumented;
+
+impl Clone for Undocumented {
+    fn clone(&self) -> Undocumented {
+        Undocumented
+    }
+}
+
+fn private() {}
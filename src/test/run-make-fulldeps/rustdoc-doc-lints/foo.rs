@@ -0,0 +1,29 @@
+// Copyright 2018 The Rust Project Developers. See the COPYRIGHT
+// file at the top-level directory of this distribution and at
+// http://rust-lang.org/COPYRIGHT.
+//
+// Licensed under the Apache License, Version 2.0 <LICENSE-APACHE or
+// http://www.apache.org/licenses/LICENSE-2.0> or the MIT license
+// <LICENSE-MIT or http://opensource.org/licenses/MIT>, at your
+// option. This file may not be copied, modified, or distributed
+// except according to those terms.
+
+//! Links to [`Missing`], to [`Private`] and to https://example.com, unlike
+//! [`Public`] and <https://example.com>.
+//!
+//! ```
+//! let x = ;
+//! ```
+//!
+//! ```text
+//! let x = ;
+//! ```
+
+/// Nothing to see here.
+pub struct Public;
+
+struct Private;
+
+/// Links to [`Missing`] too, but is allowed to.
+#[allow(intra_doc_link_resolution_failure)]
+pub fn allowed() {}
@@ -0,0 +1,24 @@
+// Copyright 2018 The Rust Project Developers. See the COPYRIGHT
+// file at the top-level directory of this distribution and at
+// http://rust-lang.org/COPYRIGHT.
+//
+// Licensed under the Apache License, Version 2.0 <LICENSE-APACHE or
+// http://www.apache.org/licenses/LICENSE-2.0> or the MIT license
+// <LICENSE-MIT or http://opensource.org/licenses/MIT>, at your
+// option. This file may not be copied, modified, or distributed
+// except according to those terms.
+
+pub mod bar {
+    /// Some documentation.
+    pub struct Baz;
+
+    impl Clone for Baz {
+        fn clone(&self) -> Baz {
+            Baz
+        }
+    }
+}
+
+pub fn check(x: u32) -> Option<u32> {
+    Some(x)
+}
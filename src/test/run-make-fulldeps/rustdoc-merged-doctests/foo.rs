@@ -0,0 +1,30 @@
+// Copyright 2018 The Rust Project Developers. See the COPYRIGHT
+// file at the top-level directory of this distribution and at
+// http://rust-lang.org/COPYRIGHT.
+//
+// Licensed under the Apache License, Version 2.0 <LICENSE-APACHE or
+// http://www.apache.org/licenses/LICENSE-2.0> or the MIT license
+// <LICENSE-MIT or http://opensource.org/licenses/MIT>, at your
+// option. This file may not be copied, modified, or distributed
+// except according to those terms.
+
+/// ```
+/// assert_eq!(foo::passes(), 1);
+/// ```
+///
+/// ```
+/// let x = foo::passes();
+/// assert!(x > 0);
+/// ```
+pub fn passes() -> u32 { 1 }
+
+/// ```
+/// let x = foo::fails();
+/// panic!("merged failure {}", x);
+/// ```
+pub fn fails() -> u32 { 2 }
+
+/// ```
+/// let x: u32 = "not a number";
+/// ```
+pub fn broken() {}
@@ -0,0 +1,42 @@
+// Copyright 2018 The Rust Project Developers. See the COPYRIGHT
+// file at the top-level directory of this distribution and at
+// http://rust-lang.org/COPYRIGHT.
+//
+// Licensed under the Apache License, Version 2.0 <LICENSE-APACHE or
+// http://www.apache.org/licenses/LICENSE-2.0> or the MIT license
+// <LICENSE-MIT or http://opensource.org/licenses/MIT>, at your
+// option. This file may not be copied, modified, or distributed
+// except according to those terms.
+
+// ignore-wasm32-bare compiled with panic=abort by default
+// compile-flags: --test
+#![feature(test_cases)]
+
+#[test_case(1, 2, 3)]
+#[test_case(2, 2, 4)]
+#[test_case(-1, 1, 0)]
+fn add(a: i32, b: i32, sum: i32) {
+    assert_eq!(a + b, sum);
+}
+
+#[test_case(vec![1, 2, 3], 6)]
+#[test_case(Vec::new(), 0)]
+fn sum(v: Vec<u32>, sum: u32) {
+    assert_eq!(v.iter().sum::<u32>(), sum);
+}
+
+#[test_case("")]
+#[should_panic]
+fn parse_empty(s: &str) {
+    s.parse::<u8>().unwrap();
+}
+
+mod inner {
+    const TEN: u64 = 10;
+
+    #[test_case(TEN)]
+    #[test_case(TEN * 2)]
+    fn uses_module_items(n: u64) -> Result<(), String> {
+        if n % TEN == 0 { Ok(()) } else { Err(format!("{} is not a multiple of {}", n, TEN)) }
+    }
+}
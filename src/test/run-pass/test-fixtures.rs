@@ -0,0 +1,87 @@
+// Copyright 2018 The Rust Project Developers. See the COPYRIGHT
+// file at the top-level directory of this distribution and at
+// http://rust-lang.org/COPYRIGHT.
+//
+// Licensed under the Apache License, Version 2.0 <LICENSE-APACHE or
+// http://www.apache.org/licenses/LICENSE-2.0> or the MIT license
+// <LICENSE-MIT or http://opensource.org/licenses/MIT>, at your
+// option. This file may not be copied, modified, or distributed
+// except according to those terms.
+
+// ignore-wasm32-bare compiled with panic=abort by default
+// ignore-emscripten no threads, and the counts below are per test thread
+// compile-flags: --test
+#![feature(test_fixtures, test)]
+
+extern crate test;
+
+use std::cell::Cell;
+use test::TestFixture;
+
+// Every test runs on a thread of its own, so these count the hooks run for
+// the current test only.
+thread_local! {
+    static SETUPS: Cell<usize> = Cell::new(0);
+    static TEARDOWNS: Cell<usize> = Cell::new(0);
+    static INNER_SETUPS: Cell<usize> = Cell::new(0);
+}
+
+fn count(counter: &'static std::thread::LocalKey<Cell<usize>>) -> usize {
+    counter.with(|c| c.get())
+}
+
+fn bump(counter: &'static std::thread::LocalKey<Cell<usize>>) {
+    counter.with(|c| c.set(c.get() + 1));
+}
+
+#[test_setup]
+fn setup() {
+    bump(&SETUPS);
+}
+
+#[test_teardown]
+fn teardown() {
+    bump(&TEARDOWNS);
+    assert_eq!(count(&SETUPS), 1);
+    assert_eq!(count(&TEARDOWNS), 1);
+}
+
+struct Answer(u32);
+
+impl TestFixture for Answer {
+    fn setup() -> Answer {
+        Answer(42)
+    }
+}
+
+#[test]
+fn runs_setup_once() {
+    assert_eq!(count(&SETUPS), 1);
+    assert_eq!(count(&TEARDOWNS), 0);
+    assert_eq!(count(&INNER_SETUPS), 0);
+}
+
+#[test]
+fn takes_fixtures(a: &mut Answer, b: &mut Answer) {
+    assert_eq!(count(&SETUPS), 1);
+    assert_eq!(a.0, 42);
+    b.0 += 1;
+    assert_eq!(b.0, 43);
+}
+
+mod inner {
+    use super::{count, bump, SETUPS, TEARDOWNS, INNER_SETUPS};
+
+    #[test_setup]
+    fn setup() {
+        assert_eq!(count(&SETUPS), 1);
+        bump(&INNER_SETUPS);
+    }
+
+    #[test]
+    fn runs_both_setups_once() {
+        assert_eq!(count(&SETUPS), 1);
+        assert_eq!(count(&INNER_SETUPS), 1);
+        assert_eq!(count(&TEARDOWNS), 0);
+    }
+}
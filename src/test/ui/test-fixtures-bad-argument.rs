@@ -0,0 +1,20 @@
+// Copyright 2018 The Rust Project Developers. See the COPYRIGHT
+// file at the top-level directory of this distribution and at
+// http://rust-lang.org/COPYRIGHT.
+//
+// Licensed under the Apache License, Version 2.0 <LICENSE-APACHE or
+// http://www.apache.org/licenses/LICENSE-2.0> or the MIT license
+// <LICENSE-MIT or http://opensource.org/licenses/MIT>, at your
+// option. This file may not be copied, modified, or distributed
+// except according to those terms.
+
+// compile-flags: --test
+
+// check that every argument of a test is a fixture
+
+#![feature(test_fixtures)]
+
+#[test]
+fn takes_a_number(_a: &mut u32, _b: u32, _c: &u32) {}
+//~^ ERROR test fixtures must be taken as `&mut T`
+//~| ERROR test fixtures must be taken as `&mut T`
@@ -230,6 +230,9 @@ pub struct TestProps {
     pub run_pass: bool,
     // Do not pass `-Z ui-testing` to UI tests
     pub disable_ui_testing_normalization: bool,
+    // Apply the suggestions of the compiler, compare the result with the `.fixed` file and
+    // check that it compiles. Only used in UI tests.
+    pub run_rustfix: bool,
     // customized normalization rules
     pub normalize_stdout: Vec<(String, String)>,
     pub normalize_stderr: Vec<(String, String)>,
@@ -261,6 +264,7 @@ impl TestProps {
             check_test_line_numbers_match: false,
             run_pass: false,
             disable_ui_testing_normalization: false,
+            run_rustfix: false,
             normalize_stdout: vec![],
             normalize_stderr: vec![],
             failure_status: 101,
@@ -386,6 +390,10 @@ impl TestProps {
                     config.parse_disable_ui_testing_normalization(ln);
             }
 
+            if !self.run_rustfix {
+                self.run_rustfix = config.parse_run_rustfix(ln);
+            }
+
             if let Some(rule) = config.parse_custom_normalization(ln, "normalize-stdout") {
                 self.normalize_stdout.push(rule);
             }
@@ -524,6 +532,10 @@ impl Config {
         self.parse_name_directive(line, "run-pass")
     }
 
+    fn parse_run_rustfix(&self, line: &str) -> bool {
+        self.parse_name_directive(line, "run-rustfix")
+    }
+
     fn parse_env(&self, line: &str, name: &str) -> Option<(String, String)> {
         self.parse_name_value_directive(line, name).map(|nv| {
             // nv is either FOO or FOO=BAR
@@ -34,9 +34,12 @@ struct DiagnosticSpan {
     line_end: usize,
     column_start: usize,
     column_end: usize,
+    byte_start: usize,
+    byte_end: usize,
     is_primary: bool,
     label: Option<String>,
     suggested_replacement: Option<String>,
+    suggestion_approximate: Option<bool>,
     expansion: Option<Box<DiagnosticSpanMacroExpansion>>,
 }
 
@@ -76,6 +79,102 @@ pub fn extract_rendered(output: &str, proc_res: &ProcRes) -> String {
         .collect()
 }
 
+/// A replacement of a part of a file, as suggested by the compiler.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Replacement {
+    /// Byte offset of the start of the replaced text.
+    pub byte_start: usize,
+    /// Byte offset of the end of the replaced text.
+    pub byte_end: usize,
+    pub text: String,
+}
+
+impl Replacement {
+    fn overlaps(&self, other: &Replacement) -> bool {
+        // Two insertions at the same position can't both be applied either,
+        // as their order is unclear.
+        self.byte_start == other.byte_start ||
+            (self.byte_start < other.byte_end && other.byte_start < self.byte_end)
+    }
+}
+
+/// Extracts the suggestions for `file_name` from the JSON output of the
+/// compiler, except for the approximate ones. Each suggestion is a list of
+/// replacements that need to be applied together.
+pub fn get_suggestions(file_name: &str, output: &str, proc_res: &ProcRes) -> Vec<Vec<Replacement>> {
+    let mut suggestions = vec![];
+    for line in output.lines().filter(|line| line.starts_with('{')) {
+        match serde_json::from_str::<Diagnostic>(line) {
+            Ok(diagnostic) => push_suggestions(&mut suggestions, &diagnostic, file_name),
+            Err(error) => {
+                proc_res.fatal(Some(&format!("failed to decode compiler output as json: \
+                                              `{}`\noutput: {}\nline: {}",
+                                             error,
+                                             line,
+                                             output)));
+            }
+        }
+    }
+    suggestions
+}
+
+fn push_suggestions(suggestions: &mut Vec<Vec<Replacement>>,
+                    diagnostic: &Diagnostic,
+                    file_name: &str) {
+    let replacements: Vec<_> = diagnostic.spans
+        .iter()
+        .filter(|span| Path::new(&span.file_name) == Path::new(&file_name))
+        .filter(|span| span.suggestion_approximate != Some(true))
+        .filter_map(|span| {
+            span.suggested_replacement.as_ref().map(|text| Replacement {
+                byte_start: span.byte_start,
+                byte_end: span.byte_end,
+                text: text.clone(),
+            })
+        })
+        .collect();
+
+    // A suggestion offering several alternatives for the same code has
+    // overlapping replacements, and there is no way to tell which one to pick.
+    let is_ambiguous = replacements.iter().enumerate().any(|(i, r)| {
+        replacements[i + 1..].iter().any(|other| r.overlaps(other))
+    });
+    if !replacements.is_empty() && !is_ambiguous {
+        suggestions.push(replacements);
+    }
+
+    for child in &diagnostic.children {
+        push_suggestions(suggestions, child, file_name);
+    }
+}
+
+/// Applies `suggestions` to `code`, skipping every suggestion that conflicts
+/// with one applied before it.
+pub fn apply_suggestions(code: &str, suggestions: &[Vec<Replacement>]) -> String {
+    let mut applied: Vec<&Replacement> = vec![];
+    for suggestion in suggestions {
+        // The same suggestion is sometimes emitted more than once.
+        if suggestion.iter().all(|r| applied.contains(&r)) {
+            continue;
+        }
+        if suggestion.iter().any(|r| applied.iter().any(|a| a.overlaps(r))) {
+            continue;
+        }
+        applied.extend(suggestion);
+    }
+    applied.sort_by_key(|r| r.byte_start);
+
+    let mut fixed = String::with_capacity(code.len());
+    let mut pos = 0;
+    for r in applied {
+        fixed.push_str(&code[pos..r.byte_start]);
+        fixed.push_str(&r.text);
+        pos = r.byte_end;
+    }
+    fixed.push_str(&code[pos..]);
+    fixed
+}
+
 pub fn parse_output(file_name: &str, output: &str, proc_res: &ProcRes) -> Vec<Error> {
     output.lines()
         .flat_map(|line| parse_line(file_name, line, output, proc_res))
@@ -234,3 +333,23 @@ fn push_backtrace(expected_errors: &mut Vec<Error>,
         push_backtrace(expected_errors, previous_expansion, file_name);
     }
 }
+
+#[test]
+fn test_apply_suggestions() {
+    let replace = |byte_start, byte_end, text: &str| Replacement {
+        byte_start,
+        byte_end,
+        text: text.to_string(),
+    };
+    let code = "let mut a = (1);";
+
+    let suggestions = vec![
+        vec![replace(4, 8, "")],
+        // emitted twice
+        vec![replace(4, 8, "")],
+        vec![replace(12, 13, ""), replace(14, 15, "")],
+        // conflicts with the suggestion above
+        vec![replace(12, 15, "2")],
+    ];
+    assert_eq!(apply_suggestions(code, &suggestions), "let a = 1;");
+}
@@ -33,12 +33,14 @@ use std::fs;
 use std::io;
 use std::path::{Path, PathBuf};
 use std::process::Command;
+use std::sync::{Arc, Mutex};
 use filetime::FileTime;
 use getopts::Options;
 use common::{Config, TestPaths};
 use common::{DebugInfoGdb, DebugInfoLldb, Mode, Pretty};
 use common::{expected_output_path, UI_EXTENSIONS};
 use common::CompareMode;
+use results::ResultDb;
 use test::ColorConfig;
 use util::logv;
 
@@ -52,6 +54,7 @@ pub mod common;
 pub mod errors;
 mod raise_fd_limit;
 mod read2;
+mod results;
 
 fn main() {
     env_logger::init();
@@ -142,6 +145,13 @@ pub fn parse_config(args: Vec<String>) -> Config {
         )
         .optflag("", "ignored", "run tests marked as ignored")
         .optflag("", "exact", "filters match exactly")
+        .optflag("", "rerun-failed", "only run the tests that failed in the last run")
+        .optflag("", "slowest-first", "run the tests that were slowest in the last run first")
+        .optflag(
+            "",
+            "diff-last-run",
+            "print the tests that started failing or passing since the last run",
+        )
         .optopt(
             "",
             "runtool",
@@ -328,6 +338,9 @@ pub fn parse_config(args: Vec<String>) -> Config {
         color,
         remote_test_client: matches.opt_str("remote-test-client").map(PathBuf::from),
         compare_mode: matches.opt_str("compare-mode").map(CompareMode::parse),
+        rerun_failed: matches.opt_present("rerun-failed"),
+        slowest_first: matches.opt_present("slowest-first"),
+        diff_last_run: matches.opt_present("diff-last-run"),
 
         cc: matches.opt_str("cc").unwrap(),
         cxx: matches.opt_str("cxx").unwrap(),
@@ -363,6 +376,9 @@ pub fn log_config(config: &Config) {
         ),
     );
     logv(c, format!("filter_exact: {}", config.filter_exact));
+    logv(c, format!("rerun_failed: {}", config.rerun_failed));
+    logv(c, format!("slowest_first: {}", config.slowest_first));
+    logv(c, format!("diff_last_run: {}", config.diff_last_run));
     logv(c, format!("runtool: {}", opt_str(&config.runtool)));
     logv(
         c,
@@ -465,8 +481,24 @@ pub fn run_tests(config: &Config) {
         let _ = fs::remove_dir_all("tmp/partitioning-tests");
     }
 
-    let opts = test_opts(config);
-    let tests = make_tests(config);
+    let mut opts = test_opts(config);
+    let mut tests = make_tests(config);
+
+    let db_path = ResultDb::path(config);
+    let mut last_run = ResultDb::load(&db_path);
+    if config.rerun_failed {
+        tests.retain(|t| last_run.failed(&t.desc.name.to_string()));
+    }
+    if config.slowest_first {
+        last_run.sort_slowest_first(&mut tests);
+        opts.options = opts.options.preserve_order(true);
+    }
+    let this_run = Arc::new(Mutex::new(ResultDb::new()));
+    let tests = tests
+        .into_iter()
+        .map(|t| results::record(t, &this_run))
+        .collect();
+
     // sadly osx needs some file descriptor limits raised for running tests in
     // parallel (especially when we have lots and lots of child processes).
     // For context, see #8904
@@ -480,7 +512,19 @@ pub fn run_tests(config: &Config) {
     // Let tests know which target they're running as
     env::set_var("TARGET", &config.target);
 
-    let res = test::run_tests_console(&opts, tests.into_iter().collect());
+    let res = test::run_tests_console(&opts, tests);
+
+    {
+        let this_run = this_run.lock().unwrap();
+        if config.diff_last_run {
+            last_run.print_diff(&this_run);
+        }
+        last_run.merge(&this_run);
+    }
+    if let Err(e) = last_run.save(&db_path) {
+        println!("failed to save test results to {}: {}", db_path.display(), e);
+    }
+
     match res {
         Ok(true) => {}
         Ok(false) => panic!("Some tests failed"),
@@ -506,6 +550,14 @@ pub fn test_opts(config: &Config) -> test::TestOpts {
         color: config.color,
         test_threads: None,
         skip: vec![],
+        shard: None,
+        shuffle: false,
+        shuffle_seed: None,
+        save_baseline: None,
+        baseline: None,
+        time_options: test::TestTimeOptions::default(),
+        isolate: false,
+        retries: 0,
         list: false,
         options: test::Options::new(),
     }
@@ -732,7 +784,7 @@ pub fn make_test_name(config: &Config, testpaths: &TestPaths) -> test::TestName
 pub fn make_test_closure(config: &Config, testpaths: &TestPaths) -> test::TestFn {
     let config = config.clone();
     let testpaths = testpaths.clone();
-    test::DynTestFn(Box::new(move || runtest::run(config, &testpaths)))
+    test::DynRetryableTestFn(Box::new(move || runtest::run(config.clone(), &testpaths)))
 }
 
 /// Returns (Path to GDB, GDB Version, GDB has Rust Support)
@@ -0,0 +1,322 @@
+// Copyright 2018 The Rust Project Developers. See the COPYRIGHT
+// file at the top-level directory of this distribution and at
+// http://rust-lang.org/COPYRIGHT.
+//
+// Licensed under the Apache License, Version 2.0 <LICENSE-APACHE or
+// http://www.apache.org/licenses/LICENSE-2.0> or the MIT license
+// <LICENSE-MIT or http://opensource.org/licenses/MIT>, at your
+// option. This file may not be copied, modified, or distributed
+// except according to those terms.
+
+//! A small database of the outcome and duration of every test of a suite,
+//! kept in the build directory so that later runs can re-run only the tests
+//! that failed, run the slowest tests first, or report what changed.
+//!
+//! The database is a text file with one line per test: `ok` or `failed`, a
+//! tab, the duration of the test in milliseconds, a tab, and the test name.
+
+use std::cmp::Ordering;
+use std::collections::BTreeMap;
+use std::fs::{self, File};
+use std::io::prelude::*;
+use std::io::{self, BufReader, BufWriter};
+use std::panic::{self, AssertUnwindSafe};
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+
+use common::Config;
+use test::{self, ShouldPanic, TestDescAndFn};
+
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub struct TestRecord {
+    pub passed: bool,
+    pub duration: Duration,
+}
+
+/// The recorded results of a test suite, by test name.
+pub struct ResultDb {
+    records: BTreeMap<String, TestRecord>,
+}
+
+impl ResultDb {
+    pub fn new() -> ResultDb {
+        ResultDb {
+            records: BTreeMap::new(),
+        }
+    }
+
+    /// The path of the database of the suite `config` runs.
+    pub fn path(config: &Config) -> PathBuf {
+        let mut name = format!("results-{}", config.stage_id);
+        if let Some(ref mode) = config.compare_mode {
+            name.push_str(&format!("-{}", mode.to_str()));
+        }
+        config.build_base.join(name + ".tsv")
+    }
+
+    /// Loads the database at `path`. A missing database is empty, and
+    /// malformed lines are skipped so that a corrupt database never keeps the
+    /// tests from running.
+    pub fn load(path: &Path) -> ResultDb {
+        let mut db = ResultDb::new();
+        let file = match File::open(path) {
+            Ok(file) => file,
+            Err(_) => return db,
+        };
+
+        for line in BufReader::new(file).lines() {
+            let line = match line {
+                Ok(line) => line,
+                Err(_) => break,
+            };
+            let mut parts = line.splitn(3, '\t');
+            let passed = match parts.next() {
+                Some("ok") => true,
+                Some("failed") => false,
+                _ => continue,
+            };
+            let millis = match parts.next().and_then(|s| s.parse::<u64>().ok()) {
+                Some(millis) => millis,
+                None => continue,
+            };
+            if let Some(name) = parts.next() {
+                db.insert(name, passed, Duration::from_millis(millis));
+            }
+        }
+        db
+    }
+
+    pub fn save(&self, path: &Path) -> io::Result<()> {
+        if let Some(dir) = path.parent() {
+            fs::create_dir_all(dir)?;
+        }
+
+        let mut out = BufWriter::new(File::create(path)?);
+        for (name, record) in &self.records {
+            let millis = record.duration.as_secs() * 1000
+                + u64::from(record.duration.subsec_nanos() / 1_000_000);
+            let outcome = if record.passed { "ok" } else { "failed" };
+            writeln!(out, "{}\t{}\t{}", outcome, millis, name)?;
+        }
+        out.flush()
+    }
+
+    pub fn insert(&mut self, name: &str, passed: bool, duration: Duration) {
+        self.records
+            .insert(name.to_owned(), TestRecord { passed, duration });
+    }
+
+    pub fn get(&self, name: &str) -> Option<&TestRecord> {
+        self.records.get(name)
+    }
+
+    pub fn failed(&self, name: &str) -> bool {
+        self.get(name).map_or(false, |r| !r.passed)
+    }
+
+    /// Adds the results of `newer` to this database, replacing the older
+    /// record of any test that was run again.
+    pub fn merge(&mut self, newer: &ResultDb) {
+        for (name, record) in &newer.records {
+            self.records.insert(name.clone(), *record);
+        }
+    }
+
+    /// Orders the tests so that the ones that took longest last time run
+    /// first, which keeps a long test from being started at the very end of
+    /// a parallel run. Tests with no record are assumed to be slow.
+    pub fn sort_slowest_first(&self, tests: &mut Vec<TestDescAndFn>) {
+        tests.sort_by(|a, b| {
+            let a = self.get(&a.desc.name.to_string()).map(|r| r.duration);
+            let b = self.get(&b.desc.name.to_string()).map(|r| r.duration);
+            match (a, b) {
+                (Some(a), Some(b)) => b.cmp(&a),
+                (None, Some(_)) => Ordering::Less,
+                (Some(_), None) => Ordering::Greater,
+                (None, None) => Ordering::Equal,
+            }
+        });
+    }
+
+    /// Returns the tests that fail in `current` but did not fail in this
+    /// database, and the tests that used to fail but now pass.
+    fn diff<'a>(&self, current: &'a ResultDb) -> (Vec<&'a str>, Vec<&'a str>) {
+        let mut newly_failing = Vec::new();
+        let mut newly_passing = Vec::new();
+        for (name, record) in &current.records {
+            match self.get(name) {
+                Some(old) if old.passed == record.passed => {}
+                None if record.passed => {}
+                _ if record.passed => newly_passing.push(&name[..]),
+                _ => newly_failing.push(&name[..]),
+            }
+        }
+        (newly_failing, newly_passing)
+    }
+
+    /// Prints the tests whose outcome in `current` differs from this
+    /// database.
+    pub fn print_diff(&self, current: &ResultDb) {
+        let (newly_failing, newly_passing) = self.diff(current);
+
+        println!();
+        if newly_failing.is_empty() && newly_passing.is_empty() {
+            println!("no changes since the last run");
+            return;
+        }
+        if !newly_failing.is_empty() {
+            println!("newly failing since the last run:");
+            for name in &newly_failing {
+                println!("    {}", name);
+            }
+        }
+        if !newly_passing.is_empty() {
+            println!("newly passing since the last run:");
+            for name in &newly_passing {
+                println!("    {}", name);
+            }
+        }
+    }
+}
+
+/// Wraps the test so that its outcome and duration end up in `db` once it
+/// has run.
+pub fn record(test: TestDescAndFn, db: &Arc<Mutex<ResultDb>>) -> TestDescAndFn {
+    let TestDescAndFn { desc, testfn } = test;
+    let name = desc.name.to_string();
+    let should_panic = desc.should_panic != ShouldPanic::No;
+    let db = db.clone();
+    let testfn = match testfn {
+        test::DynTestFn(f) => {
+            test::DynTestFn(Box::new(move || run_and_record(&name, should_panic, &db, f)))
+        }
+        test::DynRetryableTestFn(mut f) => {
+            // A test which is retried is recorded with the outcome of its
+            // last attempt.
+            test::DynRetryableTestFn(Box::new(move || {
+                run_and_record(&name, should_panic, &db, &mut *f)
+            }))
+        }
+        testfn => testfn,
+    };
+    TestDescAndFn { desc, testfn }
+}
+
+fn run_and_record<F: FnOnce()>(name: &str, should_panic: bool, db: &Mutex<ResultDb>, f: F) {
+    let start = Instant::now();
+    let result = panic::catch_unwind(AssertUnwindSafe(f));
+    let passed = result.is_ok() != should_panic;
+    db.lock().unwrap().insert(name, passed, start.elapsed());
+    if let Err(e) = result {
+        panic::resume_unwind(e);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::env;
+    use std::process;
+    use test::{DynTestName, TestDesc};
+
+    fn temp_path(name: &str) -> PathBuf {
+        env::temp_dir()
+            .join(format!("compiletest-results-{}", process::id()))
+            .join(name)
+    }
+
+    fn named_test(name: &str) -> TestDescAndFn {
+        TestDescAndFn {
+            desc: TestDesc {
+                name: DynTestName(name.to_owned()),
+                ignore: false,
+                should_panic: ShouldPanic::No,
+                allow_fail: false,
+            },
+            testfn: test::DynTestFn(Box::new(|| {})),
+        }
+    }
+
+    #[test]
+    fn save_then_load() {
+        let path = temp_path("save_then_load.tsv");
+        let mut db = ResultDb::new();
+        db.insert("[ui] ui/a.rs", true, Duration::from_millis(1500));
+        db.insert("[ui] ui/b.rs", false, Duration::from_millis(20));
+        db.save(&path).unwrap();
+
+        let loaded = ResultDb::load(&path);
+        assert_eq!(loaded.get("[ui] ui/a.rs"),
+                   Some(&TestRecord { passed: true, duration: Duration::from_millis(1500) }));
+        assert_eq!(loaded.get("[ui] ui/b.rs"),
+                   Some(&TestRecord { passed: false, duration: Duration::from_millis(20) }));
+        assert!(loaded.failed("[ui] ui/b.rs"));
+        assert!(!loaded.failed("[ui] ui/a.rs"));
+        assert_eq!(loaded.records.len(), 2);
+
+        fs::remove_file(&path).unwrap();
+    }
+
+    #[test]
+    fn load_skips_malformed_lines() {
+        let path = temp_path("load_skips_malformed_lines.tsv");
+        fs::create_dir_all(path.parent().unwrap()).unwrap();
+        fs::write(&path, "ok\t10\tgood\n\
+                          maybe\t10\tbad outcome\n\
+                          ok\tfast\tbad duration\n\
+                          failed\t5\n\
+                          failed\t5\tname\twith\ttabs\n").unwrap();
+
+        let db = ResultDb::load(&path);
+        assert_eq!(db.records.keys().collect::<Vec<_>>(), ["good", "name\twith\ttabs"]);
+        assert!(db.failed("name\twith\ttabs"));
+
+        fs::remove_file(&path).unwrap();
+    }
+
+    #[test]
+    fn load_missing_database_is_empty() {
+        let db = ResultDb::load(&temp_path("does-not-exist.tsv"));
+        assert!(db.records.is_empty());
+    }
+
+    #[test]
+    fn diff_reports_changed_outcomes() {
+        let mut last = ResultDb::new();
+        last.insert("still passing", true, Duration::from_millis(1));
+        last.insert("still failing", false, Duration::from_millis(1));
+        last.insert("now failing", true, Duration::from_millis(1));
+        last.insert("now passing", false, Duration::from_millis(1));
+
+        let mut current = ResultDb::new();
+        current.insert("still passing", true, Duration::from_millis(1));
+        current.insert("still failing", false, Duration::from_millis(1));
+        current.insert("now failing", false, Duration::from_millis(1));
+        current.insert("now passing", true, Duration::from_millis(1));
+        current.insert("new and passing", true, Duration::from_millis(1));
+        current.insert("new and failing", false, Duration::from_millis(1));
+
+        let (newly_failing, newly_passing) = last.diff(&current);
+        assert_eq!(newly_failing, ["new and failing", "now failing"]);
+        assert_eq!(newly_passing, ["now passing"]);
+    }
+
+    #[test]
+    fn sort_slowest_first_puts_unknown_tests_first() {
+        let mut db = ResultDb::new();
+        db.insert("fast", true, Duration::from_millis(10));
+        db.insert("slow", false, Duration::from_millis(3000));
+        db.insert("medium", true, Duration::from_millis(200));
+
+        let mut tests = vec![
+            named_test("fast"),
+            named_test("medium"),
+            named_test("new"),
+            named_test("slow"),
+        ];
+        db.sort_slowest_first(&mut tests);
+        let names: Vec<_> = tests.iter().map(|t| t.desc.name.to_string()).collect();
+        assert_eq!(names, ["new", "slow", "medium", "fast"]);
+    }
+}
@@ -12,7 +12,7 @@ use common::{Config, TestPaths};
 use common::{CompileFail, ParseFail, Pretty, RunFail, RunPass, RunPassValgrind};
 use common::{Codegen, CodegenUnits, DebugInfoGdb, DebugInfoLldb, Rustdoc};
 use common::{Incremental, MirOpt, RunMake, Ui};
-use common::{expected_output_path, UI_STDERR, UI_STDOUT};
+use common::{expected_output_path, UI_FIXED, UI_STDERR, UI_STDOUT};
 use common::CompareMode;
 use diff;
 use errors::{self, Error, ErrorKind};
@@ -1634,6 +1634,11 @@ impl<'test> TestCx<'test> {
                 if !self.props.compile_flags.iter().any(|s| s.starts_with("--error-format")) {
                     rustc.args(&["--error-format", "json"]);
                 }
+                if self.props.run_rustfix {
+                    // mark approximate suggestions in the JSON output, so
+                    // that they are not applied
+                    rustc.arg("-Zapproximate-suggestions");
+                }
                 if !self.props.disable_ui_testing_normalization {
                     rustc.arg("-Zui-testing");
                 }
@@ -1840,6 +1845,13 @@ impl<'test> TestCx<'test> {
         f.with_file_name(&fname)
     }
 
+    fn fixed_output_dir_name(&self) -> PathBuf {
+        let f = self.output_base_name();
+        let mut fname = f.file_name().unwrap().to_os_string();
+        fname.push(&format!("{}.fixed", self.config.mode.disambiguator()));
+        f.with_file_name(&fname)
+    }
+
     fn output_testname(&self, filepath: &Path) -> PathBuf {
         PathBuf::from(filepath.file_stem().unwrap())
     }
@@ -2531,6 +2543,19 @@ impl<'test> TestCx<'test> {
         errors += self.compare_output("stdout", &normalized_stdout, &expected_stdout);
         errors += self.compare_output("stderr", &normalized_stderr, &expected_stderr);
 
+        let run_rustfix = self.props.run_rustfix && !explicit &&
+            self.config.compare_mode.is_none();
+        if run_rustfix {
+            let unfixed_code = self.load_expected_output_from_path(&self.testpaths.file)
+                .unwrap_or_else(|e| self.fatal(&e));
+            // on windows, translate all '\' path separators to '/'
+            let file_name = format!("{}", self.testpaths.file.display()).replace(r"\", "/");
+            let suggestions = json::get_suggestions(&file_name, &proc_res.stderr, &proc_res);
+            let fixed_code = json::apply_suggestions(&unfixed_code, &suggestions);
+            let expected_fixed = self.load_expected_output(UI_FIXED);
+            errors += self.compare_output(UI_FIXED, &fixed_code, &expected_fixed);
+        }
+
         if errors > 0 {
             println!("To update references, run this command from build directory:");
             let relative_path_to_file = self.testpaths
@@ -2548,6 +2573,24 @@ impl<'test> TestCx<'test> {
             );
         }
 
+        if run_rustfix {
+            // The `.fixed` file matches the fixed code at this point, so
+            // make sure that it compiles. It is compiled into a directory of
+            // its own, so that a run-pass test still runs the original code.
+            let fixed_file = expected_output_path(&self.testpaths, self.revision, &None, UI_FIXED);
+            let fixed_output_dir = self.fixed_output_dir_name();
+            create_dir_all(&fixed_output_dir).unwrap();
+            let mut rustc = self.make_compile_args(
+                &fixed_file,
+                TargetLocation::ThisDirectory(fixed_output_dir),
+            );
+            rustc.arg("-L").arg(&self.aux_output_dir_name());
+            let res = self.compose_and_run_compiler(rustc, None);
+            if !res.status.success() {
+                self.fatal_proc_rec("failed to compile fixed code", &res);
+            }
+        }
+
         let expected_errors = errors::load_errors(&self.testpaths.file, self.revision);
 
         if self.props.run_pass {